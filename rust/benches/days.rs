@@ -0,0 +1,68 @@
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+
+use advent_of_code::days::*;
+use advent_of_code::io::Input;
+use advent_of_code::solution::Solution;
+
+// Bench the parse and each solved part of a day against its input in the
+// data directory, parts are given a freshly parsed input on each iteration
+fn bench_day<S>(c : &mut Criterion, solution : S)
+  where S : Solution
+{
+    let day = solution.day();
+    let input = Input::day(day);
+    if let Err(err) = input.contents() {
+        eprintln!("Skipping day {}: {}", day, err);
+        return;
+    }
+    let mut group = c.benchmark_group(format!("day-{}", day));
+    // Some days take seconds a run, keep to the fewest samples criterion allows
+    group.sample_size(10);
+    group.bench_function("parse", |b| b.iter(
+        || solution.parse(&input).unwrap()
+    ));
+    group.bench_function("part-1", |b| b.iter_batched(
+        || solution.parse(&input).unwrap(),
+        |data| solution.part_one(data),
+        BatchSize::LargeInput
+    ));
+    if solution.has_part_two() {
+        group.bench_function("part-2", |b| b.iter_batched(
+            || solution.parse(&input).unwrap(),
+            |data| solution.part_two(data),
+            BatchSize::LargeInput
+        ));
+    }
+    group.finish();
+}
+
+fn days(c : &mut Criterion) {
+    bench_day(c, Day1);
+    bench_day(c, Day2);
+    bench_day(c, Day3);
+    bench_day(c, Day4);
+    bench_day(c, Day5);
+    bench_day(c, Day6);
+    bench_day(c, Day7);
+    bench_day(c, Day8);
+    bench_day(c, Day9);
+    bench_day(c, Day10);
+    bench_day(c, Day11);
+    bench_day(c, Day12);
+    bench_day(c, Day13);
+    bench_day(c, Day14);
+    bench_day(c, Day15);
+    bench_day(c, Day16);
+    bench_day(c, Day17);
+    bench_day(c, Day18);
+    bench_day(c, Day19);
+    bench_day(c, Day20);
+    bench_day(c, Day21);
+    bench_day(c, Day22);
+    bench_day(c, Day23);
+    bench_day(c, Day24);
+    bench_day(c, Day25);
+}
+
+criterion_group!(benches, days);
+criterion_main!(benches);
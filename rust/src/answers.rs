@@ -0,0 +1,135 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::{Error, Result};
+use crate::io::data_dir;
+use crate::solution::{Answer, Part};
+
+pub const ANSWERS_FILE : &str = "answers.toml";
+
+// Recorded answers for the inputs, kept as a table per day
+// [1]
+// part1 = 69626
+#[derive(Default)]
+pub struct Answers {
+    answers : BTreeMap<(u8, Part), Answer>
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Verdict {
+    Pass,
+    Fail(Answer),
+    Missing
+}
+
+pub fn answers_path(dir : Option<&Path>) -> PathBuf {
+    dir.map(|dir| dir.to_path_buf()).unwrap_or_else(data_dir).join(ANSWERS_FILE)
+}
+
+fn part_key(part : Part) -> String {
+    format!("part{}", part.num())
+}
+
+fn answer_from_value(value : &toml::Value) -> Option<Answer> {
+    match value {
+        toml::Value::Integer(num) => Some(Answer::Num(*num)),
+        toml::Value::String(text) => Some(Answer::Text(text.clone())),
+        _ => None
+    }
+}
+
+fn answer_to_value(answer : &Answer) -> toml::Value {
+    match answer {
+        Answer::Num(num) => toml::Value::Integer(*num),
+        Answer::Text(text) => toml::Value::String(text.clone())
+    }
+}
+
+impl Answers {
+    pub fn new() -> Answers {
+        Answers { answers : BTreeMap::new() }
+    }
+
+    pub fn from_string(string : &str) -> Result<Answers> {
+        let table = string.parse::<toml::Table>().map_err(
+            |err| Error::invalid(&format!("answers are not valid toml: {}", err))
+        )?;
+        let mut answers = Answers::new();
+        for (day_s, parts) in table.iter() {
+            let day = day_s.parse::<u8>().map_err(
+                |_| Error::invalid(&format!("answers for {:?}, which is not a day", day_s))
+            )?;
+            let parts = parts.as_table().ok_or(
+                Error::invalid(&format!("answers for day {} are not a table", day))
+            )?;
+            for part in Part::all() {
+                if let Some(value) = parts.get(&part_key(part)) {
+                    let answer = answer_from_value(value).ok_or(
+                        Error::invalid(&format!("answer for day {} part {} is not a number or text", day, part))
+                    )?;
+                    answers.record(day, part, answer);
+                }
+            }
+        }
+        Ok(answers)
+    }
+
+    // A missing file has no answers recorded yet
+    pub fn load(path : &Path) -> Result<Answers> {
+        if !path.exists() {
+            return Ok(Answers::new());
+        }
+        let string = fs::read_to_string(path).map_err(
+            |err| Error::file(path, err)
+        )?;
+        Answers::from_string(&string)
+    }
+
+    pub fn save(&self, path : &Path) -> Result<()> {
+        fs::write(path, self.to_string()).map_err(
+            |err| Error::file(path, err)
+        )
+    }
+
+    pub fn get(&self, day : u8, part : Part) -> Option<&Answer> {
+        self.answers.get(&(day, part))
+    }
+
+    pub fn record(&mut self, day : u8, part : Part, answer : Answer) {
+        self.answers.insert((day, part), answer);
+    }
+
+    pub fn check(&self, day : u8, part : Part, answer : &Answer) -> Verdict {
+        match self.get(day, part) {
+            Some(expected) if expected == answer => Verdict::Pass,
+            Some(expected) => Verdict::Fail(expected.clone()),
+            None => Verdict::Missing
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.answers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.answers.is_empty()
+    }
+}
+
+impl std::fmt::Display for Answers {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let mut last_day = None;
+        for ((day, part), answer) in self.answers.iter() {
+            if last_day != Some(*day) {
+                if last_day.is_some() {
+                    writeln!(f)?;
+                }
+                writeln!(f, "[{}]", day)?;
+                last_day = Some(*day);
+            }
+            writeln!(f, "{} = {}", part_key(*part), answer_to_value(answer))?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,253 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::sync::OnceLock;
+
+use crate::error::{Error, Result};
+use crate::geometry::{Direction, Point2};
+use crate::grid::{Bounds, Grid};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Tile {
+    Wall,
+    Open,
+    Blizzard(Direction)
+}
+
+// The valley with its walls, the blizzards repeat every period minutes so
+// the clear tiles are worked out once for each minute of the period. They
+// and the period follow from the size and the blizzards, so are worked out
+// when first needed and are neither written nor compared
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Valley {
+    start : Point2,
+    end : Point2,
+    width : usize,
+    height : usize,
+    blizzards : Vec<(Point2, Direction)>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    clear : OnceLock<Vec<Grid<bool>>>
+}
+
+fn tile(ch : char) -> Option<Tile> {
+    match ch {
+        '#' => Some(Tile::Wall),
+        '.' => Some(Tile::Open),
+        '^' => Some(Tile::Blizzard(Direction::North)),
+        '>' => Some(Tile::Blizzard(Direction::East)),
+        'v' => Some(Tile::Blizzard(Direction::South)),
+        '<' => Some(Tile::Blizzard(Direction::West)),
+        _ => None
+    }
+}
+
+// The single gap in a wall row
+fn gap(tiles : &Grid<Tile>, y : i64) -> Result<Point2> {
+    let gaps : Vec<Point2> = tiles.row(y).filter(
+        |pos| tiles[*pos] != Tile::Wall
+    ).collect();
+    match gaps[..] {
+        [pos] if tiles[pos] == Tile::Open => Ok(pos),
+        _ => Err(Error::invalid(&format!("row {} should be wall with a single gap", y + 1)))
+    }
+}
+
+impl Valley {
+    pub fn from_lines(lines : &Vec<String>) -> Result<Valley> {
+        let tiles = Grid::parse(lines, tile, "a wall #, ground . or a blizzard ^, >, v or <")?;
+        if tiles.width() < 3 || tiles.height() < 3 {
+            return Err(Error::invalid("the valley is smaller than 3 by 3"));
+        }
+        let bounds = tiles.bounds();
+        let start = gap(&tiles, bounds.min.y)?;
+        let end = gap(&tiles, bounds.max.y)?;
+        let side_walls = tiles.column(bounds.min.x).chain(tiles.column(bounds.max.x)).all(
+            |pos| tiles[pos] == Tile::Wall
+        );
+        if !side_walls {
+            return Err(Error::invalid("the valley is not walled in at the sides"));
+        }
+        let inner : Vec<Point2> = bounds.positions().filter(
+            |pos| pos.x != bounds.min.x && pos.x != bounds.max.x && pos.y != bounds.min.y && pos.y != bounds.max.y
+        ).collect();
+        if inner.iter().any(|pos| tiles[*pos] == Tile::Wall) {
+            return Err(Error::invalid("a wall inside the valley"));
+        }
+        let blizzards : Vec<(Point2, Direction)> = inner.into_iter().filter_map(
+            |pos| match tiles[pos] {
+                Tile::Blizzard(dir) => Some((pos, dir)),
+                _ => None
+            }
+        ).collect();
+
+        Ok(Valley {
+            start : start,
+            end : end,
+            width : tiles.width(),
+            height : tiles.height(),
+            blizzards : blizzards,
+            clear : OnceLock::new()
+        })
+    }
+
+    // Width and height inside the walls, at least one even for a valley
+    // loaded with a bad size
+    fn inside(&self) -> (usize, usize) {
+        (self.width.saturating_sub(2).max(1), self.height.saturating_sub(2).max(1))
+    }
+
+    fn period(&self) -> usize {
+        let (width, height) = self.inside();
+        num::integer::lcm(width, height)
+    }
+
+    fn clear(&self) -> &Vec<Grid<bool>> {
+        self.clear.get_or_init(|| {
+            let (width, height) = self.inside();
+            let mut ground = Grid::new(width + 2, height + 2, false);
+            let (width, height) = (width as i64, height as i64);
+            for pos in ground.bounds().positions() {
+                ground[pos] = pos == self.start || pos == self.end ||
+                    (1..=width).contains(&pos.x) && (1..=height).contains(&pos.y);
+            }
+            (0..self.period()).map(|time| {
+                let mut clear = ground.clone();
+                for (pos, dir) in self.blizzards.iter() {
+                    let moved = *pos - Point2::new(1, 1) + dir.step() * time as i64;
+                    let wrapped = Point2::new(
+                        moved.x.rem_euclid(width) + 1,
+                        moved.y.rem_euclid(height) + 1
+                    );
+                    clear[wrapped] = false;
+                }
+                clear
+            }).collect()
+        })
+    }
+
+    // Minutes to cross from the start to the end
+    pub fn crossing(&self) -> Option<usize> {
+        self.travel(self.start, self.end, 0)
+    }
+
+    // Minutes to cross, go back for the snacks and cross again
+    pub fn round_trip(&self) -> Option<usize> {
+        let there = self.travel(self.start, self.end, 0)?;
+        let back = self.travel(self.end, self.start, there)?;
+        self.travel(self.start, self.end, back)
+    }
+
+    // Breadth first search over position and time, the blizzards repeat so
+    // positions are only revisited at a different point in the period.
+    // Returns the time of arrival
+    fn travel(&self, from : Point2, to : Point2, start : usize) -> Option<usize> {
+        let period = self.period();
+        let mut seen = HashSet::new();
+        let mut queue = VecDeque::from(vec!((from, start)));
+        while let Some((pos, time)) = queue.pop_front() {
+            if pos == to {
+                return Some(time);
+            }
+            let next = time + 1;
+            let clear = &self.clear()[next % period];
+            for step in pos.neighbours_4().chain([pos]) {
+                if clear.get(step).copied().unwrap_or(false) && seen.insert((step, next % period)) {
+                    queue.push_back((step, next));
+                }
+            }
+        }
+        None
+    }
+}
+
+impl PartialEq for Valley {
+    fn eq(&self, other : &Valley) -> bool {
+        (self.start, self.end, self.width, self.height, &self.blizzards) ==
+            (other.start, other.end, other.width, other.height, &other.blizzards)
+    }
+}
+
+impl Eq for Valley {}
+
+// The valley as it is at the start, before the blizzards move
+impl fmt::Display for Valley {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let blizzards : HashMap<Point2, Direction> = self.blizzards.iter().copied().collect();
+        let bounds = Bounds::new(Point2::ORIGIN, Point2::new(self.width as i64 - 1, self.height as i64 - 1));
+        let rows : Vec<String> = (bounds.min.y..=bounds.max.y).map(
+            |y| bounds.row(y).map(
+                |pos| match blizzards.get(&pos) {
+                    Some(Direction::North) => '^',
+                    Some(Direction::East) => '>',
+                    Some(Direction::South) => 'v',
+                    Some(Direction::West) => '<',
+                    None if pos == self.start || pos == self.end => '.',
+                    None if pos.x == bounds.min.x || pos.x == bounds.max.x => '#',
+                    None if pos.y == bounds.min.y || pos.y == bounds.max.y => '#',
+                    None => '.'
+                }
+            ).collect()
+        ).collect();
+        write!(f, "{}", rows.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::days;
+    use crate::io::{self, Input};
+    use crate::solution::Part;
+
+    const EXAMPLE : &str = include_str!("../../data/examples/day-24.txt");
+
+    #[test]
+    fn crossing() {
+        let valley = io::input_as_valley(&Input::text(EXAMPLE)).unwrap();
+        assert_eq!(valley.crossing(), Some(18));
+    }
+
+    #[test]
+    fn round_trip() {
+        let valley = io::input_as_valley(&Input::text(EXAMPLE)).unwrap();
+        assert_eq!(valley.round_trip(), Some(54));
+    }
+
+    #[test]
+    fn blocked() {
+        let lines : Vec<String> = vec!("#.###", "#...#", "#####").into_iter().map(String::from).collect();
+        assert!(Valley::from_lines(&lines).is_err());
+        let lines : Vec<String> = vec!("#.#", "#v#", "#.#").into_iter().map(String::from).collect();
+        assert_eq!(Valley::from_lines(&lines).unwrap().crossing(), None);
+    }
+
+    #[test]
+    fn rewritten() {
+        let valley = io::input_as_valley(&Input::text(EXAMPLE)).unwrap();
+        assert_eq!(valley.to_string(), EXAMPLE.trim_end());
+        assert_eq!(io::input_as_valley(&Input::text(&valley.to_string())).unwrap(), valley);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn deserialised() {
+        let valley = io::input_as_valley(&Input::text(EXAMPLE)).unwrap();
+        assert_eq!(valley.crossing(), Some(18));
+        let json = serde_json::to_string(&valley).unwrap();
+        assert!(!json.contains("clear"));
+        let again : Valley = serde_json::from_str(&json).unwrap();
+        assert_eq!(again, valley);
+        assert_eq!(again.round_trip(), Some(54));
+        let json = json.replace(r#""width":8"#, r#""width":0"#);
+        let bad : Valley = serde_json::from_str(&json).unwrap();
+        assert_eq!(bad.crossing(), None);
+    }
+
+    #[test]
+    fn no_path() {
+        let input = Input::text("#.#\n#v#\n#.#");
+        let err = days::registry().run(24, Part::One, &input).unwrap_err();
+        assert_eq!(err.to_string(), "day 24 no path through the valley");
+        assert!(days::registry().run(24, Part::Two, &input).is_err());
+    }
+}
@@ -1,17 +1,58 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fmt;
+use std::io::BufRead;
+
+use itertools::Itertools;
+
+use crate::error::{parse_lines_from, parse_num, skip, Result};
+
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 struct Elf {
     calories : Vec<usize>
 }
 
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Elfs {
     elfs : Vec<Elf>
 }
 
+// Elf totals taken one at a time in order. The best top elves are kept in
+// a min heap so the one to drop is always on top, of equal totals the
+// earlier elf ranks higher. That takes memory for the top elves only, a
+// tally made with_totals also keeps one total per elf, never the lines,
+// which the median and percentiles of the report need
+#[derive(Clone, Debug, Default)]
+pub struct Tally {
+    top : usize,
+    best : BinaryHeap<Reverse<(usize, Reverse<usize>)>>,
+    count : usize,
+    sum : u128,
+    totals : Option<Vec<usize>>
+}
+
+// Statistics of the elf totals, the top elves are index and total best
+// first and the percentiles are by nearest rank
+#[derive(Clone, Debug, PartialEq)]
+pub struct Report {
+    pub count : usize,
+    pub mean : f64,
+    pub median : f64,
+    pub percentiles : Vec<(u8, usize)>,
+    pub top : Vec<(usize, usize)>
+}
+
 impl Elf {
-    fn from_lines(lines : &[String]) -> Elf {
-        let calories = lines.iter().filter_map(
-            |str| str.parse::<usize>().ok()
-        ).collect();
-        Elf { calories : calories }
+    // The lines start after first lines of the input
+    fn from_lines(lines : &[String], first : usize) -> Result<Elf> {
+        let calories = parse_lines_from(
+            lines,
+            first,
+            |line| parse_num(line, line)
+        )?;
+        Ok(Elf { calories : calories })
     }
 
     fn total(&self) -> usize {
@@ -20,26 +61,248 @@ impl Elf {
 }
 
 impl Elfs {
-    pub fn from_lines(lines : &Vec<String>) -> Elfs {
-        let elfs : Vec<Elf> = lines.split(
-       |str| str == "" 
-        ).map(
-            |nums| Elf::from_lines(nums)
-        ).collect();
-        Elfs { elfs : elfs }
+    // Each blank line ends an elf, nothing after the last one is no elf
+    // rather than an elf with no calories, the same as for Tally::read
+    pub fn from_lines(lines : &Vec<String>) -> Result<Elfs> {
+        let mut elfs : Vec<Elf> = Vec::new();
+        let mut start = 0;
+        let mut groups = lines.split(|str| str.is_empty()).collect_vec();
+        if groups.last().is_some_and(|nums| nums.is_empty()) {
+            groups.pop();
+        }
+        for nums in groups {
+            elfs.push(Elf::from_lines(nums, start)?);
+            start += nums.len() + 1;
+        }
+        Ok(Elfs { elfs : elfs })
     }
 
-    pub fn best_elf(&self) -> usize {
+    // None if there are no elves
+    pub fn best_elf(&self) -> Option<usize> {
         self.elfs.iter().map(
             |elf| elf.total()
-        ).max().unwrap()
+        ).max()
     }
 
     pub fn best_elfs(&self, count : usize) -> usize {
-        let mut totals : Vec<usize> = self.elfs.iter().map(
-            |elf| elf.total()
-        ).collect();
-        totals.sort();
-        totals.iter().rev().take(count).sum()
+        self.tally(count).best_total()
+    }
+
+    pub fn tally(&self, top : usize) -> Tally {
+        let mut tally = Tally::new(top);
+        for elf in self.elfs.iter() {
+            tally.push(elf.total());
+        }
+        tally
+    }
+}
+
+impl Tally {
+    // Keeps only the top elves, so there is no report
+    pub fn new(top : usize) -> Tally {
+        Tally { top : top, best : BinaryHeap::new(), count : 0, sum : 0, totals : None }
+    }
+
+    // Keeps a total for every elf as well, for the report
+    pub fn with_totals(top : usize) -> Tally {
+        Tally { totals : Some(Vec::new()), ..Tally::new(top) }
+    }
+
+    pub fn from_reader<R : BufRead>(reader : R, top : usize) -> Result<Tally> {
+        let mut tally = Tally::new(top);
+        tally.read(reader)?;
+        Ok(tally)
+    }
+
+    // Read the elves a line at a time, a blank line ends an elf the same
+    // as for Elfs::from_lines
+    pub fn read<R : BufRead>(&mut self, reader : R) -> Result<()> {
+        let mut total : Option<usize> = None;
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.is_empty() {
+                self.push(total.take().unwrap_or(0));
+                continue;
+            }
+            let sum = total.get_or_insert(0);
+            match parse_num::<usize>(&line, &line) {
+                Ok(calories) => *sum += calories,
+                Err(err) => skip(err.offset_lines(i))?
+            }
+        }
+        if let Some(total) = total {
+            self.push(total);
+        }
+        Ok(())
+    }
+
+    pub fn push(&mut self, total : usize) {
+        let index = self.count;
+        self.count += 1;
+        self.sum += total as u128;
+        if let Some(totals) = self.totals.as_mut() {
+            totals.push(total);
+        }
+        self.best.push(Reverse((total, Reverse(index))));
+        if self.best.len() > self.top {
+            self.best.pop();
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    // The best elves as index and total, best first
+    pub fn best(&self) -> Vec<(usize, usize)> {
+        self.best.iter().map(
+            |Reverse((total, Reverse(index)))| (*index, *total)
+        ).sorted_by_key(|(index, total)| (Reverse(*total), *index)).collect()
+    }
+
+    pub fn best_total(&self) -> usize {
+        self.best.iter().map(|Reverse((total, _))| total).sum()
     }
-}
\ No newline at end of file
+
+    // Sorts the totals kept, none if there are no elves or the tally was
+    // not made with_totals
+    pub fn report(&mut self, percentiles : &[u8]) -> Option<Report> {
+        let count = self.count;
+        let sorted = self.totals.as_mut().filter(|_| count > 0)?;
+        sorted.sort_unstable();
+        let median = if count % 2 == 1 {
+            sorted[count / 2] as f64
+        } else {
+            (sorted[count / 2 - 1] as f64 + sorted[count / 2] as f64) / 2.0
+        };
+        let percentiles = percentiles.iter().map(|percent| {
+            let rank = (*percent.min(&100) as usize * count).div_ceil(100).max(1);
+            (*percent, sorted[rank - 1])
+        }).collect();
+        Some(Report {
+            count : count,
+            mean : self.sum as f64 / count as f64,
+            median : median,
+            percentiles : percentiles,
+            top : self.best()
+        })
+    }
+}
+
+// The calories of each elf a line each with a blank line between elves, so
+// an elf with no calories is just the blank line. As nothing after the last
+// blank line is no elf, such an elf at the end gets another blank line
+impl fmt::Display for Elfs {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut lines : Vec<String> = Vec::new();
+        for (i, elf) in self.elfs.iter().enumerate() {
+            if i > 0 {
+                lines.push(String::new());
+            }
+            lines.extend(elf.calories.iter().map(|calories| calories.to_string()));
+        }
+        if self.elfs.last().is_some_and(|elf| elf.calories.is_empty()) {
+            lines.push(String::new());
+            return writeln!(f, "{}", lines.join("\n"));
+        }
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error;
+    use crate::io::{self, Input};
+
+    const EXAMPLE : &str = include_str!("../../data/examples/day-1.txt");
+
+    #[test]
+    fn best_elf() {
+        let elfs = io::input_as_elfs(&Input::text(EXAMPLE)).unwrap();
+        assert_eq!(elfs.best_elf(), Some(24000));
+    }
+
+    #[test]
+    fn best_three_elfs() {
+        let elfs = io::input_as_elfs(&Input::text(EXAMPLE)).unwrap();
+        assert_eq!(elfs.best_elfs(3), 45000);
+    }
+
+    #[test]
+    fn round_trip() {
+        let elfs = io::input_as_elfs(&Input::text(EXAMPLE)).unwrap();
+        assert_eq!(elfs.to_string(), EXAMPLE.trim_end());
+        assert_eq!(io::input_as_elfs(&Input::text(&elfs.to_string())).unwrap(), elfs);
+        for text in ["1\n\n\n2", "\n1", "1\n\n\n", "\n", "1\n\n\n\n2\n\n\n", ""] {
+            let elfs = io::input_as_elfs(&Input::text(text)).unwrap();
+            assert_eq!(io::input_as_elfs(&Input::text(&elfs.to_string())).unwrap(), elfs, "{:?}", text);
+        }
+        assert_eq!(io::input_as_elfs(&Input::text("1\n\n\n2")).unwrap().elfs.len(), 3);
+        assert_eq!(io::input_as_elfs(&Input::text("1\n\n\n")).unwrap().elfs.len(), 2);
+    }
+
+    #[test]
+    fn streamed() {
+        let elfs = io::input_as_elfs(&Input::text(EXAMPLE)).unwrap();
+        let tally = Tally::from_reader(EXAMPLE.as_bytes(), 3).unwrap();
+        assert_eq!(tally.count(), 5);
+        assert_eq!(tally.best_total(), elfs.best_elfs(3));
+        assert_eq!(tally.best(), elfs.tally(3).best());
+        let skipped = Tally::from_reader("100\n\nabc\n200".as_bytes(), 1).unwrap();
+        assert_eq!(skipped.best(), vec!((1, 200)));
+    }
+
+    #[test]
+    fn report() {
+        let mut tally = Tally::with_totals(3);
+        tally.read(EXAMPLE.as_bytes()).unwrap();
+        let report = tally.report(&[0, 25, 90, 100]).unwrap();
+        assert_eq!(report.count, 5);
+        assert_eq!(report.mean, 11000.0);
+        assert_eq!(report.median, 10000.0);
+        assert_eq!(report.percentiles, vec!((0, 4000), (25, 6000), (90, 24000), (100, 24000)));
+        assert_eq!(report.top, vec!((3, 24000), (2, 11000), (4, 10000)));
+        assert_eq!(Tally::with_totals(3).report(&[50]), None);
+        assert_eq!(Tally::from_reader(EXAMPLE.as_bytes(), 3).unwrap().report(&[50]), None);
+    }
+
+    #[test]
+    fn ties() {
+        let mut tally = Tally::with_totals(3);
+        for total in [5, 7, 5, 7, 5, 1] {
+            tally.push(total);
+            assert!(tally.best.len() <= 3);
+        }
+        assert_eq!(tally.best(), vec!((1, 7), (3, 7), (0, 5)));
+        assert_eq!(tally.report(&[]).unwrap().median, 5.0);
+    }
+
+    #[test]
+    fn skipped_line() {
+        error::take_skipped();
+        let elfs = io::input_as_elfs(&Input::text("1000\n\nx\n2000")).unwrap();
+        assert_eq!(elfs.best_elf(), Some(2000));
+        let skipped = error::take_skipped();
+        assert_eq!(skipped.len(), 1);
+        assert!(skipped[0].to_string().starts_with("line 3 "), "{}", skipped[0]);
+    }
+
+    #[test]
+    fn trailing_blank_lines() {
+        for text in ["1\n\n2\n", "1\n\n2\n\n", "1\n\n2\n\n\n", "\n1\n\n\n2\n\n", "x\n\n1\n\nx\n", ""] {
+            let elfs = io::input_as_elfs(&Input::text(text)).unwrap();
+            let tally = Tally::from_reader(text.as_bytes(), 5).unwrap();
+            assert_eq!(tally.count(), elfs.elfs.len(), "{:?}", text);
+            assert_eq!(tally.best(), elfs.tally(5).best(), "{:?}", text);
+        }
+        assert_eq!(Tally::from_reader("1\n\n2\n\n".as_bytes(), 5).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn no_elves() {
+        let elfs = io::input_as_elfs(&Input::text("")).unwrap();
+        assert_eq!(elfs.best_elf(), None);
+        assert_eq!(elfs.best_elfs(3), 0);
+    }
+}
@@ -1,19 +1,29 @@
+use std::fmt;
+
+use crate::error::{Error, Result, parse_num};
+
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 struct Assignment {
     min : usize,
     max : usize
 }
 
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AssignmentPair {
     elf1 : Assignment,
     elf2 : Assignment
 }
 
 impl Assignment {
-    fn from_string(string : &str) -> Option<Assignment> {
-        let parts = string.split_once("-")?;
-        let min = parts.0.parse::<usize>().ok()?;
-        let max = parts.1.parse::<usize>().ok()?;
-        Some(Assignment {min : min, max : max} )
+    fn from_string(string : &str) -> Result<Assignment> {
+        let parts = string.split_once("-").ok_or(
+            Error::line(string, "a range min-max")
+        )?;
+        let min = parse_num(string, parts.0)?;
+        let max = parse_num(string, parts.1)?;
+        Ok(Assignment {min : min, max : max} )
     }
 
     fn contains(&self, other : &Assignment) -> bool {
@@ -26,11 +36,17 @@ impl Assignment {
 }
 
 impl AssignmentPair {
-    pub fn from_line(line : &String) -> Option<AssignmentPair> {
-        let parts = line.split_once(",")?;
-        let elf1 = Assignment::from_string(parts.0)?;
-        let elf2 = Assignment::from_string(parts.1)?;
-        Some(
+    pub fn from_line(line : &str) -> Result<AssignmentPair> {
+        let parts = line.split_once(",").ok_or(
+            Error::line(line, "two ranges separated by a comma")
+        )?;
+        let elf1 = Assignment::from_string(parts.0).map_err(
+            |err| err.within(line, parts.0)
+        )?;
+        let elf2 = Assignment::from_string(parts.1).map_err(
+            |err| err.within(line, parts.1)
+        )?;
+        Ok(
             AssignmentPair { elf1: elf1, elf2: elf2 }
         )
     }
@@ -42,4 +58,43 @@ impl AssignmentPair {
     pub fn overlap(&self) -> bool {
         self.elf1.overlap(&self.elf2)
     }
-}
\ No newline at end of file
+}
+
+impl fmt::Display for AssignmentPair {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}-{},{}-{}", self.elf1.min, self.elf1.max, self.elf2.min, self.elf2.max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::{self, Input};
+
+    const EXAMPLE : &str = include_str!("../../data/examples/day-4.txt");
+
+    #[test]
+    fn contains() {
+        let pairs = io::input_as_assignment(&Input::text(EXAMPLE)).unwrap();
+        assert_eq!(pairs.iter().filter(|pair| pair.contains()).count(), 2);
+    }
+
+    #[test]
+    fn overlap() {
+        let pairs = io::input_as_assignment(&Input::text(EXAMPLE)).unwrap();
+        assert_eq!(pairs.iter().filter(|pair| pair.overlap()).count(), 4);
+    }
+
+    #[test]
+    fn round_trip() {
+        let pairs = io::input_as_assignment(&Input::text(EXAMPLE)).unwrap();
+        let lines : Vec<String> = pairs.iter().map(|pair| pair.to_string()).collect();
+        assert_eq!(lines, EXAMPLE.lines().collect::<Vec<&str>>());
+        assert_eq!(io::input_as_assignment(&Input::text(&lines.join("\n"))).unwrap(), pairs);
+    }
+
+    #[test]
+    fn missing_pair() {
+        assert!(AssignmentPair::from_line("2-4").is_err());
+    }
+}
@@ -1,37 +1,63 @@
+use std::fmt;
+
+use itertools::Itertools;
+
+use crate::error::{Error, Result, parse_num};
+
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Move {
     num : usize,
     from : usize,
     to : usize
 }
 
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Crates {
     stacks : Vec<Vec<char>>
 }
 
+// The number of a stack, between 1 and the number of stacks
+fn parse_stack(line : &str, part : &str, stacks : usize) -> Result<usize> {
+    let stack = parse_num(line, part)?;
+    if stack == 0 || stack > stacks {
+        return Err(Error::at(line, part, &format!("a stack number from 1 to {}", stacks)));
+    }
+    Ok(stack)
+}
+
 impl Move {
-    pub fn from_line(line : &String) -> Option<Move> {
-        let mut parts = line.split_whitespace();
-        let num_s = parts.nth(1)?;
-        let from_s = parts.nth(1)?;
-        let to_s = parts.nth(1)?;
-        let num = num_s.parse::<usize>().ok()?;
-        let from = from_s.parse::<usize>().ok()?;
-        let to = to_s.parse::<usize>().ok()?;
-        Some(
-            Move { num : num, from : from, to : to }
+    // A move between two of the given number of stacks
+    pub fn from_line(line : &str, stacks : usize) -> Result<Move> {
+        let parts : Vec<&str> = line.split_whitespace().collect();
+        let (num_s, from_s, to_s) = match parts.as_slice() {
+            ["move", num_s, "from", from_s, "to", to_s] => (*num_s, *from_s, *to_s),
+            _ => return Err(Error::line(line, "move N from A to B"))
+        };
+        Ok(
+            Move {
+                num : parse_num(line, num_s)?,
+                from : parse_stack(line, from_s, stacks)?,
+                to : parse_stack(line, to_s, stacks)?
+            }
         )
     }
 }
 
 impl Crates {
-    pub fn from_lines(lines : &Vec<&String>) -> Option<Crates> {
-        let last_line = lines.first()?;
+    pub fn from_lines(lines : &Vec<&String>) -> Result<Crates> {
+        let last_line = lines.first().ok_or(
+            Error::invalid("missing the drawing of the crates")
+        )?;
         let size = (last_line.len()+1) / 4;
         let mut crates = Crates::empty(size);
-        for line in lines.iter().rev().skip(1) {
-            crates.add_line(line);
+        for (i, line) in lines.iter().enumerate().rev().skip(1) {
+            crates.add_line(line).map_err(
+                |err| err.offset_lines(i)
+            )?;
         }
-        Some(crates)
+        Ok(crates)
     }
 
     fn empty(size : usize) -> Crates {
@@ -42,22 +68,36 @@ impl Crates {
         init
     }
 
-    fn add_line(&mut self, line : &str) {
+    fn add_line(&mut self, line : &str) -> Result<()> {
         for (i,c) in line.char_indices() {
             if i % 4 == 1 && c != ' ' {
                 let s_in = i / 4;
-                self.stacks[s_in].push(c);
+                let stack = self.stacks.get_mut(s_in).ok_or(
+                    Error::at(line, &line[i..], "a crate inside the stacks")
+                )?;
+                stack.push(c);
             }
         }
+        Ok(())
+    }
+
+    pub fn stacks(&self) -> usize {
+        self.stacks.len()
     }
 
-    pub fn simulate(&mut self, moves : &Vec<Move>, multi : bool) {
+    // Fails on a move from or to a stack that does not exist
+    pub fn simulate(&mut self, moves : &Vec<Move>, multi : bool) -> Result<()> {
         for to_move in moves {
-            self.do_move(to_move, multi);
+            self.do_move(to_move, multi)?;
         }
+        Ok(())
     }
 
-    fn do_move(&mut self, to_move : &Move, multi : bool) {
+    fn do_move(&mut self, to_move : &Move, multi : bool) -> Result<()> {
+        let stacks = 1..=self.stacks.len();
+        if !stacks.contains(&to_move.from) || !stacks.contains(&to_move.to) {
+            return Err(Error::invalid(&format!("{} with {} stacks", to_move, self.stacks.len())));
+        }
         let mut temp : Vec<char> = Vec::new();
         for _ in 0..to_move.num {
             if let Some(top) = self.pop_top(to_move.from - 1) {
@@ -70,26 +110,98 @@ impl Crates {
         for ch in temp {
             self.push_top(to_move.to - 1, ch)
         }
+        Ok(())
     }
 
     fn pop_top(&mut self, from : usize) -> Option<char> {
-        if let Some(stack) = self.stacks.get_mut(from) {
-            stack.pop()
-        } else {
-            None
-        }
+        self.stacks[from].pop()
     }
 
     fn push_top(&mut self, to : usize, ch : char) {
-        if let Some(stack) = self.stacks.get_mut(to) {
-            stack.push(ch);
-        }
+        self.stacks[to].push(ch);
     }
 
-
     pub fn tops(&self) -> String {
         self.stacks.iter().map(
             |stack| stack.last().unwrap_or(&' ')
         ).collect()
     }
-}
\ No newline at end of file
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "move {} from {} to {}", self.num, self.from, self.to)
+    }
+}
+
+// The drawing is padded to the full width on every line, as the width of
+// the top line gives the number of stacks
+impl fmt::Display for Crates {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let height = self.stacks.iter().map(|stack| stack.len()).max().unwrap_or(0);
+        for level in (0..height).rev() {
+            writeln!(f, "{}", self.stacks.iter().map(
+                |stack| match stack.get(level) {
+                    Some(ch) => format!("[{}]", ch),
+                    None => "   ".to_string()
+                }
+            ).join(" "))?;
+        }
+        write!(f, "{}", (1..=self.stacks.len()).map(|i| format!("{:^3}", i)).join(" "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error;
+    use crate::io::{self, Input};
+
+    const EXAMPLE : &str = include_str!("../../data/examples/day-5.txt");
+
+    #[test]
+    fn single_moves() {
+        let (mut crates, moves) = io::input_as_crates(&Input::text(EXAMPLE)).unwrap();
+        crates.simulate(&moves, false).unwrap();
+        assert_eq!(crates.tops(), "CMZ");
+    }
+
+    #[test]
+    fn multi_moves() {
+        let (mut crates, moves) = io::input_as_crates(&Input::text(EXAMPLE)).unwrap();
+        crates.simulate(&moves, true).unwrap();
+        assert_eq!(crates.tops(), "MCD");
+    }
+
+    #[test]
+    fn round_trip() {
+        let (crates, moves) = io::input_as_crates(&Input::text(EXAMPLE)).unwrap();
+        let text = format!("{}\n\n{}", crates, moves.iter().join("\n"));
+        assert_eq!(text, EXAMPLE.trim_end());
+        assert_eq!(io::input_as_crates(&Input::text(&text)).unwrap(), (crates, moves));
+    }
+
+    #[test]
+    fn bad_move() {
+        assert!(Move::from_line("move one from 2 to 1", 3).is_err());
+        assert!(Move::from_line("take 1 from 2 to 1", 3).is_err());
+        assert!(Move::from_line("move 1 from 2 into 1", 3).is_err());
+        assert!(Move::from_line("move 1 from 2 to 1 now", 3).is_err());
+        assert!(Move::from_line("move 1 from 4 to 1", 3).is_err());
+        assert!(Move::from_line("move 1 from 2 to 0", 3).is_err());
+        assert_eq!(Move::from_line("move 1 from 2 to 3", 3).unwrap(), Move { num : 1, from : 2, to : 3 });
+        let mut crates = Crates::empty(2);
+        assert!(crates.simulate(&vec!(Move { num : 1, from : 1, to : 3 }), false).is_err());
+    }
+
+    #[test]
+    fn skipped_move() {
+        let text = EXAMPLE.replacen("move 2 from 2 to 1", "move 2 from 2", 1);
+        error::take_skipped();
+        let (_, moves) = io::input_as_crates(&Input::text(&text)).unwrap();
+        assert_eq!(moves.len(), 3);
+        let skipped = error::take_skipped();
+        assert_eq!(skipped.len(), 1);
+        assert!(skipped[0].to_string().starts_with("line 8 "), "{}", skipped[0]);
+    }
+}
@@ -1,35 +1,48 @@
+use std::fmt;
+
+use itertools::Itertools;
+
+use crate::error::{Error, Result, parse_lines, parse_num};
+
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 enum Instruction {
     Noop,
     AddX(i64)
 }
 
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CPU {
     code : Vec<Instruction>
 }
 
 impl Instruction {
-    fn from_line(line : &str) -> Option<Instruction> {
+    fn from_line(line : &str) -> Result<Instruction> {
         if line == "noop" {
-            Some(Instruction::Noop)
+            Ok(Instruction::Noop)
         } else {
-            let (ins, num_s) = line.split_once(" ")?;
+            let (ins, num_s) = line.split_once(" ").ok_or(
+                Error::line(line, "noop or addx")
+            )?;
             if ins == "addx" {
-                let num = num_s.parse::<i64>().ok()?;
-                Some(Instruction::AddX(num))
+                let num = parse_num(line, num_s)?;
+                Ok(Instruction::AddX(num))
             } else {
-                None
+                Err(Error::at(line, ins, "noop or addx"))
             }
         }
     }
 }
 
 impl CPU {
-    pub fn from_lines(lines : &Vec<String>) -> CPU {
-        CPU {
-            code : lines.iter().filter_map(
-                |line| Instruction::from_line(line)
-            ).collect()
-        }
+    pub fn from_lines(lines : &Vec<String>) -> Result<CPU> {
+        Ok(CPU {
+            code : parse_lines(
+                lines,
+                Instruction::from_line
+            )?
+        })
     }
 
     fn x_vals(&self) -> Vec<i64> {
@@ -72,4 +85,52 @@ impl CPU {
             )
         )
     }
-}
\ No newline at end of file
+}
+
+impl fmt::Display for CPU {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.code.iter().map(
+            |inst| match inst {
+                Instruction::Noop => "noop".to_string(),
+                Instruction::AddX(val) => format!("addx {}", val)
+            }
+        ).join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::io::{self, Input};
+
+    const EXAMPLE : &str = include_str!("../../data/examples/day-10.txt");
+
+    #[test]
+    fn signals() {
+        let signals = io::input_as_code(&Input::text(EXAMPLE)).unwrap().signals();
+        let strengths : Vec<i64> = [20, 60, 100, 140, 180, 220].iter().map(
+            |cycle| signals[cycle - 1]
+        ).collect();
+        assert_eq!(strengths, vec!(420, 1140, 1800, 2940, 2880, 3960));
+    }
+
+    #[test]
+    fn display() {
+        let cpu = io::input_as_code(&Input::text(EXAMPLE)).unwrap();
+        let expected = [
+            "##..##..##..##..##..##..##..##..##..##..",
+            "###...###...###...###...###...###...###.",
+            "####....####....####....####....####....",
+            "#####.....#####.....#####.....#####.....",
+            "######......######......######......####",
+            "#######.......#######.......#######....."
+        ].join("\n");
+        assert_eq!(cpu.display(), expected);
+    }
+
+    #[test]
+    fn round_trip() {
+        let cpu = io::input_as_code(&Input::text(EXAMPLE)).unwrap();
+        assert_eq!(cpu.to_string(), EXAMPLE.trim_end());
+        assert_eq!(io::input_as_code(&Input::text(&cpu.to_string())).unwrap(), cpu);
+    }
+}
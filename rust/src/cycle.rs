@@ -0,0 +1,92 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use num::PrimInt;
+
+// A simulation that repeats, after start steps the state comes round
+// again every period steps. The metric is kept for each step up to the
+// end of the first repeat so it can be worked out for any step count
+#[derive(Clone, Debug)]
+pub struct Cycle<M> {
+    start : usize,
+    period : usize,
+    values : Vec<M>
+}
+
+// Step the simulation until the key of a state has been seen before. The
+// key must capture everything that decides the following steps and the
+// metric should change by the same amount each time round the cycle.
+// Keeps stepping until a repeat is found
+pub fn detect<S, K, M, FK, FM, FS>(mut state : S, key : FK, metric : FM, mut step : FS) -> Cycle<M>
+  where K : Hash + Eq, M : PrimInt, FK : Fn(&S) -> K, FM : Fn(&S) -> M, FS : FnMut(&mut S)
+{
+    let mut seen = HashMap::new();
+    let mut values = Vec::new();
+    for i in 0.. {
+        values.push(metric(&state));
+        if let Some(start) = seen.insert(key(&state), i) {
+            return Cycle { start : start, period : i - start, values : values };
+        }
+        step(&mut state);
+    }
+    unreachable!()
+}
+
+impl<M : PrimInt> Cycle<M> {
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    pub fn period(&self) -> usize {
+        self.period
+    }
+
+    // The metric after steps steps of the simulation
+    pub fn at(&self, steps : usize) -> M {
+        if steps < self.values.len() {
+            return self.values[steps];
+        }
+        let cycles = (steps - self.start) / self.period;
+        let offset = (steps - self.start) % self.period;
+        let per_cycle = self.values[self.start + self.period] - self.values[self.start];
+        self.values[self.start + offset] + per_cycle * M::from(cycles).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A generator that runs into a loop, the metric is the running total
+    fn next(x : u64) -> u64 {
+        (x * x + 1) % 1009
+    }
+
+    #[test]
+    fn extrapolate() {
+        let cycle = detect(
+            (3u64, 0u64),
+            |(x, _)| *x,
+            |(_, total)| *total,
+            |(x, total)| {
+                *total += *x;
+                *x = next(*x);
+            }
+        );
+        let (mut x, mut total) = (3, 0);
+        for steps in 0..5000 {
+            assert_eq!(cycle.at(steps), total);
+            total += x;
+            x = next(x);
+        }
+        assert!(cycle.period() > 0);
+        assert!(cycle.start() + cycle.period() < 1009);
+    }
+
+    #[test]
+    fn immediate() {
+        let cycle = detect(0, |_| 0, |steps : &i64| *steps, |steps| *steps += 2);
+        assert_eq!((cycle.start(), cycle.period()), (0, 1));
+        assert_eq!(cycle.at(1_000_000_000_000), 2_000_000_000_000);
+    }
+}
@@ -0,0 +1,668 @@
+use crate::io::{self, Input};
+use crate::error::{Error, Result};
+use crate::render::Animate;
+use crate::solution::{Registry, Solution};
+use crate::{calories, game, packing, camp, crates, files, trees, rope, crt, monkey};
+use crate::{heights, packets, sand, sensors, pressure, tetris, lava, robot, riddle};
+use crate::{encrypted, map, planting, blizzard, snafu};
+
+pub struct Day1;
+pub struct Day2;
+pub struct Day3;
+pub struct Day4;
+pub struct Day5;
+pub struct Day6;
+pub struct Day7;
+pub struct Day8;
+pub struct Day9;
+pub struct Day10;
+pub struct Day11;
+pub struct Day12;
+pub struct Day13;
+pub struct Day14;
+pub struct Day15;
+pub struct Day16;
+pub struct Day17;
+pub struct Day18;
+pub struct Day19;
+pub struct Day20;
+pub struct Day21;
+pub struct Day22;
+pub struct Day23;
+pub struct Day24;
+pub struct Day25;
+
+pub fn registry() -> Registry {
+    let mut registry = Registry::new();
+    registry.register(Day1);
+    registry.register(Day2);
+    registry.register(Day3);
+    registry.register(Day4);
+    registry.register(Day5);
+    registry.register(Day6);
+    registry.register(Day7);
+    registry.register(Day8);
+    registry.register(Day9);
+    registry.register(Day10);
+    registry.register(Day11);
+    registry.register(Day12);
+    registry.register(Day13);
+    registry.register(Day14);
+    registry.register(Day15);
+    registry.register(Day16);
+    registry.register(Day17);
+    registry.register(Day18);
+    registry.register(Day19);
+    registry.register(Day20);
+    registry.register(Day21);
+    registry.register(Day22);
+    registry.register(Day23);
+    registry.register(Day24);
+    registry.register(Day25);
+    registry
+}
+
+// The simulations that can be watched, None for days without one
+pub fn animation(day : u8, input : &Input) -> Result<Option<Box<dyn Animate>>> {
+    let animation : Option<Box<dyn Animate>> = match day {
+        9 => Some(Box::new(rope::Motion::new(io::input_as_directions(input)?, 10))),
+        14 => Some(Box::new(io::input_as_cave(input)?)),
+        17 => Some(Box::new(tetris::Tower::new(io::input_as_jets(input)?, 2022))),
+        22 => Some(Box::new(map::Walk::new(io::input_as_map(input)?, true)?)),
+        23 => Some(Box::new(planting::Spreading::new(io::input_as_field(input)?))),
+        _ => None
+    };
+    Ok(animation)
+}
+
+impl Solution for Day1 {
+    type Input = calories::Elfs;
+    type PartOne = usize;
+    type PartTwo = usize;
+
+    fn day(&self) -> u8 {
+        1
+    }
+
+    fn parse(&self, input : &Input) -> Result<calories::Elfs> {
+        io::input_as_elfs(input)
+    }
+
+    fn part_one(&self, data : calories::Elfs) -> Result<usize> {
+        data.best_elf().ok_or(
+            Error::invalid("there are no elves")
+        )
+    }
+
+    fn part_two(&self, data : calories::Elfs) -> Result<Option<usize>> {
+        Ok(Some(data.best_elfs(3)))
+    }
+}
+
+impl Solution for Day2 {
+    type Input = (game::Game, game::Guide);
+    type PartOne = usize;
+    type PartTwo = usize;
+
+    fn day(&self) -> u8 {
+        2
+    }
+
+    fn parse(&self, input : &Input) -> Result<(game::Game, game::Guide)> {
+        Ok((io::input_as_game(input)?, io::input_as_guide(input)?))
+    }
+
+    fn part_one(&self, (data, _) : (game::Game, game::Guide)) -> Result<usize> {
+        Ok(data.score())
+    }
+
+    fn part_two(&self, (_, data) : (game::Game, game::Guide)) -> Result<Option<usize>> {
+        Ok(Some(data.score()))
+    }
+}
+
+impl Solution for Day3 {
+    type Input = (Vec<packing::Rucksack>, Vec<packing::Group>);
+    type PartOne = usize;
+    type PartTwo = usize;
+
+    fn day(&self) -> u8 {
+        3
+    }
+
+    fn parse(&self, input : &Input) -> Result<(Vec<packing::Rucksack>, Vec<packing::Group>)> {
+        Ok((io::input_as_rucksack(input)?, io::input_as_groups(input)?))
+    }
+
+    fn part_one(&self, (data, _) : (Vec<packing::Rucksack>, Vec<packing::Group>)) -> Result<usize> {
+        Ok(data.iter().map(|r| r.score()).sum())
+    }
+
+    fn part_two(&self, (_, data) : (Vec<packing::Rucksack>, Vec<packing::Group>)) -> Result<Option<usize>> {
+        Ok(Some(data.iter().map(|r| r.score()).sum()))
+    }
+}
+
+impl Solution for Day4 {
+    type Input = Vec<camp::AssignmentPair>;
+    type PartOne = usize;
+    type PartTwo = usize;
+
+    fn day(&self) -> u8 {
+        4
+    }
+
+    fn parse(&self, input : &Input) -> Result<Vec<camp::AssignmentPair>> {
+        io::input_as_assignment(input)
+    }
+
+    fn part_one(&self, data : Vec<camp::AssignmentPair>) -> Result<usize> {
+        Ok(data.iter().filter(|&r| r.contains()).count())
+    }
+
+    fn part_two(&self, data : Vec<camp::AssignmentPair>) -> Result<Option<usize>> {
+        Ok(Some(data.iter().filter(|&r| r.overlap()).count()))
+    }
+}
+
+impl Solution for Day5 {
+    type Input = (crates::Crates, Vec<crates::Move>);
+    type PartOne = String;
+    type PartTwo = String;
+
+    fn day(&self) -> u8 {
+        5
+    }
+
+    fn parse(&self, input : &Input) -> Result<(crates::Crates, Vec<crates::Move>)> {
+        io::input_as_crates(input)
+    }
+
+    fn part_one(&self, (mut data, moves) : (crates::Crates, Vec<crates::Move>)) -> Result<String> {
+        data.simulate(&moves, false)?;
+        Ok(data.tops())
+    }
+
+    fn part_two(&self, (mut data, moves) : (crates::Crates, Vec<crates::Move>)) -> Result<Option<String>> {
+        data.simulate(&moves, true)?;
+        Ok(Some(data.tops()))
+    }
+}
+
+impl Solution for Day6 {
+    type Input = String;
+    type PartOne = usize;
+    type PartTwo = usize;
+
+    fn day(&self) -> u8 {
+        6
+    }
+
+    fn parse(&self, input : &Input) -> Result<String> {
+        io::input_as_line(input)
+    }
+
+    fn part_one(&self, data : String) -> Result<usize> {
+        Ok(crate::signal::start_of_packet(&data, 4))
+    }
+
+    fn part_two(&self, data : String) -> Result<Option<usize>> {
+        Ok(Some(crate::signal::start_of_packet(&data, 14)))
+    }
+}
+
+impl Solution for Day7 {
+    type Input = files::Commands;
+    type PartOne = usize;
+    type PartTwo = usize;
+
+    fn day(&self) -> u8 {
+        7
+    }
+
+    fn parse(&self, input : &Input) -> Result<files::Commands> {
+        io::input_as_commands(input)
+    }
+
+    fn part_one(&self, data : files::Commands) -> Result<usize> {
+        let files = data.filesystem();
+        Ok(files.total(100000))
+    }
+
+    fn part_two(&self, data : files::Commands) -> Result<Option<usize>> {
+        let files = data.filesystem();
+        let size = files.size();
+        files.smallest_dir(size.saturating_sub(40000000)).map(Some).ok_or(
+            Error::invalid("no directory is big enough to free the space")
+        )
+    }
+}
+
+impl Solution for Day8 {
+    type Input = trees::Forest;
+    type PartOne = usize;
+    type PartTwo = usize;
+
+    fn day(&self) -> u8 {
+        8
+    }
+
+    fn parse(&self, input : &Input) -> Result<trees::Forest> {
+        io::input_as_forest(input)
+    }
+
+    fn part_one(&self, data : trees::Forest) -> Result<usize> {
+        Ok(data.count_visible())
+    }
+
+    fn part_two(&self, data : trees::Forest) -> Result<Option<usize>> {
+        Ok(Some(data.scenic_max()))
+    }
+}
+
+impl Solution for Day9 {
+    type Input = rope::Directions;
+    type PartOne = usize;
+    type PartTwo = usize;
+
+    fn day(&self) -> u8 {
+        9
+    }
+
+    fn parse(&self, input : &Input) -> Result<rope::Directions> {
+        io::input_as_directions(input)
+    }
+
+    fn part_one(&self, data : rope::Directions) -> Result<usize> {
+        Ok(data.simulate(2).len())
+    }
+
+    fn part_two(&self, data : rope::Directions) -> Result<Option<usize>> {
+        Ok(Some(data.simulate(10).len()))
+    }
+}
+
+impl Solution for Day10 {
+    type Input = crt::CPU;
+    type PartOne = i64;
+    type PartTwo = String;
+
+    fn day(&self) -> u8 {
+        10
+    }
+
+    fn parse(&self, input : &Input) -> Result<crt::CPU> {
+        io::input_as_code(input)
+    }
+
+    fn part_one(&self, data : crt::CPU) -> Result<i64> {
+        let signals = data.signals();
+        Ok(signals[19] + signals[59] + signals[99] + signals[139] + signals[179] + signals[219])
+    }
+
+    fn part_two(&self, data : crt::CPU) -> Result<Option<String>> {
+        Ok(Some(data.display()))
+    }
+}
+
+impl Solution for Day11 {
+    type Input = monkey::Monkeys;
+    type PartOne = usize;
+    type PartTwo = usize;
+
+    fn day(&self) -> u8 {
+        11
+    }
+
+    fn parse(&self, input : &Input) -> Result<monkey::Monkeys> {
+        io::input_as_monkeys(input)
+    }
+
+    fn part_one(&self, mut data : monkey::Monkeys) -> Result<usize> {
+        data.simulate(20, true);
+        Ok(data.monkey_buisness())
+    }
+
+    fn part_two(&self, mut data : monkey::Monkeys) -> Result<Option<usize>> {
+        data.simulate(10000, false);
+        Ok(Some(data.monkey_buisness()))
+    }
+}
+
+impl Solution for Day12 {
+    type Input = heights::HeightMap;
+    type PartOne = usize;
+    type PartTwo = usize;
+
+    fn day(&self) -> u8 {
+        12
+    }
+
+    fn parse(&self, input : &Input) -> Result<heights::HeightMap> {
+        io::input_as_heights(input)
+    }
+
+    fn part_one(&self, data : heights::HeightMap) -> Result<usize> {
+        Ok(data.distance())
+    }
+
+    fn part_two(&self, data : heights::HeightMap) -> Result<Option<usize>> {
+        Ok(Some(data.distance_start()))
+    }
+}
+
+impl Solution for Day13 {
+    type Input = (Vec<packets::PacketPair>, Vec<packets::Packet>);
+    type PartOne = usize;
+    type PartTwo = usize;
+
+    fn day(&self) -> u8 {
+        13
+    }
+
+    fn parse(&self, input : &Input) -> Result<(Vec<packets::PacketPair>, Vec<packets::Packet>)> {
+        Ok((io::input_as_packet_pairs(input)?, io::input_as_packets(input)?))
+    }
+
+    fn part_one(&self, (data, _) : (Vec<packets::PacketPair>, Vec<packets::Packet>)) -> Result<usize> {
+        Ok(data.iter().enumerate().filter_map(
+            |(i, pair)| if pair.ordered() { Some(i+1) } else { None }
+        ).sum())
+    }
+
+    fn part_two(&self, (_, mut data) : (Vec<packets::PacketPair>, Vec<packets::Packet>)) -> Result<Option<usize>> {
+        let (div1, div2) = packets::Packet::dividers();
+        data.push(div1.clone());
+        data.push(div2.clone());
+        data.sort();
+        Ok(Some(data.iter().enumerate().filter_map(
+            |(i,p)| if *p==div1 || *p==div2 { Some(i+1) } else { None }
+        ).product()))
+    }
+}
+
+impl Solution for Day14 {
+    type Input = sand::Cave;
+    type PartOne = usize;
+    type PartTwo = usize;
+
+    fn day(&self) -> u8 {
+        14
+    }
+
+    fn parse(&self, input : &Input) -> Result<sand::Cave> {
+        io::input_as_cave(input)
+    }
+
+    fn part_one(&self, mut data : sand::Cave) -> Result<usize> {
+        Ok(data.pour_void())
+    }
+
+    fn part_two(&self, mut data : sand::Cave) -> Result<Option<usize>> {
+        Ok(Some(data.pour_wall()))
+    }
+}
+
+impl Solution for Day15 {
+    type Input = sensors::Sensors;
+    type PartOne = usize;
+    type PartTwo = i64;
+
+    fn day(&self) -> u8 {
+        15
+    }
+
+    fn parse(&self, input : &Input) -> Result<sensors::Sensors> {
+        io::input_as_sensors(input)
+    }
+
+    fn part_one(&self, data : sensors::Sensors) -> Result<usize> {
+        Ok(data.no_becons_row(2000000))
+    }
+
+    fn part_two(&self, data : sensors::Sensors) -> Result<Option<i64>> {
+        let pt = data.find_beacon(0, 4000000, 0, 4000000).ok_or(
+            Error::invalid("there is nowhere left for the distress beacon")
+        )?;
+        Ok(Some(pt.x * 4000000 + pt.y))
+    }
+}
+
+impl Solution for Day16 {
+    type Input = pressure::Network;
+    type PartOne = usize;
+    type PartTwo = usize;
+
+    fn day(&self) -> u8 {
+        16
+    }
+
+    fn parse(&self, input : &Input) -> Result<pressure::Network> {
+        io::input_as_network(input)
+    }
+
+    fn part_one(&self, data : pressure::Network) -> Result<usize> {
+        Ok(data.maximum_pressure())
+    }
+
+    fn part_two(&self, data : pressure::Network) -> Result<Option<usize>> {
+        Ok(Some(data.maximum_pressure_dual()))
+    }
+}
+
+impl Solution for Day17 {
+    type Input = tetris::Jets;
+    type PartOne = usize;
+    type PartTwo = usize;
+
+    fn day(&self) -> u8 {
+        17
+    }
+
+    fn parse(&self, input : &Input) -> Result<tetris::Jets> {
+        io::input_as_jets(input)
+    }
+
+    fn part_one(&self, mut data : tetris::Jets) -> Result<usize> {
+        let mut game = tetris::Game::new();
+        game.simulate(2022, &mut data);
+        Ok(game.height())
+    }
+
+    fn part_two(&self, mut data : tetris::Jets) -> Result<Option<usize>> {
+        let mut game = tetris::Game::new();
+        Ok(Some(game.simulate_long(1000000000000, &mut data)))
+    }
+}
+
+impl Solution for Day18 {
+    type Input = lava::Droplets;
+    type PartOne = usize;
+    type PartTwo = usize;
+
+    fn day(&self) -> u8 {
+        18
+    }
+
+    fn parse(&self, input : &Input) -> Result<lava::Droplets> {
+        io::input_as_lava(input)
+    }
+
+    fn part_one(&self, data : lava::Droplets) -> Result<usize> {
+        Ok(data.surface_area())
+    }
+
+    fn part_two(&self, data : lava::Droplets) -> Result<Option<usize>> {
+        Ok(Some(data.outside_surface_area()))
+    }
+}
+
+impl Solution for Day19 {
+    type Input = Vec<robot::Blueprint>;
+    type PartOne = usize;
+    type PartTwo = usize;
+
+    fn day(&self) -> u8 {
+        19
+    }
+
+    fn parse(&self, input : &Input) -> Result<Vec<robot::Blueprint>> {
+        io::input_as_blueprints(input)
+    }
+
+    fn part_one(&self, data : Vec<robot::Blueprint>) -> Result<usize> {
+        Ok(data.iter().map(
+            |bp| bp.id() * bp.max_geodes(24)
+        ).sum())
+    }
+
+    fn part_two(&self, data : Vec<robot::Blueprint>) -> Result<Option<usize>> {
+        Ok(Some(data.iter().take(3).map(
+            |bp| bp.max_geodes(32)
+        ).product()))
+    }
+}
+
+impl Solution for Day20 {
+    type Input = Vec<i64>;
+    type PartOne = i64;
+    type PartTwo = i64;
+
+    fn day(&self) -> u8 {
+        20
+    }
+
+    fn parse(&self, input : &Input) -> Result<Vec<i64>> {
+        io::input_as_ints(input)
+    }
+
+    fn part_one(&self, data : Vec<i64>) -> Result<i64> {
+        let encrypted = encrypted::File::make_file(&data);
+        Ok(encrypted.data(1000) + encrypted.data(2000) + encrypted.data(3000))
+    }
+
+    fn part_two(&self, data : Vec<i64>) -> Result<Option<i64>> {
+        let encrypted = encrypted::File::make_file_key(&data, 811589153, 10);
+        Ok(Some(encrypted.data(1000) + encrypted.data(2000) + encrypted.data(3000)))
+    }
+}
+
+impl Solution for Day21 {
+    type Input = riddle::Riddle;
+    type PartOne = i64;
+    type PartTwo = i64;
+
+    fn day(&self) -> u8 {
+        21
+    }
+
+    fn parse(&self, input : &Input) -> Result<riddle::Riddle> {
+        io::input_as_riddles(input)
+    }
+
+    fn part_one(&self, data : riddle::Riddle) -> Result<i64> {
+        data.solve("root").ok_or(
+            Error::invalid("no root monkey, or a monkey it waits for is missing")
+        )
+    }
+
+    fn part_two(&self, data : riddle::Riddle) -> Result<Option<i64>> {
+        let poly = data.root_eqn("humn").ok_or(
+            Error::invalid("no root monkey with two to compare, or a monkey it waits for is missing")
+        )?;
+        Ok(Some(poly.solve()))
+    }
+}
+
+impl Solution for Day22 {
+    type Input = map::Map;
+    type PartOne = usize;
+    type PartTwo = usize;
+
+    fn day(&self) -> u8 {
+        22
+    }
+
+    fn parse(&self, input : &Input) -> Result<map::Map> {
+        io::input_as_map(input)
+    }
+
+    fn part_one(&self, data : map::Map) -> Result<usize> {
+        Ok(data.simulate().val())
+    }
+
+    fn part_two(&self, data : map::Map) -> Result<Option<usize>> {
+        Ok(Some(data.simulate_cube()?.val()))
+    }
+}
+
+impl Solution for Day23 {
+    type Input = planting::Field;
+    type PartOne = usize;
+    type PartTwo = usize;
+
+    fn day(&self) -> u8 {
+        23
+    }
+
+    fn parse(&self, input : &Input) -> Result<planting::Field> {
+        io::input_as_field(input)
+    }
+
+    fn part_one(&self, mut data : planting::Field) -> Result<usize> {
+        data.simulate(10);
+        Ok(data.empty_ground())
+    }
+
+    fn part_two(&self, mut data : planting::Field) -> Result<Option<usize>> {
+        Ok(Some(data.simulate_till_still()))
+    }
+}
+
+impl Solution for Day24 {
+    type Input = blizzard::Valley;
+    type PartOne = usize;
+    type PartTwo = usize;
+
+    fn day(&self) -> u8 {
+        24
+    }
+
+    fn parse(&self, input : &Input) -> Result<blizzard::Valley> {
+        io::input_as_valley(input)
+    }
+
+    fn part_one(&self, data : blizzard::Valley) -> Result<usize> {
+        data.crossing().ok_or(
+            Error::invalid("no path through the valley")
+        )
+    }
+
+    fn part_two(&self, data : blizzard::Valley) -> Result<Option<usize>> {
+        data.round_trip().map(Some).ok_or(
+            Error::invalid("no path through the valley")
+        )
+    }
+}
+
+impl Solution for Day25 {
+    type Input = Vec<snafu::Snafu>;
+    type PartOne = String;
+    type PartTwo = String;
+
+    fn day(&self) -> u8 {
+        25
+    }
+
+    fn parse(&self, input : &Input) -> Result<Vec<snafu::Snafu>> {
+        io::input_as_snafus(input)
+    }
+
+    fn part_one(&self, data : Vec<snafu::Snafu>) -> Result<String> {
+        Ok(data.iter().sum::<snafu::Snafu>().to_string())
+    }
+
+    fn has_part_two(&self) -> bool {
+        false
+    }
+}
@@ -61,4 +61,29 @@ impl File {
         let i = (base + index) % self.vals.len();
         self.vals[i].1
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::{self, Input};
+
+    const EXAMPLE : &str = include_str!("../../data/examples/day-20.txt");
+
+    fn coordinates(file : &File) -> Vec<i64> {
+        vec!(file.data(1000), file.data(2000), file.data(3000))
+    }
+
+    #[test]
+    fn mix() {
+        let vals = io::input_as_ints(&Input::text(EXAMPLE)).unwrap();
+        assert_eq!(coordinates(&File::make_file(&vals)), vec!(4, -3, 2));
+    }
+
+    #[test]
+    fn mix_with_key() {
+        let vals = io::input_as_ints(&Input::text(EXAMPLE)).unwrap();
+        let file = File::make_file_key(&vals, 811589153, 10);
+        assert_eq!(coordinates(&file), vec!(811589153, 2434767459, -1623178306));
+    }
+}
@@ -0,0 +1,233 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+#[derive(Debug)]
+pub enum Error {
+    Io {
+        day : Option<u8>,
+        path : Option<PathBuf>,
+        source : std::io::Error
+    },
+    Parse {
+        day : Option<u8>,
+        line : usize,
+        column : usize,
+        text : String,
+        expected : String
+    },
+    Invalid {
+        day : Option<u8>,
+        reason : String
+    },
+    Unsolved {
+        day : u8,
+        part : u8
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+static STRICT : AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    static SKIPPED : RefCell<Vec<Error>> = const { RefCell::new(Vec::new()) };
+}
+
+// In strict mode the first line that fails to parse is an error,
+// otherwise it is reported and skipped
+pub fn set_strict(strict : bool) {
+    STRICT.store(strict, Ordering::Relaxed);
+}
+
+pub fn strict() -> bool {
+    STRICT.load(Ordering::Relaxed)
+}
+
+// Byte offset of part inside whole, part must be a slice taken from whole
+fn offset(whole : &str, part : &str) -> usize {
+    let start = whole.as_ptr() as usize;
+    let pos = part.as_ptr() as usize;
+    if pos >= start && pos <= start + whole.len() {
+        pos - start
+    } else {
+        0
+    }
+}
+
+// Number of lines in whole before the slice part starts
+pub fn lines_before(whole : &str, part : &str) -> usize {
+    whole[..offset(whole, part)].matches('\n').count()
+}
+
+impl Error {
+    // Error at the slice part of line
+    pub fn at(line : &str, part : &str, expected : &str) -> Error {
+        Error::Parse {
+            day : None,
+            line : 1,
+            column : offset(line, part) + 1,
+            text : line.to_string(),
+            expected : expected.to_string()
+        }
+    }
+
+    // Error with the whole line
+    pub fn line(line : &str, expected : &str) -> Error {
+        Error::at(line, line, expected)
+    }
+
+    pub fn file(path : &Path, source : std::io::Error) -> Error {
+        Error::Io { day : None, path : Some(path.to_path_buf()), source : source }
+    }
+
+    pub fn invalid(reason : &str) -> Error {
+        Error::Invalid { day : None, reason : reason.to_string() }
+    }
+
+    // Move an error found in the slice part so it is relative to line
+    pub fn within(self, line : &str, part : &str) -> Error {
+        match self {
+            Error::Parse { day, column, expected, .. } => Error::Parse {
+                day : day,
+                line : 1,
+                column : offset(line, part) + column,
+                text : line.to_string(),
+                expected : expected
+            },
+            other => other
+        }
+    }
+
+    pub fn offset_lines(self, count : usize) -> Error {
+        match self {
+            Error::Parse { day, line, column, text, expected } => Error::Parse {
+                day : day,
+                line : line + count,
+                column : column,
+                text : text,
+                expected : expected
+            },
+            other => other
+        }
+    }
+
+    // The input file does not exist, which is not a failure of the solution
+    pub fn is_missing_input(&self) -> bool {
+        matches!(self, Error::Io { path : Some(_), source, .. } if source.kind() == std::io::ErrorKind::NotFound)
+    }
+
+    pub fn on_day(self, day_num : u8) -> Error {
+        match self {
+            Error::Io { path, source, .. } => Error::Io { day : Some(day_num), path : path, source : source },
+            Error::Parse { line, column, text, expected, .. } => Error::Parse {
+                day : Some(day_num),
+                line : line,
+                column : column,
+                text : text,
+                expected : expected
+            },
+            Error::Invalid { reason, .. } => Error::Invalid { day : Some(day_num), reason : reason },
+            other => other
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io { day, path, source } => {
+                write_day(f, day)?;
+                if let Some(file) = path {
+                    write!(f, "{}: ", file.display())?;
+                }
+                write!(f, "{}", source)
+            },
+            Error::Parse { day, line, column, text, expected } => {
+                write_day(f, day)?;
+                write!(f, "line {} column {}: expected {} in {:?}", line, column, expected, text)
+            },
+            Error::Invalid { day, reason } => {
+                write_day(f, day)?;
+                write!(f, "{}", reason)
+            },
+            Error::Unsolved { day, part } => {
+                write!(f, "day {} part {} is not solved", day, part)
+            }
+        }
+    }
+}
+
+fn write_day(f: &mut fmt::Formatter, day : &Option<u8>) -> fmt::Result {
+    if let Some(num) = day {
+        write!(f, "day {} ", num)?;
+    }
+    Ok(())
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } => Some(source),
+            _ => None
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(source : std::io::Error) -> Error {
+        Error::Io { day : None, path : None, source : source }
+    }
+}
+
+// Parse each non-empty line, in lenient mode lines that fail are skipped
+pub fn parse_lines<T, S, F>(lines : &[S], parse : F) -> Result<Vec<T>>
+  where S : AsRef<str>, F : Fn(&str) -> Result<T>
+{
+    parse_lines_from(lines, 0, parse)
+}
+
+// As parse_lines for lines that start after first lines of the input
+pub fn parse_lines_from<T, S, F>(lines : &[S], first : usize, parse : F) -> Result<Vec<T>>
+  where S : AsRef<str>, F : Fn(&str) -> Result<T>
+{
+    let mut items = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        let line = line.as_ref();
+        if line.is_empty() {
+            continue;
+        }
+        match parse(line) {
+            Ok(item) => items.push(item),
+            Err(err) => skip(err.offset_lines(first + i))?
+        }
+    }
+    Ok(items)
+}
+
+// Decide what to do with an unparseable part of the input, the error must
+// already give the line in the whole input. Skipped errors are kept for the
+// caller to report, which knows the day and whether it has done so before
+pub fn skip(err : Error) -> Result<()> {
+    if strict() {
+        Err(err)
+    } else {
+        SKIPPED.with(|skipped| skipped.borrow_mut().push(err));
+        Ok(())
+    }
+}
+
+// The errors skipped on this thread since the last call
+pub fn take_skipped() -> Vec<Error> {
+    SKIPPED.with(|skipped| skipped.take())
+}
+
+// Parse a number from a slice of line
+pub fn parse_num<T>(line : &str, part : &str) -> Result<T>
+  where T : std::str::FromStr
+{
+    part.parse::<T>().map_err(
+        |_| Error::at(line, part, "a number")
+    )
+}
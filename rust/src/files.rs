@@ -1,6 +1,10 @@
 use std::{collections::HashMap};
+use std::fmt;
 
-#[derive(Debug)]
+use crate::error::{Error, Result, lines_before, parse_num, skip};
+
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 enum Command {
     GoRoot,
     GoUp,
@@ -8,48 +12,56 @@ enum Command {
     Files(Vec<(String, Option<usize>)>)
 }
 
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Commands {
     commands : Vec<Command>
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FileSystem {
     files :HashMap<String, usize>,
     directories : HashMap<String, FileSystem>
 }
 
-fn parse_file(line : &str) -> Option<(String, Option<usize>)> {
-    let (start, end) = line.split_once(" ")?;
+fn parse_file(line : &str) -> Result<(String, Option<usize>)> {
+    let (start, end) = line.split_once(" ").ok_or(
+        Error::line(line, "a size or dir followed by a name")
+    )?;
     if start=="dir" {
-        Some((end.to_string(), None))
+        Ok((end.to_string(), None))
     } else {
-        if let Some(size) = start.parse::<usize>().ok() {
-            Some((end.to_string(), Some(size)))
-        } else {
-            None
-        }
+        let size = parse_num(line, start)?;
+        Ok((end.to_string(), Some(size)))
     }
 }
 
 impl Command {
-    fn from_string(string : &str) -> Option<Command> {
+    // The command starts after first lines of the input
+    fn from_string(string : &str, first : usize) -> Result<Command> {
         if let Some(rest) = string.strip_prefix("cd ") {
             if rest == "/\n" {
-                Some(Command::GoRoot)
+                Ok(Command::GoRoot)
             } else {
                 if rest == "..\n" {
-                    Some(Command::GoUp)
+                    Ok(Command::GoUp)
                 } else {
-                    Some(Command::GoDown(rest.trim_end().to_string()))
+                    Ok(Command::GoDown(rest.trim_end().to_string()))
                 }
             }
         } else {
             if let Some(rest) = string.strip_prefix("ls") {
-                let files = rest.lines().filter_map(
-                    |line| parse_file(line)
-                ).collect();
-                Some(Command::Files(files))
+                let mut files = Vec::new();
+                for line in rest.lines().filter(|line| !line.is_empty()) {
+                    match parse_file(line) {
+                        Ok(file) => files.push(file),
+                        Err(err) => skip(err.offset_lines(first + lines_before(string, line)))?
+                    }
+                }
+                Ok(Command::Files(files))
             } else {
-                None
+                let line = string.lines().next().unwrap_or(string);
+                Err(Error::line(line, "cd or ls").offset_lines(first))
             }
         }
     }
@@ -73,11 +85,15 @@ impl Command {
 }
 
 impl Commands {
-    pub fn from_string(string : &str) -> Commands {
-        let cmds = string.split("$ ").filter_map(
-            |str| Command::from_string(str)
-        ).collect();
-        Commands { commands: cmds }
+    pub fn from_string(string : &str) -> Result<Commands> {
+        let mut cmds = Vec::new();
+        for str in string.split("$ ").filter(|str| !str.is_empty()) {
+            match Command::from_string(str, lines_before(string, str)) {
+                Ok(cmd) => cmds.push(cmd),
+                Err(err) => skip(err)?
+            }
+        }
+        Ok(Commands { commands: cmds })
     }
 
     pub fn filesystem(&self) -> FileSystem {
@@ -90,6 +106,37 @@ impl Commands {
     }
 }
 
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::GoRoot => writeln!(f, "$ cd /"),
+            Self::GoUp => writeln!(f, "$ cd .."),
+            Self::GoDown(dir) => writeln!(f, "$ cd {}", dir),
+            Self::Files(files) => {
+                writeln!(f, "$ ls")?;
+                for (name, form) in files {
+                    match form {
+                        Some(size) => writeln!(f, "{} {}", size, name)?,
+                        None => writeln!(f, "dir {}", name)?
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+// Every line ends with a newline, a cd is only told apart from a directory
+// name by the line break after it
+impl fmt::Display for Commands {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for command in self.commands.iter() {
+            write!(f, "{}", command)?;
+        }
+        Ok(())
+    }
+}
+
 impl FileSystem {
 
     fn new() -> FileSystem {
@@ -182,7 +229,7 @@ impl FileSystem {
     }
 
     fn add_files(&mut self, path : &[String], files : &Vec<(String, Option<usize>)>) {
-        if path.len() == 0 {
+        if path.is_empty() {
             self.add_files_root(files);
         } else {
             self.directories.get_mut(&path[0]).unwrap().add_files(
@@ -192,4 +239,43 @@ impl FileSystem {
         }
     }
 
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error;
+    use crate::io::{self, Input};
+
+    const EXAMPLE : &str = include_str!("../../data/examples/day-7.txt");
+
+    #[test]
+    fn small_dirs() {
+        let files = io::input_as_commands(&Input::text(EXAMPLE)).unwrap().filesystem();
+        assert_eq!(files.size(), 48381165);
+        assert_eq!(files.total(100000), 95437);
+    }
+
+    #[test]
+    fn smallest_dir() {
+        let files = io::input_as_commands(&Input::text(EXAMPLE)).unwrap().filesystem();
+        assert_eq!(files.smallest_dir(files.size() - 40000000), Some(24933642));
+    }
+
+    #[test]
+    fn round_trip() {
+        let commands = io::input_as_commands(&Input::text(EXAMPLE)).unwrap();
+        assert_eq!(commands.to_string(), EXAMPLE);
+        assert_eq!(io::input_as_commands(&Input::text(&commands.to_string())).unwrap(), commands);
+    }
+
+    #[test]
+    fn skipped_line() {
+        let text = EXAMPLE.replacen("29116 f", "29116", 1);
+        error::take_skipped();
+        let files = io::input_as_commands(&Input::text(&text)).unwrap().filesystem();
+        assert_eq!(files.size(), 48381165 - 29116);
+        let skipped = error::take_skipped();
+        assert_eq!(skipped.len(), 1);
+        assert!(skipped[0].to_string().starts_with("line 10 "), "{}", skipped[0]);
+    }
+}
@@ -1,45 +1,70 @@
-#[derive(Copy, Clone, Debug)]
+use std::fmt;
+
+use itertools::Itertools;
+
+use crate::error::{self, Error, parse_lines};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 enum State{
     Rock,
     Paper,
     Scissors
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 enum Result{
     Draw,
     Win,
     Loss
 }
 
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 struct Turn{
     opp : State,
     own : State
 }
 
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 struct TurnResult{
     opp : State,
     res : Result
 }
 
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Game {
     turns : Vec<Turn>
 }
 
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Guide {
     turns : Vec<TurnResult>
 }
 
 impl State {
-    fn from_string(string : &str) -> Option<State> {
+    fn from_string(string : &str) -> error::Result<State> {
         match string {
-            "A" => Some(State::Rock),
-            "B" => Some(State::Paper),
-            "C" => Some(State::Scissors),
-            "X" => Some(State::Rock),
-            "Y" => Some(State::Paper),
-            "Z" => Some(State::Scissors),
-            _ => None
+            "A" => Ok(State::Rock),
+            "B" => Ok(State::Paper),
+            "C" => Ok(State::Scissors),
+            "X" => Ok(State::Rock),
+            "Y" => Ok(State::Paper),
+            "Z" => Ok(State::Scissors),
+            _ => Err(Error::line(string, "one of A, B, C, X, Y or Z"))
+        }
+    }
+
+    // The letters the opponent and the strategy guide use
+    fn letters(&self) -> (char, char) {
+        match self {
+            State::Rock => ('A', 'X'),
+            State::Paper => ('B', 'Y'),
+            State::Scissors => ('C', 'Z')
         }
     }
 
@@ -53,12 +78,20 @@ impl State {
 }
 
 impl Result {
-    fn from_string(string : &str) -> Option<Result> {
+    fn from_string(string : &str) -> error::Result<Result> {
         match string {
-            "Y" => Some(Result::Draw),
-            "Z" => Some(Result::Win),
-            "X" => Some(Result::Loss),
-            _ => None
+            "Y" => Ok(Result::Draw),
+            "Z" => Ok(Result::Win),
+            "X" => Ok(Result::Loss),
+            _ => Err(Error::line(string, "one of X, Y or Z"))
+        }
+    }
+
+    fn letter(&self) -> char {
+        match self {
+            Result::Draw => 'Y',
+            Result::Win => 'Z',
+            Result::Loss => 'X'
         }
     }
 
@@ -72,18 +105,17 @@ impl Result {
 }
 
 impl Turn {
-  fn from_line(line : &str) -> Option<Turn> {
-    match line.split_once(" ") {
-        Some((opp_str, self_str)) => {
-            match (State::from_string(opp_str), State::from_string(self_str)) {
-                (Some(opp), Some(self_state)) => Some(
-                    Turn {opp : opp, own :self_state}
-                ),
-                _ => None
-            }
-        },
-        _ => None
-    }
+  fn from_line(line : &str) -> error::Result<Turn> {
+    let (opp_str, self_str) = line.split_once(" ").ok_or(
+        Error::line(line, "two moves")
+    )?;
+    let opp = State::from_string(opp_str).map_err(
+        |err| err.within(line, opp_str)
+    )?;
+    let self_state = State::from_string(self_str).map_err(
+        |err| err.within(line, self_str)
+    )?;
+    Ok(Turn {opp : opp, own :self_state})
   }
 
   fn score(&self) -> usize {
@@ -106,18 +138,17 @@ impl Turn {
 }
 
 impl TurnResult {
-    fn from_line(line : &str) -> Option<TurnResult> {
-      match line.split_once(" ") {
-          Some((opp_str, res_str)) => {
-              match (State::from_string(opp_str), Result::from_string(res_str)) {
-                  (Some(opp), Some(res)) => Some(
-                      TurnResult {opp : opp, res :res}
-                  ),
-                  _ => None
-              }
-          },
-          _ => None
-      }
+    fn from_line(line : &str) -> error::Result<TurnResult> {
+      let (opp_str, res_str) = line.split_once(" ").ok_or(
+          Error::line(line, "a move and a result")
+      )?;
+      let opp = State::from_string(opp_str).map_err(
+          |err| err.within(line, opp_str)
+      )?;
+      let res = Result::from_string(res_str).map_err(
+          |err| err.within(line, res_str)
+      )?;
+      Ok(TurnResult {opp : opp, res :res})
     }
 
     fn turn(&self) -> Turn {
@@ -137,11 +168,12 @@ impl TurnResult {
 }
 
 impl Game {
-    pub fn from_lines(lines : &Vec<String>) -> Game {
-        let turns = lines.iter().filter_map(
-            |line| Turn::from_line(line)
-        ).collect();
-        Game { turns : turns }
+    pub fn from_lines(lines : &Vec<String>) -> error::Result<Game> {
+        let turns = parse_lines(
+            lines,
+            Turn::from_line
+        )?;
+        Ok(Game { turns : turns })
     }
 
     pub fn score(&self) -> usize {
@@ -152,11 +184,12 @@ impl Game {
 }
 
 impl Guide {
-    pub fn from_lines(lines : &Vec<String>) -> Guide {
-        let turns = lines.iter().filter_map(
-            |line| TurnResult::from_line(line)
-        ).collect();
-        Guide { turns : turns }
+    pub fn from_lines(lines : &Vec<String>) -> error::Result<Guide> {
+        let turns = parse_lines(
+            lines,
+            TurnResult::from_line
+        )?;
+        Ok(Guide { turns : turns })
     }
 
     pub fn score(&self) -> usize {
@@ -169,4 +202,49 @@ impl Guide {
         ).collect();
         Game {turns : turns}
     }
-}
\ No newline at end of file
+}
+
+impl fmt::Display for Game {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.turns.iter().map(
+            |turn| format!("{} {}", turn.opp.letters().0, turn.own.letters().1)
+        ).join("\n"))
+    }
+}
+
+impl fmt::Display for Guide {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.turns.iter().map(
+            |turn| format!("{} {}", turn.opp.letters().0, turn.res.letter())
+        ).join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::io::{self, Input};
+
+    const EXAMPLE : &str = include_str!("../../data/examples/day-2.txt");
+
+    #[test]
+    fn game_score() {
+        let game = io::input_as_game(&Input::text(EXAMPLE)).unwrap();
+        assert_eq!(game.score(), 15);
+    }
+
+    #[test]
+    fn guide_score() {
+        let guide = io::input_as_guide(&Input::text(EXAMPLE)).unwrap();
+        assert_eq!(guide.score(), 12);
+    }
+
+    #[test]
+    fn round_trip() {
+        let game = io::input_as_game(&Input::text(EXAMPLE)).unwrap();
+        let guide = io::input_as_guide(&Input::text(EXAMPLE)).unwrap();
+        assert_eq!(game.to_string(), EXAMPLE.trim_end());
+        assert_eq!(guide.to_string(), EXAMPLE.trim_end());
+        assert_eq!(io::input_as_game(&Input::text(&game.to_string())).unwrap(), game);
+        assert_eq!(io::input_as_guide(&Input::text(&guide.to_string())).unwrap(), guide);
+    }
+}
@@ -0,0 +1,909 @@
+use std::collections::HashSet;
+use std::fmt;
+
+use itertools::Itertools;
+
+use crate::geometry::Point2;
+use crate::grid::Grid;
+use crate::packets::Packet;
+
+// A small seeded generator (splitmix64) so the same seed always gives the
+// same input on every platform
+pub struct Rng {
+    state : u64
+}
+
+impl Rng {
+    pub fn new(seed : u64) -> Rng {
+        Rng { state : seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    // A number in lo..hi, hi must be above lo
+    pub fn range(&mut self, lo : i64, hi : i64) -> i64 {
+        lo + (self.next_u64() % (hi - lo) as u64) as i64
+    }
+
+    // An index below n, n must not be zero
+    pub fn below(&mut self, n : usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    pub fn chance(&mut self, percent : u64) -> bool {
+        self.next_u64() % 100 < percent
+    }
+
+    pub fn pick<'a, T>(&mut self, items : &'a [T]) -> &'a T {
+        &items[self.below(items.len())]
+    }
+
+    pub fn shuffle<T>(&mut self, items : &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.below(i + 1));
+        }
+    }
+}
+
+type Generator = fn(&mut Rng, usize) -> String;
+
+// The generator for a day and the size that gives an input about as big as
+// the real puzzle input
+fn generator(day : u8) -> Option<(Generator, usize)> {
+    match day {
+        1 => Some((elfs, 250)),
+        2 => Some((guide, 2500)),
+        3 => Some((rucksacks, 300)),
+        4 => Some((assignments, 1000)),
+        5 => Some((crates, 500)),
+        6 => Some((signal, 4096)),
+        7 => Some((commands, 180)),
+        8 => Some((forest, 99)),
+        9 => Some((directions, 2000)),
+        10 => Some((code, 240)),
+        11 => Some((monkeys, 8)),
+        12 => Some((heights, 160)),
+        13 => Some((packet_pairs, 150)),
+        14 => Some((cave, 150)),
+        15 => Some((sensors, 30)),
+        16 => Some((network, 15)),
+        17 => Some((jets, 10091)),
+        18 => Some((lava, 2000)),
+        19 => Some((blueprints, 30)),
+        20 => Some((ints, 5000)),
+        21 => Some((riddles, 70)),
+        22 => Some((map, 50)),
+        23 => Some((field, 72)),
+        24 => Some((valley, 120)),
+        25 => Some((snafus, 120)),
+        _ => None
+    }
+}
+
+// A random input for the day that the day's parser accepts and that both
+// parts can solve. The size is the main dimension of each day's input, the
+// number of lines, monkeys, valves or the side of a grid
+pub fn generate(day : u8, seed : u64, size : Option<usize>) -> Option<String> {
+    let (generator, default) = generator(day)?;
+    let mut rng = Rng::new(seed);
+    Some(generator(&mut rng, size.unwrap_or(default).max(1)))
+}
+
+// Lines of text with a newline after each
+fn lines<I, S>(lines : I) -> String
+  where I : IntoIterator<Item = S>, S : fmt::Display
+{
+    lines.into_iter().map(|line| format!("{}\n", line)).collect()
+}
+
+// Size is the number of elves
+pub fn elfs(rng : &mut Rng, size : usize) -> String {
+    (0..size).map(
+        |_| lines((0..rng.range(1, 16)).map(|_| rng.range(1000, 60000)).collect_vec())
+    ).join("\n")
+}
+
+// Size is the number of rounds
+pub fn guide(rng : &mut Rng, size : usize) -> String {
+    lines((0..size).map(
+        |_| format!("{} {}", rng.pick(&['A', 'B', 'C']), rng.pick(&['X', 'Y', 'Z']))
+    ).collect_vec())
+}
+
+// Size is the number of rucksacks, rounded up to whole groups of three.
+// The three in a group share only their badge and the halves of each
+// rucksack share a single item
+pub fn rucksacks(rng : &mut Rng, size : usize) -> String {
+    let mut sacks = Vec::new();
+    for _ in 0..size.div_ceil(3) {
+        let mut items : Vec<char> = ('a'..='z').chain('A'..='Z').collect();
+        rng.shuffle(&mut items);
+        let badge = items[0];
+        for pool in items[1..].chunks(17) {
+            let shared = if rng.chance(10) { badge } else { pool[0] };
+            let (left_pool, right_pool) = pool[1..].split_at(8);
+            let len = rng.range(4, 17) as usize;
+            let mut left = vec!(shared);
+            let mut right = vec!(shared);
+            if shared != badge {
+                if rng.chance(50) { left.push(badge) } else { right.push(badge) }
+            }
+            while left.len() < len {
+                left.push(*rng.pick(left_pool));
+            }
+            while right.len() < len {
+                right.push(*rng.pick(right_pool));
+            }
+            rng.shuffle(&mut left);
+            rng.shuffle(&mut right);
+            sacks.push(left.into_iter().chain(right).collect::<String>());
+        }
+    }
+    lines(sacks)
+}
+
+// Size is the number of pairs
+pub fn assignments(rng : &mut Rng, size : usize) -> String {
+    lines((0..size).map(|_| {
+        let a = rng.range(1, 100);
+        let b = rng.range(a, 100);
+        let c = rng.range(1, 100);
+        let d = rng.range(c, 100);
+        format!("{}-{},{}-{}", a, b, c, d)
+    }).collect_vec())
+}
+
+// Size is the number of moves, each only moves crates that are there
+pub fn crates(rng : &mut Rng, size : usize) -> String {
+    let mut stacks : Vec<Vec<char>> = (0..9).map(
+        |_| (0..rng.range(1, 9)).map(|_| *rng.pick(&('A'..='Z').collect_vec())).collect()
+    ).collect();
+    let height = stacks.iter().map(Vec::len).max().unwrap_or(0);
+    let mut drawing = (0..height).rev().map(
+        |row| stacks.iter().map(
+            |stack| stack.get(row).map(|ch| format!("[{}]", ch)).unwrap_or("   ".to_string())
+        ).join(" ")
+    ).collect_vec();
+    drawing.push((1..=stacks.len()).map(|i| format!(" {} ", i)).join(" "));
+
+    let mut moves = Vec::new();
+    for _ in 0..size {
+        let full = (0..stacks.len()).filter(|i| !stacks[*i].is_empty()).collect_vec();
+        let from = *rng.pick(&full);
+        let to = (from + 1 + rng.below(stacks.len() - 1)) % stacks.len();
+        let num = rng.range(1, stacks[from].len() as i64 + 1) as usize;
+        let kept = stacks[from].len() - num;
+        let moved = stacks[from].split_off(kept);
+        stacks[to].extend(moved);
+        moves.push(format!("move {} from {} to {}", num, from + 1, to + 1));
+    }
+    format!("{}\n{}", lines(drawing), lines(moves))
+}
+
+// Size is the length of the signal. The letters come from only ten until a
+// run of fourteen different letters, so the message marker is always found
+pub fn signal(rng : &mut Rng, size : usize) -> String {
+    let mut letters : Vec<char> = ('a'..='z').collect();
+    rng.shuffle(&mut letters);
+    let mut signal : Vec<char> = (0..size.max(28)).map(|_| *rng.pick(&letters[..10])).collect();
+    let marker = signal.len() / 2 + rng.below(signal.len() / 2 - 13);
+    signal.splice(marker..marker + 14, letters[..14].iter().copied());
+    lines([signal.into_iter().collect::<String>()])
+}
+
+// A directory with its files and the indices of its subdirectories
+struct Dir {
+    name : String,
+    files : Vec<(String, usize)>,
+    dirs : Vec<usize>
+}
+
+fn word(rng : &mut Rng) -> String {
+    (0..rng.range(1, 9)).map(|_| (b'a' + rng.below(26) as u8) as char).collect()
+}
+
+// Size is the number of directories. The disk is always full enough that a
+// directory has to be deleted
+pub fn commands(rng : &mut Rng, size : usize) -> String {
+    let mut dirs = vec!(Dir { name : "/".to_string(), files : Vec::new(), dirs : Vec::new() });
+    for i in 1..size {
+        let parent = rng.below(i);
+        let mut name = word(rng);
+        while dirs[parent].dirs.iter().any(|d| dirs[*d].name == name) {
+            name = word(rng);
+        }
+        dirs.push(Dir { name : name, files : Vec::new(), dirs : Vec::new() });
+        dirs[parent].dirs.push(i);
+    }
+    let mut total = 0;
+    for dir in dirs.iter_mut() {
+        for _ in 0..rng.below(5) {
+            let mut name = word(rng);
+            if rng.chance(50) {
+                name = format!("{}.{}", name, rng.pick(&["txt", "dat", "lst", "log"]));
+            }
+            let size = rng.range(1000, 300000) as usize;
+            total += size;
+            dir.files.push((name, size));
+        }
+    }
+    let full = rng.range(41000000, 70000000) as usize;
+    if total < full {
+        dirs[0].files.push(("swap.img".to_string(), full - total));
+    }
+
+    let mut out = Vec::new();
+    out.push("$ cd /".to_string());
+    list_dir(rng, &dirs, 0, &mut out);
+    lines(out)
+}
+
+fn list_dir(rng : &mut Rng, dirs : &[Dir], i : usize, out : &mut Vec<String>) {
+    out.push("$ ls".to_string());
+    let mut listing = dirs[i].dirs.iter().map(
+        |d| format!("dir {}", dirs[*d].name)
+    ).chain(dirs[i].files.iter().map(
+        |(name, size)| format!("{} {}", size, name)
+    )).collect_vec();
+    rng.shuffle(&mut listing);
+    out.extend(listing);
+    for d in dirs[i].dirs.iter() {
+        out.push(format!("$ cd {}", dirs[*d].name));
+        list_dir(rng, dirs, *d, out);
+        out.push("$ cd ..".to_string());
+    }
+}
+
+// Size is the side of the forest
+pub fn forest(rng : &mut Rng, size : usize) -> String {
+    lines((0..size).map(
+        |_| (0..size).map(|_| rng.range(0, 10).to_string()).collect::<String>()
+    ).collect_vec())
+}
+
+// Size is the number of motions
+pub fn directions(rng : &mut Rng, size : usize) -> String {
+    lines((0..size).map(
+        |_| format!("{} {}", rng.pick(&['U', 'D', 'L', 'R']), rng.range(1, 20))
+    ).collect_vec())
+}
+
+// Size is the number of cycles the program takes, at least the 240 needed
+// to draw the screen. The register is kept near the screen
+pub fn code(rng : &mut Rng, size : usize) -> String {
+    let mut program = Vec::new();
+    let (mut cycles, mut x) = (0, 1);
+    while cycles < size.max(240) {
+        if rng.chance(30) {
+            program.push("noop".to_string());
+            cycles += 1;
+        } else {
+            let mut val = rng.range(-15, 16);
+            if !(-2..43).contains(&(x + val)) {
+                val = -val;
+            }
+            x += val;
+            program.push(format!("addx {}", val));
+            cycles += 2;
+        }
+    }
+    lines(program)
+}
+
+const PRIMES : [usize; 8] = [2, 3, 5, 7, 11, 13, 17, 19];
+
+// Size is the number of monkeys, at least two. The divisors repeat after
+// eight monkeys to keep the worry levels from overflowing
+pub fn monkeys(rng : &mut Rng, size : usize) -> String {
+    let count = size.max(2);
+    let mut divisors = PRIMES.to_vec();
+    rng.shuffle(&mut divisors);
+    (0..count).map(|i| {
+        let items = (0..rng.range(1, 9)).map(|_| rng.range(50, 100)).join(", ");
+        let operation = match rng.below(5) {
+            0 => "old * old".to_string(),
+            1 | 2 => format!("old + {}", rng.range(1, 9)),
+            _ => format!("old * {}", rng.range(2, 20))
+        };
+        let if_true = (i + 1 + rng.below(count - 1)) % count;
+        let mut if_false = (i + 1 + rng.below(count - 1)) % count;
+        if if_false == if_true && count > 2 {
+            if_false = (0..count).find(|j| *j != i && *j != if_true).unwrap_or(if_false);
+        }
+        format!(
+            "Monkey {}:\n  Starting items: {}\n  Operation: new = {}\n  Test: divisible by {}\n    If true: throw to monkey {}\n    If false: throw to monkey {}\n",
+            i, items, operation, divisors[i % divisors.len()], if_true, if_false
+        )
+    }).join("\n")
+}
+
+// Size is the width, at least 26 so the heights can climb one a column.
+// The top row climbs steadily from the start to the end so there is always
+// a path, the rest is rougher
+pub fn heights(rng : &mut Rng, size : usize) -> String {
+    let width = size.max(26);
+    let height = (width / 4).max(2);
+    lines((0..height).map(|y| (0..width).map(|x| {
+        let base = (x * 26 / width) as i64;
+        if y == 0 && x == 0 {
+            'S'
+        } else if y == 0 && x == width - 1 {
+            'E'
+        } else if y == 0 {
+            (b'a' + base as u8) as char
+        } else {
+            (b'a' + rng.range((base - 3).max(0), base + 1) as u8) as char
+        }
+    }).collect::<String>()).collect_vec())
+}
+
+fn packet(rng : &mut Rng, depth : usize) -> Packet {
+    Packet::List((0..rng.below(6)).map(
+        |_| if depth == 0 || rng.chance(60) {
+            Packet::Num(rng.range(0, 11) as usize)
+        } else {
+            packet(rng, depth - 1)
+        }
+    ).collect())
+}
+
+// A copy of the packet with one change somewhere, so the pair only differs
+// deep inside
+fn mutate(rng : &mut Rng, packet : &Packet) -> Packet {
+    match packet {
+        Packet::Num(_) => Packet::Num(rng.range(0, 11) as usize),
+        Packet::List(items) if items.is_empty() || rng.chance(20) => packet_like(rng, items),
+        Packet::List(items) => {
+            let i = rng.below(items.len());
+            let mut items = items.clone();
+            items[i] = mutate(rng, &items[i]);
+            Packet::List(items)
+        }
+    }
+}
+
+fn packet_like(rng : &mut Rng, items : &[Packet]) -> Packet {
+    let mut items = items.to_vec();
+    if items.is_empty() || rng.chance(50) {
+        items.push(Packet::Num(rng.range(0, 11) as usize));
+    } else {
+        items.pop();
+    }
+    Packet::List(items)
+}
+
+// Packets such as [2] compare equal to a divider and would be counted as
+// one when sorting
+fn is_divider(packet : &Packet) -> bool {
+    let (div1, div2) = Packet::dividers();
+    *packet == div1 || *packet == div2
+}
+
+// Size is the number of pairs, none of the packets looks like a divider
+pub fn packet_pairs(rng : &mut Rng, size : usize) -> String {
+    let mut pairs = Vec::new();
+    while pairs.len() < size {
+        let left = packet(rng, 4);
+        let right = if rng.chance(60) { mutate(rng, &left) } else { packet(rng, 4) };
+        if !is_divider(&left) && !is_divider(&right) {
+            pairs.push(format!("{}\n{}\n", left, right));
+        }
+    }
+    pairs.join("\n")
+}
+
+// Rock as a grid of the columns paths can cover, with a free column on
+// either side, which sand that reaches it falls straight down
+const CAVE_LEFT : i64 = 439;
+const CAVE_WIDTH : usize = 123;
+const CAVE_HEIGHT : usize = 182;
+
+fn draw_rock(rock : &mut Grid<bool>, points : &[(i64, i64)]) {
+    for ((x1, y1), (x2, y2)) in points.iter().tuple_windows() {
+        for x in *x1.min(x2)..=*x1.max(x2) {
+            for y in *y1.min(y2)..=*y1.max(y2) {
+                rock[Point2::new(x - CAVE_LEFT, y)] = true;
+            }
+        }
+    }
+}
+
+// Sand from the source gets past the lowest rock if some run of falls,
+// each straight down or diagonally, misses every rock on the way
+fn sand_escapes(rock : &Grid<bool>) -> bool {
+    let mut reach = vec!(false; CAVE_WIDTH);
+    reach[(500 - CAVE_LEFT) as usize] = true;
+    for y in 1..CAVE_HEIGHT as i64 {
+        if reach[0] || reach[CAVE_WIDTH - 1] {
+            return true;
+        }
+        reach = (0..CAVE_WIDTH).map(
+            |x| !rock[Point2::new(x as i64, y)] && reach[x.saturating_sub(1)..=(x + 1).min(CAVE_WIDTH - 1)].contains(&true)
+        ).collect();
+        if !reach.contains(&true) {
+            return false;
+        }
+    }
+    true
+}
+
+// Size is the number of rock paths, all below the source of the sand. A
+// path that would leave the sand no way past the rock is thrown away, as
+// then the source clogs before any sand falls into the void
+pub fn cave(rng : &mut Rng, size : usize) -> String {
+    let mut rock = Grid::new(CAVE_WIDTH, CAVE_HEIGHT, false);
+    let mut paths = Vec::new();
+    while paths.len() < size {
+        let (mut x, mut y) = (rng.range(460, 540), rng.range(13, 170));
+        let mut points = vec!((x, y));
+        let across = rng.chance(50);
+        for i in 0..rng.range(1, 6) {
+            if (i % 2 == 0) == across {
+                x = (x + rng.range(-10, 11)).clamp(440, 560);
+            } else {
+                y = (y + rng.range(-10, 11)).clamp(1, 180);
+            }
+            points.push((x, y));
+        }
+        let mut more = rock.clone();
+        draw_rock(&mut more, &points);
+        if sand_escapes(&more) {
+            rock = more;
+            paths.push(points.iter().map(|(x, y)| format!("{},{}", x, y)).join(" -> "));
+        }
+    }
+    lines(paths)
+}
+
+const BEACON_RANGE : i64 = 4000000;
+
+fn sensor(sx : i64, sy : i64, bx : i64, by : i64) -> String {
+    format!("Sensor at x={}, y={}: closest beacon is at x={}, y={}", sx, sy, bx, by)
+}
+
+// Size is the number of sensors, at least four. Four far off sensors cover
+// everything in range but a hidden point, the rest each stop short of it
+pub fn sensors(rng : &mut Rng, size : usize) -> String {
+    let (px, py) = (rng.range(0, BEACON_RANGE + 1), rng.range(0, BEACON_RANGE + 1));
+    let far = BEACON_RANGE + 1;
+    let mut sensors = Vec::new();
+    for (dx, dy) in [(1, 1), (1, -1), (-1, 1), (-1, -1)] {
+        let (sx, sy) = (px + dx * far, py + dy * far);
+        sensors.push(sensor(sx, sy, sx, sy + dy * (2 * far - 1)));
+    }
+    while sensors.len() < size {
+        let (sx, sy) = (rng.range(0, BEACON_RANGE + 1), rng.range(0, BEACON_RANGE + 1));
+        let to_hidden = (sx - px).abs() + (sy - py).abs();
+        if to_hidden < 2 {
+            continue;
+        }
+        let radius = rng.range(1, to_hidden);
+        let dx = rng.range(-radius, radius + 1);
+        let dy = (radius - dx.abs()) * if rng.chance(50) { 1 } else { -1 };
+        sensors.push(sensor(sx, sy, sx + dx, sy + dy));
+    }
+    rng.shuffle(&mut sensors);
+    lines(sensors)
+}
+
+fn valve_name(i : usize) -> String {
+    let letter = |n : usize| (b'A' + (n % 26) as u8) as char;
+    format!("{}{}", letter(i / 26), letter(i))
+}
+
+// Size is the number of valves with a flow, at most 63. There are twice as
+// many empty valves joining them up
+pub fn network(rng : &mut Rng, size : usize) -> String {
+    let flowing = size.min(63);
+    let count = 1 + flowing * 3;
+    let mut names : Vec<String> = (1..26 * 26).map(valve_name).collect();
+    rng.shuffle(&mut names);
+    names.insert(0, valve_name(0));
+    names.truncate(count);
+
+    let mut tunnels : Vec<Vec<usize>> = vec!(Vec::new(); count);
+    let mut join = |a : usize, b : usize| {
+        if a != b && !tunnels[a].contains(&b) {
+            tunnels[a].push(b);
+            tunnels[b].push(a);
+        }
+    };
+    for i in 1..count {
+        join(i, rng.below(i));
+    }
+    for _ in 0..count / 3 {
+        join(rng.below(count), rng.below(count));
+    }
+    let mut flows = vec!(0; count);
+    let mut valves = (1..count).collect_vec();
+    rng.shuffle(&mut valves);
+    for valve in valves.into_iter().take(flowing) {
+        flows[valve] = rng.range(3, 26);
+    }
+
+    let mut out = (0..count).map(|i| {
+        let to = tunnels[i].iter().map(|j| &names[*j]).join(", ");
+        if tunnels[i].len() == 1 {
+            format!("Valve {} has flow rate={}; tunnel leads to valve {}", names[i], flows[i], to)
+        } else {
+            format!("Valve {} has flow rate={}; tunnels lead to valves {}", names[i], flows[i], to)
+        }
+    }).collect_vec();
+    rng.shuffle(&mut out);
+    lines(out)
+}
+
+// Size is the number of jets
+pub fn jets(rng : &mut Rng, size : usize) -> String {
+    lines([(0..size).map(|_| if rng.chance(50) { '<' } else { '>' }).collect::<String>()])
+}
+
+// Size is the number of cubes, at most half of the 20 by 20 by 20 space
+pub fn lava(rng : &mut Rng, size : usize) -> String {
+    let mut cubes = HashSet::new();
+    let mut out = Vec::new();
+    while out.len() < size.min(4000) {
+        let cube = (rng.range(1, 21), rng.range(1, 21), rng.range(1, 21));
+        if cubes.insert(cube) {
+            out.push(format!("{},{},{}", cube.0, cube.1, cube.2));
+        }
+    }
+    lines(out)
+}
+
+// Size is the number of blueprints
+pub fn blueprints(rng : &mut Rng, size : usize) -> String {
+    lines((1..=size).map(|i| format!(
+        "Blueprint {}: Each ore robot costs {} ore. Each clay robot costs {} ore. Each obsidian robot costs {} ore and {} clay. Each geode robot costs {} ore and {} obsidian.",
+        i, rng.range(2, 5), rng.range(2, 5), rng.range(2, 5), rng.range(5, 21), rng.range(2, 5), rng.range(5, 21)
+    )).collect_vec())
+}
+
+// Size is the number of values, exactly one of them zero
+pub fn ints(rng : &mut Rng, size : usize) -> String {
+    let mut values : Vec<i64> = (0..size).map(|_| {
+        let value = rng.range(-10000, 10000);
+        if value == 0 { 1 } else { value }
+    }).collect();
+    let zero = rng.below(values.len());
+    values[zero] = 0;
+    lines(values)
+}
+
+// The jobs of the monkeys built so far, names are never reused
+struct Jobs {
+    lines : Vec<String>,
+    names : HashSet<String>
+}
+
+impl Jobs {
+    fn name(&mut self, rng : &mut Rng) -> String {
+        loop {
+            let name : String = (0..4).map(|_| (b'a' + rng.below(26) as u8) as char).collect();
+            if name != "root" && name != "humn" && self.names.insert(name.clone()) {
+                return name;
+            }
+        }
+    }
+
+    fn job(&mut self, rng : &mut Rng, job : String) -> String {
+        let name = self.name(rng);
+        self.lines.push(format!("{}: {}", name, job));
+        name
+    }
+
+    // A monkey yelling a positive value, every division along the way is
+    // exact
+    fn yelling(&mut self, rng : &mut Rng, value : i64, depth : usize) -> String {
+        if depth == 0 || rng.chance(20) {
+            return self.job(rng, value.to_string());
+        }
+        let factors = (2..10).filter(|d| value % d == 0).collect_vec();
+        let (a, op, b) = match rng.below(4) {
+            0 if value >= 2 => {
+                let a = rng.range(1, value);
+                (a, '+', value - a)
+            },
+            1 if !factors.is_empty() => {
+                let b = *rng.pick(&factors);
+                (value / b, '*', b)
+            },
+            2 if value < 1 << 40 => {
+                let b = rng.range(2, 10);
+                (value * b, '/', b)
+            },
+            _ => {
+                let b = rng.range(1, 1000);
+                (value + b, '-', b)
+            }
+        };
+        let a = self.yelling(rng, a, depth - 1);
+        let b = self.yelling(rng, b, depth - 1);
+        self.job(rng, format!("{} {} {}", a, op, b))
+    }
+
+    // The monkeys between the root and the human, which has to yell answer
+    // for this monkey to yell target. Only adds, subtracts and multiplies by
+    // a constant on the way so the values stay whole whatever the human yells
+    fn path(&mut self, rng : &mut Rng, target : i64, depth : usize, answer : &mut i64) -> String {
+        if depth == 0 {
+            *answer = target;
+            return "humn".to_string();
+        }
+        let factors = (2..10).filter(|d| target % d == 0).collect_vec();
+        let (child, op, other, first) = match rng.below(4) {
+            0 if target >= 2 => {
+                let other = rng.range(1, target);
+                (target - other, '+', other, rng.chance(50))
+            },
+            1 if !factors.is_empty() => {
+                let other = *rng.pick(&factors);
+                (target / other, '*', other, rng.chance(50))
+            },
+            2 => {
+                let other = target + rng.range(1, 1000);
+                (other - target, '-', other, false)
+            },
+            _ => {
+                let other = rng.range(1, 1000);
+                (target + other, '-', other, true)
+            }
+        };
+        let child = self.path(rng, child, depth - 1, answer);
+        let other = self.yelling(rng, other, 4);
+        if first {
+            self.job(rng, format!("{} {} {}", child, op, other))
+        } else {
+            self.job(rng, format!("{} {} {}", other, op, child))
+        }
+    }
+}
+
+// Size is the number of monkeys between the root and the human
+pub fn riddles(rng : &mut Rng, size : usize) -> String {
+    let mut jobs = Jobs { lines : Vec::new(), names : HashSet::new() };
+    let target = rng.range(1 << 30, 1 << 40);
+    let mut answer = 0;
+    let left = jobs.path(rng, target, size, &mut answer);
+    let right = jobs.yelling(rng, target, 6);
+    log::debug!("The human has to yell {}", answer);
+    jobs.lines.push(format!("root: {} + {}", left, right));
+    jobs.lines.push(format!("humn: {}", rng.range(1, 5000)));
+    rng.shuffle(&mut jobs.lines);
+    lines(jobs.lines)
+}
+
+// The eleven nets of a cube, faces marked by # in rows separated by /
+const NETS : [&str; 11] = [
+    "#.../####/#...", "#.../####/.#..", "#.../####/..#.", "#.../####/...#",
+    ".#../####/.#..", ".#../####/..#.",
+    "##../.###/.#..", "##../.###/..#.", "##../.###/...#",
+    "##../.##./..##", "###../..###"
+];
+
+// The face positions of a net turned or flipped one of eight ways
+fn net(index : usize, transform : usize) -> Vec<(i64, i64)> {
+    let faces = NETS[index].split('/').enumerate().flat_map(
+        |(y, row)| row.char_indices().filter(|(_, ch)| *ch == '#').map(
+            move |(x, _)| (x as i64, y as i64)
+        )
+    ).map(|(x, y)| {
+        let (x, y) = if transform & 1 == 1 { (y, x) } else { (x, y) };
+        let x = if transform & 2 == 2 { -x } else { x };
+        let y = if transform & 4 == 4 { -y } else { y };
+        (x, y)
+    }).collect_vec();
+    let min_x = faces.iter().map(|f| f.0).min().unwrap_or(0);
+    let min_y = faces.iter().map(|f| f.1).min().unwrap_or(0);
+    faces.into_iter().map(|(x, y)| (x - min_x, y - min_y)).collect()
+}
+
+fn net_map(rng : &mut Rng, faces : &[(i64, i64)], size : usize, moves : usize) -> String {
+    let side = size as i64;
+    let rows = faces.iter().map(|f| f.1).max().unwrap_or(0) + 1;
+    let cols = faces.iter().map(|f| f.0).max().unwrap_or(0) + 1;
+    let mut map = (0..rows * side).map(|y| (0..cols * side).map(
+        |x| if faces.contains(&(x / side, y / side)) {
+            if rng.chance(12) { '#' } else { '.' }
+        } else {
+            ' '
+        }
+    ).collect::<String>().trim_end().to_string()).collect_vec();
+    let start = map[0].find(|ch| ch != ' ').unwrap_or(0);
+    map[0].replace_range(start..start + 1, ".");
+    let path = (0..moves).map(
+        |_| format!("{}{}", rng.range(1, 51), rng.pick(&["L", "R"]))
+    ).collect::<String>();
+    format!("{}\n{}{}\n", lines(map), path, rng.range(1, 51))
+}
+
+// Size is the side of each face of the cube the map folds into
+pub fn map(rng : &mut Rng, size : usize) -> String {
+    let faces = net(rng.below(NETS.len()), rng.below(8));
+    net_map(rng, &faces, size, size * 40)
+}
+
+// Size is the side of the square of ground
+pub fn field(rng : &mut Rng, size : usize) -> String {
+    lines((0..size).map(
+        |_| (0..size).map(|_| if rng.chance(45) { '#' } else { '.' }).collect::<String>()
+    ).collect_vec())
+}
+
+// Size is the width inside the walls, the valley is a fifth as tall
+pub fn valley(rng : &mut Rng, size : usize) -> String {
+    let width = size.max(2);
+    let height = (width / 5).max(3);
+    let wall = |gap : usize| (0..width + 2).map(|x| if x == gap { '.' } else { '#' }).collect::<String>();
+    let mut rows = vec!(wall(1));
+    for _ in 0..height {
+        let inner = (0..width).map(
+            |_| if rng.chance(40) { *rng.pick(&['^', '>', 'v', '<']) } else { '.' }
+        ).collect::<String>();
+        rows.push(format!("#{}#", inner));
+    }
+    rows.push(wall(width));
+    lines(rows)
+}
+
+// Size is the number of fuel requirements
+pub fn snafus(rng : &mut Rng, size : usize) -> String {
+    lines((0..size).map(|_| {
+        let digits = rng.range(1, 20) as u32;
+        crate::snafu::Snafu::from(rng.range(1, 5i64.pow(digits) + 1))
+    }).collect_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::days;
+    use crate::io::{self, Input};
+    use crate::map::Map;
+    use crate::solution::Part;
+
+    // Small inputs that solve quickly in a debug build
+    const SIZES : [usize; 25] = [
+        20, 30, 30, 30, 40, 200, 30, 15, 100, 240,
+        4, 40, 20, 30, 8, 6, 60, 100, 2, 50,
+        12, 6, 12, 20, 20
+    ];
+
+    #[test]
+    fn seeded() {
+        for day in 1..=25 {
+            assert_eq!(generate(day, 7, Some(10)), generate(day, 7, Some(10)));
+        }
+        assert_ne!(generate(17, 7, None), generate(17, 8, None));
+        assert_eq!(generate(26, 7, None), None);
+    }
+
+    #[test]
+    fn every_day_solves() {
+        let registry = days::registry();
+        for (day, size) in (1..=25).zip(SIZES) {
+            for seed in 0..3 {
+                let input = Input::text(&generate(day, seed, Some(size)).unwrap());
+                let puzzle = registry.get(day).unwrap();
+                for part in puzzle.parts() {
+                    let answer = puzzle.run(part, &input);
+                    assert!(answer.is_ok(), "day {} part {} seed {}: {:?}", day, part, seed, answer);
+                }
+            }
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn every_day_dumps() {
+        let registry = days::registry();
+        for (day, size) in (1..=25).zip(SIZES) {
+            let input = Input::text(&generate(day, 1, Some(size)).unwrap());
+            assert!(registry.get(day).unwrap().dump(&input).is_ok(), "day {}", day);
+        }
+    }
+
+    // Parse a generated input, write it out and parse it again
+    fn rewrites<T, F>(day : u8, parse : F)
+      where T : fmt::Display + fmt::Debug + PartialEq, F : Fn(&Input) -> crate::error::Result<T>
+    {
+        for seed in 0..3 {
+            let model = parse(&Input::text(&generate(day, seed, Some(SIZES[day as usize - 1])).unwrap())).unwrap();
+            assert_eq!(parse(&Input::text(&model.to_string())).unwrap(), model, "day {} seed {}", day, seed);
+        }
+    }
+
+    #[test]
+    fn every_model_rewrites() {
+        rewrites(1, io::input_as_elfs);
+        rewrites(2, io::input_as_game);
+        rewrites(2, io::input_as_guide);
+        rewrites(7, io::input_as_commands);
+        rewrites(8, io::input_as_forest);
+        rewrites(9, io::input_as_directions);
+        rewrites(10, io::input_as_code);
+        rewrites(11, io::input_as_monkeys);
+        rewrites(12, io::input_as_heights);
+        rewrites(14, io::input_as_cave);
+        rewrites(15, io::input_as_sensors);
+        rewrites(16, io::input_as_network);
+        rewrites(17, io::input_as_jets);
+        rewrites(18, io::input_as_lava);
+        rewrites(21, io::input_as_riddles);
+        rewrites(22, io::input_as_map);
+        rewrites(23, io::input_as_field);
+        rewrites(24, io::input_as_valley);
+    }
+
+    // Sand always gets past the rock, so part one never runs into a clogged
+    // source and stops with sand falling into the void
+    #[test]
+    fn sand_falls_through() {
+        let registry = days::registry();
+        for seed in 0..31 {
+            let mut rock = Grid::new(CAVE_WIDTH, CAVE_HEIGHT, false);
+            for line in generate(14, seed, None).unwrap().lines() {
+                let points : Vec<(i64, i64)> = line.split(" -> ").map(|point| {
+                    let (x, y) = point.split_once(',').unwrap();
+                    (x.parse().unwrap(), y.parse().unwrap())
+                }).collect();
+                draw_rock(&mut rock, &points);
+            }
+            assert!(sand_escapes(&rock), "seed {}", seed);
+        }
+        // Seeds that used to clog the source before any sand reached the void
+        for seed in [2, 6, 29] {
+            let input = Input::text(&generate(14, seed, None).unwrap());
+            assert!(registry.get(14).unwrap().run(Part::One, &input).is_ok(), "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn hidden_beacon() {
+        for seed in 0..5 {
+            let sensors = io::input_as_sensors(&Input::text(&generate(15, seed, None).unwrap())).unwrap();
+            let pos = sensors.find_beacon(0, BEACON_RANGE, 0, BEACON_RANGE).unwrap();
+            assert!(sensors.find_beacon(0, pos.x - 1, 0, BEACON_RANGE).is_none());
+            assert!(sensors.find_beacon(pos.x + 1, BEACON_RANGE, 0, BEACON_RANGE).is_none());
+        }
+    }
+
+    #[test]
+    fn riddle_balances() {
+        let registry = days::registry();
+        for seed in 0..5 {
+            let text = generate(21, seed, None).unwrap();
+            let answer = registry.run(21, Part::Two, &Input::text(&text)).unwrap().to_string();
+            let solved = text.lines().map(
+                |line| match line.split_once(": ") {
+                    Some(("humn", _)) => format!("humn: {}", answer),
+                    Some(("root", job)) => format!("root: {}", job.replace('+', "-")),
+                    _ => line.to_string()
+                }
+            ).join("\n");
+            let riddle = io::input_as_riddles(&Input::text(&solved)).unwrap();
+            assert!(answer.parse::<i64>().unwrap() > 0);
+            assert_eq!(riddle.solve("root"), Some(0));
+        }
+    }
+
+    #[test]
+    fn nets_fold() {
+        let mut rng = Rng::new(1);
+        for index in 0..NETS.len() {
+            for transform in 0..8 {
+                let text = net_map(&mut rng, &net(index, transform), 3, 5);
+                let map = io::input_as_map(&Input::text(&text)).unwrap();
+                assert!(map.simulate_cube().is_ok(), "net {} turned {}:\n{}", index, transform, text);
+            }
+        }
+        let lines : Vec<String> = net_map(&mut rng, &[(0, 0), (1, 0)], 3, 5).lines().map(String::from).collect();
+        assert!(Map::from_lines(&lines).unwrap().simulate_cube().is_err());
+    }
+}
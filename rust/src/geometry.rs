@@ -0,0 +1,264 @@
+use std::fmt;
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+
+// Point in the plane, on grids y increases down the page
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Point2 {
+    pub x : i64,
+    pub y : i64
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Point3 {
+    pub x : i64,
+    pub y : i64,
+    pub z : i64
+}
+
+// Compass direction on a grid, north is up the page
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Direction {
+    North,
+    East,
+    South,
+    West
+}
+
+impl Point2 {
+    pub const ORIGIN : Point2 = Point2 { x : 0, y : 0 };
+
+    pub const fn new(x : i64, y : i64) -> Point2 {
+        Point2 { x : x, y : y }
+    }
+
+    pub fn manhattan(&self, other : Point2) -> i64 {
+        (self.x - other.x).abs() + (self.y - other.y).abs()
+    }
+
+    pub fn chebyshev(&self, other : Point2) -> i64 {
+        (self.x - other.x).abs().max((self.y - other.y).abs())
+    }
+
+    // Each coordinate replaced by its sign
+    pub fn signum(&self) -> Point2 {
+        Point2::new(self.x.signum(), self.y.signum())
+    }
+
+    pub fn step(&self, dir : Direction) -> Point2 {
+        *self + dir.step()
+    }
+
+    pub fn neighbours_4(self) -> impl Iterator<Item = Point2> {
+        Direction::all().into_iter().map(move |dir| self.step(dir))
+    }
+
+    // The orthogonal and diagonal neighbours clockwise from north
+    pub fn neighbours_8(self) -> impl Iterator<Item = Point2> {
+        Direction::all().into_iter().flat_map(
+            move |dir| [self.step(dir), self.step(dir).step(dir.right())]
+        )
+    }
+}
+
+impl Point3 {
+    pub const ORIGIN : Point3 = Point3 { x : 0, y : 0, z : 0 };
+
+    pub const fn new(x : i64, y : i64, z : i64) -> Point3 {
+        Point3 { x : x, y : y, z : z }
+    }
+
+    pub fn manhattan(&self, other : Point3) -> i64 {
+        (self.x - other.x).abs() + (self.y - other.y).abs() + (self.z - other.z).abs()
+    }
+
+    pub fn chebyshev(&self, other : Point3) -> i64 {
+        (self.x - other.x).abs().max((self.y - other.y).abs()).max((self.z - other.z).abs())
+    }
+
+    pub fn dot(&self, other : Point3) -> i64 {
+        self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    // The points sharing a face with this one
+    pub fn neighbours_6(self) -> impl Iterator<Item = Point3> {
+        [
+            Point3::new(1, 0, 0), Point3::new(-1, 0, 0),
+            Point3::new(0, 1, 0), Point3::new(0, -1, 0),
+            Point3::new(0, 0, 1), Point3::new(0, 0, -1)
+        ].into_iter().map(move |step| self + step)
+    }
+}
+
+impl Direction {
+    // Clockwise from north
+    pub fn all() -> [Direction; 4] {
+        [Direction::North, Direction::East, Direction::South, Direction::West]
+    }
+
+    pub fn step(&self) -> Point2 {
+        match self {
+            Direction::North => Point2::new(0, -1),
+            Direction::East => Point2::new(1, 0),
+            Direction::South => Point2::new(0, 1),
+            Direction::West => Point2::new(-1, 0)
+        }
+    }
+
+    pub fn left(&self) -> Direction {
+        match self {
+            Direction::North => Direction::West,
+            Direction::East => Direction::North,
+            Direction::South => Direction::East,
+            Direction::West => Direction::South
+        }
+    }
+
+    pub fn right(&self) -> Direction {
+        match self {
+            Direction::North => Direction::East,
+            Direction::East => Direction::South,
+            Direction::South => Direction::West,
+            Direction::West => Direction::North
+        }
+    }
+
+    pub fn reverse(&self) -> Direction {
+        self.left().left()
+    }
+}
+
+impl fmt::Display for Point2 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{},{}", self.x, self.y)
+    }
+}
+
+impl fmt::Display for Point3 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{},{},{}", self.x, self.y, self.z)
+    }
+}
+
+impl Add for Point2 {
+    type Output = Point2;
+
+    fn add(self, other : Point2) -> Point2 {
+        Point2::new(self.x + other.x, self.y + other.y)
+    }
+}
+
+impl Sub for Point2 {
+    type Output = Point2;
+
+    fn sub(self, other : Point2) -> Point2 {
+        Point2::new(self.x - other.x, self.y - other.y)
+    }
+}
+
+impl Mul<i64> for Point2 {
+    type Output = Point2;
+
+    fn mul(self, scale : i64) -> Point2 {
+        Point2::new(self.x * scale, self.y * scale)
+    }
+}
+
+impl Neg for Point2 {
+    type Output = Point2;
+
+    fn neg(self) -> Point2 {
+        Point2::new(-self.x, -self.y)
+    }
+}
+
+impl AddAssign for Point2 {
+    fn add_assign(&mut self, other : Point2) {
+        *self = *self + other;
+    }
+}
+
+impl SubAssign for Point2 {
+    fn sub_assign(&mut self, other : Point2) {
+        *self = *self - other;
+    }
+}
+
+impl Add for Point3 {
+    type Output = Point3;
+
+    fn add(self, other : Point3) -> Point3 {
+        Point3::new(self.x + other.x, self.y + other.y, self.z + other.z)
+    }
+}
+
+impl Sub for Point3 {
+    type Output = Point3;
+
+    fn sub(self, other : Point3) -> Point3 {
+        Point3::new(self.x - other.x, self.y - other.y, self.z - other.z)
+    }
+}
+
+impl Mul<i64> for Point3 {
+    type Output = Point3;
+
+    fn mul(self, scale : i64) -> Point3 {
+        Point3::new(self.x * scale, self.y * scale, self.z * scale)
+    }
+}
+
+impl Neg for Point3 {
+    type Output = Point3;
+
+    fn neg(self) -> Point3 {
+        Point3::new(-self.x, -self.y, -self.z)
+    }
+}
+
+impl AddAssign for Point3 {
+    fn add_assign(&mut self, other : Point3) {
+        *self = *self + other;
+    }
+}
+
+impl SubAssign for Point3 {
+    fn sub_assign(&mut self, other : Point3) {
+        *self = *self - other;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn distances() {
+        let a = Point2::new(1, -2);
+        let b = Point2::new(-3, 4);
+        assert_eq!(a.manhattan(b), 10);
+        assert_eq!(a.chebyshev(b), 6);
+        assert_eq!(Point3::new(1, 2, 3).manhattan(Point3::ORIGIN), 6);
+        assert_eq!(Point3::new(1, 2, 3).dot(Point3::new(-1, 0, 2)), 5);
+        assert_eq!((b - a).signum(), Point2::new(-1, 1));
+    }
+
+    #[test]
+    fn turning() {
+        for dir in Direction::all() {
+            assert_eq!(dir.left().right(), dir);
+            assert_eq!(dir.reverse().step(), -dir.step());
+        }
+        assert_eq!(Point2::ORIGIN.step(Direction::North), Point2::new(0, -1));
+    }
+
+    #[test]
+    fn neighbours() {
+        let all : Vec<Point2> = Point2::ORIGIN.neighbours_8().collect();
+        assert_eq!(all.len(), 8);
+        assert!(all.iter().all(|pt| pt.chebyshev(Point2::ORIGIN) == 1));
+        assert_eq!(Point3::ORIGIN.neighbours_6().filter(|pt| pt.manhattan(Point3::ORIGIN) == 1).count(), 6);
+    }
+}
@@ -0,0 +1,438 @@
+use std::collections::HashMap;
+use std::cmp::{min, max};
+use std::ops::{Index, IndexMut};
+
+use crate::error::{Error, Result};
+use crate::geometry::Point2;
+
+// Inclusive box of positions, y increases down the page
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Bounds {
+    pub min : Point2,
+    pub max : Point2
+}
+
+// Dense grid with the top left at (0, 0), stored row by row
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Grid<T> {
+    width : usize,
+    height : usize,
+    cells : Vec<T>
+}
+
+// Grid holding only the positions that have been set, the bounds grow as
+// cells are inserted and are not shrunk when they are removed
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SparseGrid<T> {
+    cells : HashMap<Point2, T>,
+    bounds : Option<Bounds>
+}
+
+// The lines of a character map with any trailing blank lines dropped
+fn map_lines<S>(lines : &[S]) -> &[S]
+  where S : AsRef<str>
+{
+    let len = lines.iter().rposition(
+        |line| !line.as_ref().is_empty()
+    ).map(|i| i + 1).unwrap_or(0);
+    &lines[..len]
+}
+
+impl Bounds {
+    pub fn new(min : Point2, max : Point2) -> Bounds {
+        Bounds { min : min, max : max }
+    }
+
+    pub fn point(pos : Point2) -> Bounds {
+        Bounds::new(pos, pos)
+    }
+
+    pub fn include(&self, pos : Point2) -> Bounds {
+        Bounds::new(
+            Point2::new(min(self.min.x, pos.x), min(self.min.y, pos.y)),
+            Point2::new(max(self.max.x, pos.x), max(self.max.y, pos.y))
+        )
+    }
+
+    pub fn contains(&self, pos : Point2) -> bool {
+        self.min.x <= pos.x && pos.x <= self.max.x &&
+        self.min.y <= pos.y && pos.y <= self.max.y
+    }
+
+    pub fn width(&self) -> usize {
+        (self.max.x - self.min.x + 1) as usize
+    }
+
+    pub fn height(&self) -> usize {
+        (self.max.y - self.min.y + 1) as usize
+    }
+
+    pub fn area(&self) -> usize {
+        self.width() * self.height()
+    }
+
+    // Positions row by row from the top left
+    pub fn positions(&self) -> impl Iterator<Item = Point2> {
+        let (min_x, max_x) = (self.min.x, self.max.x);
+        (self.min.y..=self.max.y).flat_map(
+            move |y| (min_x..=max_x).map(move |x| Point2::new(x, y))
+        )
+    }
+
+    pub fn row(&self, y : i64) -> impl DoubleEndedIterator<Item = Point2> {
+        (self.min.x..=self.max.x).map(move |x| Point2::new(x, y))
+    }
+
+    pub fn column(&self, x : i64) -> impl DoubleEndedIterator<Item = Point2> {
+        (self.min.y..=self.max.y).map(move |y| Point2::new(x, y))
+    }
+
+    // Positions from start, not including it, in steps until leaving the bounds
+    pub fn ray(&self, start : Point2, step : Point2) -> impl Iterator<Item = Point2> {
+        let bounds = *self;
+        std::iter::successors(
+            Some(start),
+            move |pos| Some(*pos + step)
+        ).skip(1).take_while(move |pos| bounds.contains(*pos))
+    }
+}
+
+impl<T> Grid<T> {
+    pub fn new(width : usize, height : usize, fill : T) -> Grid<T>
+      where T : Clone
+    {
+        Grid { width : width, height : height, cells : vec![fill; width * height] }
+    }
+
+    pub fn from_rows(rows : Vec<Vec<T>>) -> Result<Grid<T>> {
+        let width = rows.first().map(|row| row.len()).unwrap_or(0);
+        if rows.iter().any(|row| row.len() != width) {
+            return Err(Error::invalid("rows of the grid differ in width"));
+        }
+        let height = rows.len();
+        Ok(Grid { width : width, height : height, cells : rows.into_iter().flatten().collect() })
+    }
+
+    // Parse a character map, each character is turned into a cell by parse
+    // and expected describes the characters that are allowed
+    pub fn parse<S, F>(lines : &[S], parse : F, expected : &str) -> Result<Grid<T>>
+      where S : AsRef<str>, F : Fn(char) -> Option<T>
+    {
+        let lines = map_lines(lines);
+        let mut cells = Vec::new();
+        let width = lines.first().map(|line| line.as_ref().chars().count()).unwrap_or(0);
+        for (y, line) in lines.iter().enumerate() {
+            let line = line.as_ref();
+            if line.chars().count() != width {
+                return Err(Error::line(line, "a row as wide as the first").offset_lines(y));
+            }
+            for (i, ch) in line.char_indices() {
+                let cell = parse(ch).ok_or_else(
+                    || Error::at(line, &line[i..], expected).offset_lines(y)
+                )?;
+                cells.push(cell);
+            }
+        }
+        Ok(Grid { width : width, height : lines.len(), cells : cells })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    pub fn bounds(&self) -> Bounds {
+        Bounds::new(Point2::ORIGIN, Point2::new(self.width as i64 - 1, self.height as i64 - 1))
+    }
+
+    pub fn contains(&self, pos : Point2) -> bool {
+        pos.x >= 0 && pos.y >= 0 && (pos.x as usize) < self.width && (pos.y as usize) < self.height
+    }
+
+    fn offset(&self, pos : Point2) -> Option<usize> {
+        if self.contains(pos) {
+            Some(pos.y as usize * self.width + pos.x as usize)
+        } else {
+            None
+        }
+    }
+
+    pub fn get(&self, pos : Point2) -> Option<&T> {
+        self.offset(pos).map(|i| &self.cells[i])
+    }
+
+    pub fn get_mut(&mut self, pos : Point2) -> Option<&mut T> {
+        self.offset(pos).map(|i| &mut self.cells[i])
+    }
+
+    pub fn positions(&self) -> impl Iterator<Item = Point2> {
+        self.bounds().positions()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Point2, &T)> {
+        self.positions().zip(self.cells.iter())
+    }
+
+    pub fn find<P>(&self, pred : P) -> Option<Point2>
+      where P : Fn(&T) -> bool
+    {
+        self.iter().find(|(_, cell)| pred(cell)).map(|(pos, _)| pos)
+    }
+
+    pub fn map<U, F>(&self, f : F) -> Grid<U>
+      where F : Fn(&T) -> U
+    {
+        Grid { width : self.width, height : self.height, cells : self.cells.iter().map(f).collect() }
+    }
+
+    // The orthogonal neighbours inside the grid
+    pub fn neighbours(&self, pos : Point2) -> impl Iterator<Item = Point2> + '_ {
+        pos.neighbours_4().filter(move |pos| self.contains(*pos))
+    }
+
+    // The orthogonal and diagonal neighbours inside the grid
+    pub fn neighbours_8(&self, pos : Point2) -> impl Iterator<Item = Point2> + '_ {
+        pos.neighbours_8().filter(move |pos| self.contains(*pos))
+    }
+
+    pub fn row(&self, y : i64) -> impl DoubleEndedIterator<Item = Point2> {
+        self.bounds().row(y)
+    }
+
+    pub fn column(&self, x : i64) -> impl DoubleEndedIterator<Item = Point2> {
+        self.bounds().column(x)
+    }
+
+    pub fn ray(&self, start : Point2, step : Point2) -> impl Iterator<Item = Point2> {
+        self.bounds().ray(start, step)
+    }
+
+    pub fn render<F>(&self, f : F) -> String
+      where F : Fn(&T) -> char
+    {
+        (0..self.height).map(
+            |y| self.cells[y * self.width .. (y + 1) * self.width].iter().map(&f).collect::<String>()
+        ).collect::<Vec<String>>().join("\n")
+    }
+}
+
+impl<T> Index<Point2> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, pos : Point2) -> &T {
+        self.get(pos).expect("position outside the grid")
+    }
+}
+
+impl<T> IndexMut<Point2> for Grid<T> {
+    fn index_mut(&mut self, pos : Point2) -> &mut T {
+        self.get_mut(pos).expect("position outside the grid")
+    }
+}
+
+impl<T> Default for SparseGrid<T> {
+    fn default() -> SparseGrid<T> {
+        SparseGrid::new()
+    }
+}
+
+impl<T> SparseGrid<T> {
+    pub fn new() -> SparseGrid<T> {
+        SparseGrid { cells : HashMap::new(), bounds : None }
+    }
+
+    // Parse a character map, characters in blank are left unset and the
+    // rest are turned into cells by parse
+    pub fn parse<S, F>(lines : &[S], blank : &str, parse : F, expected : &str) -> Result<SparseGrid<T>>
+      where S : AsRef<str>, F : Fn(char) -> Option<T>
+    {
+        let mut grid = SparseGrid::new();
+        for (y, line) in map_lines(lines).iter().enumerate() {
+            let line = line.as_ref();
+            for (x, (i, ch)) in line.char_indices().enumerate() {
+                if blank.contains(ch) {
+                    continue;
+                }
+                let cell = parse(ch).ok_or_else(
+                    || Error::at(line, &line[i..], expected).offset_lines(y)
+                )?;
+                grid.insert(Point2::new(x as i64, y as i64), cell);
+            }
+        }
+        Ok(grid)
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    // Bounds of every position inserted, none if nothing has been
+    pub fn bounds(&self) -> Option<Bounds> {
+        self.bounds
+    }
+
+    pub fn contains(&self, pos : Point2) -> bool {
+        self.cells.contains_key(&pos)
+    }
+
+    pub fn get(&self, pos : Point2) -> Option<&T> {
+        self.cells.get(&pos)
+    }
+
+    pub fn get_mut(&mut self, pos : Point2) -> Option<&mut T> {
+        self.cells.get_mut(&pos)
+    }
+
+    pub fn insert(&mut self, pos : Point2, cell : T) -> Option<T> {
+        self.bounds = Some(
+            self.bounds.map(|bounds| bounds.include(pos)).unwrap_or(Bounds::point(pos))
+        );
+        self.cells.insert(pos, cell)
+    }
+
+    pub fn remove(&mut self, pos : Point2) -> Option<T> {
+        self.cells.remove(&pos)
+    }
+
+    pub fn positions(&self) -> impl Iterator<Item = Point2> + '_ {
+        self.cells.keys().cloned()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Point2, &T)> {
+        self.cells.iter().map(|(pos, cell)| (*pos, cell))
+    }
+
+    // The orthogonal neighbours that are set
+    pub fn neighbours(&self, pos : Point2) -> impl Iterator<Item = Point2> + '_ {
+        pos.neighbours_4().filter(move |pos| self.contains(*pos))
+    }
+
+    // The orthogonal and diagonal neighbours that are set
+    pub fn neighbours_8(&self, pos : Point2) -> impl Iterator<Item = Point2> + '_ {
+        pos.neighbours_8().filter(move |pos| self.contains(*pos))
+    }
+
+    // Positions set in row y from left to right
+    pub fn row(&self, y : i64) -> impl DoubleEndedIterator<Item = Point2> + '_ {
+        let (min_x, max_x) = self.bounds().map(|b| (b.min.x, b.max.x)).unwrap_or((0, -1));
+        (min_x..=max_x).map(move |x| Point2::new(x, y)).filter(move |pos| self.contains(*pos))
+    }
+
+    // Positions set in column x from top to bottom
+    pub fn column(&self, x : i64) -> impl DoubleEndedIterator<Item = Point2> + '_ {
+        let (min_y, max_y) = self.bounds().map(|b| (b.min.y, b.max.y)).unwrap_or((0, -1));
+        (min_y..=max_y).map(move |y| Point2::new(x, y)).filter(move |pos| self.contains(*pos))
+    }
+
+    // Positions from start, not including it, in steps until leaving the bounds
+    pub fn ray(&self, start : Point2, step : Point2) -> impl Iterator<Item = Point2> {
+        let bounds = self.bounds().unwrap_or(Bounds::point(start));
+        bounds.ray(start, step)
+    }
+
+    pub fn render<F>(&self, f : F) -> String
+      where F : Fn(Option<&T>) -> char
+    {
+        match self.bounds() {
+            Some(bounds) => (bounds.min.y..=bounds.max.y).map(
+                |y| bounds.row(y).map(|pos| f(self.get(pos))).collect::<String>()
+            ).collect::<Vec<String>>().join("\n"),
+            None => String::new()
+        }
+    }
+}
+
+// Positions are not strings so the cells are written as a list of position
+// and cell pairs in reading order, the bounds are rebuilt when read back
+#[cfg(feature = "serde")]
+impl<T : serde::Serialize> serde::Serialize for SparseGrid<T> {
+    fn serialize<S : serde::Serializer>(&self, serializer : S) -> std::result::Result<S::Ok, S::Error> {
+        let mut cells : Vec<(Point2, &T)> = self.iter().collect();
+        cells.sort_by_key(|(pos, _)| (pos.y, pos.x));
+        serializer.collect_seq(cells)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T : serde::Deserialize<'de>> serde::Deserialize<'de> for SparseGrid<T> {
+    fn deserialize<D : serde::Deserializer<'de>>(deserializer : D) -> std::result::Result<SparseGrid<T>, D::Error> {
+        let cells : Vec<(Point2, T)> = serde::Deserialize::deserialize(deserializer)?;
+        let mut grid = SparseGrid::new();
+        for (pos, cell) in cells {
+            grid.insert(pos, cell);
+        }
+        Ok(grid)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAP : [&str; 3] = ["#..", ".#.", "..#"];
+
+    fn wall(ch : char) -> Option<bool> {
+        match ch {
+            '#' => Some(true),
+            '.' => Some(false),
+            _ => None
+        }
+    }
+
+    #[test]
+    fn dense_round_trip() {
+        let grid = Grid::parse(&MAP, wall, "# or .").unwrap();
+        assert_eq!((grid.width(), grid.height()), (3, 3));
+        assert!(grid[Point2::new(1, 1)]);
+        assert_eq!(grid.get(Point2::new(3, 0)), None);
+        assert_eq!(grid.render(|wall| if *wall { '#' } else { '.' }), MAP.join("\n"));
+    }
+
+    #[test]
+    fn dense_errors() {
+        assert!(Grid::parse(&["#.", "#"], wall, "# or .").is_err());
+        assert!(Grid::parse(&["#.", "#x"], wall, "# or .").is_err());
+    }
+
+    #[test]
+    fn dense_neighbours() {
+        let grid = Grid::new(3, 3, 0);
+        assert_eq!(grid.neighbours(Point2::new(0, 0)).count(), 2);
+        assert_eq!(grid.neighbours(Point2::new(1, 1)).count(), 4);
+        assert_eq!(grid.neighbours_8(Point2::new(0, 1)).count(), 5);
+        assert_eq!(grid.ray(Point2::new(0, 0), Point2::new(1, 1)).collect::<Vec<Point2>>(), vec!(Point2::new(1, 1), Point2::new(2, 2)));
+        assert_eq!(grid.column(2).rev().collect::<Vec<Point2>>(), vec!(Point2::new(2, 2), Point2::new(2, 1), Point2::new(2, 0)));
+    }
+
+    #[test]
+    fn sparse_round_trip() {
+        let grid = SparseGrid::parse(&MAP, ".", |ch| if ch == '#' { Some(()) } else { None }, "#").unwrap();
+        assert_eq!(grid.len(), 3);
+        assert_eq!(grid.bounds(), Some(Bounds::new(Point2::new(0, 0), Point2::new(2, 2))));
+        assert_eq!(grid.row(1).collect::<Vec<Point2>>(), vec!(Point2::new(1, 1)));
+        assert_eq!(grid.neighbours_8(Point2::new(1, 1)).count(), 2);
+        assert_eq!(grid.render(|cell| if cell.is_some() { '#' } else { '.' }), MAP.join("\n"));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn sparse_json() {
+        let grid = SparseGrid::parse(&MAP, "", wall, "# or .").unwrap();
+        let json = serde_json::to_string(&grid).unwrap();
+        assert!(json.starts_with(r#"[[{"x":0,"y":0},true],[{"x":1,"y":0},false]"#));
+        assert_eq!(serde_json::from_str::<SparseGrid<bool>>(&json).unwrap(), grid);
+    }
+}
@@ -1,145 +1,152 @@
 use std::collections::BTreeSet;
+use std::fmt;
 
-use itertools::{repeat_n, Itertools};
+use crate::error::{Error, Result};
+use crate::geometry::Point2;
+use crate::metrics;
+use crate::grid::Grid;
 
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct HeightMap {
-    grid : Vec<Vec<u8>>,
-    start : (usize, usize),
-    end : (usize, usize),
-    w : usize,
-    h : usize
+    grid : Grid<u8>,
+    start : Point2,
+    end : Point2
 }
 
-fn parse_row(i : usize, line : &String) -> (Vec<u8>, Option<(usize, usize)>, Option<(usize, usize)>) {
-    let mut row = Vec::new();
-    let mut start = None;
-    let mut end = None;
-    for (j, ch) in line.char_indices() {
-        if ch == 'S' {
-            start = Some((i, j));
-            row.push('a' as u8);
-        } else {
-            if ch == 'E' {
-                end = Some((i, j));
-                row.push('z' as u8);
-            } else {
-                row.push(ch as u8);
-            }
-        }
+fn height(ch : char) -> Option<char> {
+    if ch.is_ascii_lowercase() || ch == 'S' || ch == 'E' {
+        Some(ch)
+    } else {
+        None
     }
-    (row, start, end)
-}
-
-fn from_grid(
-    grid : &Vec<Vec<Option<u16>>>,
-    pos : &(usize, usize)
-) -> Option<u16> {
-    grid.get(pos.0).and_then(
-        |row| row.get(pos.1)
-    ).and_then(
-        |val| *val
-    )
 }
 
 impl HeightMap {
-    pub fn from_lines(lines : &Vec<String>) -> HeightMap {
-        let mut rows = Vec::new();
-        let mut start = None;
-        let mut end = None;
-        for (i, line) in lines.iter().enumerate() {
-            let (row, start_r, end_r) = parse_row(i,line);
-            start = start.or(start_r);
-            end = end.or(end_r);
-            rows.push(row);
-        }
-        let w = rows[0].len();
-        let h = rows.len();
-        HeightMap {
-            grid : rows,
-            w : w,
-            h : h,
-            start : start.unwrap(),
-            end : end.unwrap()
-        }
+    pub fn from_lines(lines : &Vec<String>) -> Result<HeightMap> {
+        let chars = Grid::parse(lines, height, "a height from a to z, S or E")?;
+        let start = chars.find(|ch| *ch == 'S').ok_or(
+            Error::invalid("no start S in the height map")
+        )?;
+        let end = chars.find(|ch| *ch == 'E').ok_or(
+            Error::invalid("no end E in the height map")
+        )?;
+        let grid = chars.map(
+            |ch| match ch {
+                'S' => b'a',
+                'E' => b'z',
+                _ => *ch as u8
+            }
+        );
+        Ok(HeightMap { grid : grid, start : start, end : end })
     }
 
     pub fn distance(&self) -> usize {
         let distance_grid = self.distance_grid();
-        distance_grid[self.start.0][self.start.1].unwrap() as usize
+        distance_grid[self.start].unwrap() as usize
     }
 
     pub fn distance_start(&self) -> usize {
         let distance_grid = self.distance_grid();
-        (0..self.h).cartesian_product(0..self.w).filter(
-            |(i,j)| self.grid[*i][*j] == ('a' as u8)
+        self.grid.iter().filter(
+            |(_, height)| **height == b'a'
         ).filter_map(
-            |(i,j)| distance_grid[i][j]
+            |(pos, _)| distance_grid[pos]
         ).min().unwrap() as usize
     }
 
-    fn distance_grid(&self) -> Vec<Vec<Option<u16>>> {
-        let mut distance_grid : Vec<Vec<Option<u16>>> = Vec::new();
-        for _ in 0..self.h {
-            distance_grid.push(
-                Vec::from_iter(repeat_n(None, self.w))
-            );
-        }
-        distance_grid[self.end.0][self.end.1] = Some(0);
+    fn distance_grid(&self) -> Grid<Option<u16>> {
+        let mut distance_grid = Grid::new(self.grid.width(), self.grid.height(), None);
+        distance_grid[self.end] = Some(0);
         let mut to_check = BTreeSet::from_iter(
-            self.neighbours(&self.end).into_iter()
+            self.grid.neighbours(self.end)
         );
+        let (mut checks, mut relaxations) = (0, 0);
         while let Some(pos) = to_check.pop_first() {
-            if let Some(val) = self.best_distance(&distance_grid, &pos) {
-                let old_val = from_grid(&distance_grid, &pos);
-                let replace = old_val.map(
+            checks += 1;
+            if let Some(val) = self.best_distance(&distance_grid, pos) {
+                let replace = distance_grid[pos].map(
                     |old_dist| old_dist > val
                 ).unwrap_or(true);
                 if replace {
-                    distance_grid[pos.0][pos.1] = Some(val);
-                    for n in self.neighbours(&pos) {
+                    relaxations += 1;
+                    distance_grid[pos] = Some(val);
+                    for n in self.grid.neighbours(pos) {
                         to_check.insert(n);
                     }
                 }
             }
         }
+        metrics::add("heights.checks", checks);
+        metrics::add("heights.relaxations", relaxations);
         distance_grid
     }
 
     fn best_distance(
         &self,
-        grid : &Vec<Vec<Option<u16>>>,
-        pos : &(usize, usize)
+        grid : &Grid<Option<u16>>,
+        pos : Point2
     ) -> Option<u16> {
-        self.neighbours(pos).into_iter().filter(
-            |p| self.valid_move(pos, p)
+        self.grid.neighbours(pos).filter(
+            |p| self.valid_move(pos, *p)
         ).filter_map(
-            |p| from_grid(grid, &p)
+            |p| grid[p]
         ).min().map(
             |res| res + 1
         )
     }
 
-    fn valid_move(&self, from : &(usize, usize), to : &(usize, usize)) -> bool{
-        let val_start = self.grid[from.0][from.1];
-        let val_end = self.grid[to.0][to.1];
+    fn valid_move(&self, from : Point2, to : Point2) -> bool{
+        let val_start = self.grid[from];
+        let val_end = self.grid[to];
         val_start +1 >= val_end
     }
+}
 
-    fn neighbours(&self, pos : &(usize, usize)) -> Vec<(usize, usize)> {
-        let mut neighbours = Vec::new();
-        if pos.0 > 0 {
-            neighbours.push((pos.0 - 1, pos.1));
-        } 
-        if pos.0 < (self.h-1) {
-            neighbours.push((pos.0 + 1, pos.1));
-        } 
-        if pos.1 > 0 {
-            neighbours.push((pos.0, pos.1 - 1));
-        } 
-        if pos.1 < (self.w-1) {
-            neighbours.push((pos.0, pos.1 + 1));
-        } 
-
-        neighbours
+impl fmt::Display for HeightMap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let rows : Vec<String> = (0..self.grid.height() as i64).map(
+            |y| self.grid.row(y).map(
+                |pos| match pos {
+                    _ if pos == self.start => 'S',
+                    _ if pos == self.end => 'E',
+                    _ => self.grid[pos] as char
+                }
+            ).collect()
+        ).collect();
+        write!(f, "{}", rows.join("\n"))
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::{self, Input};
+
+    const EXAMPLE : &str = include_str!("../../data/examples/day-12.txt");
+
+    #[test]
+    fn from_start() {
+        let map = io::input_as_heights(&Input::text(EXAMPLE)).unwrap();
+        assert_eq!(map.distance(), 31);
+    }
+
+    #[test]
+    fn from_lowest() {
+        let map = io::input_as_heights(&Input::text(EXAMPLE)).unwrap();
+        assert_eq!(map.distance_start(), 29);
+    }
+
+    #[test]
+    fn missing_end() {
+        let lines = vec!("Sabc".to_string());
+        assert!(HeightMap::from_lines(&lines).is_err());
+    }
+
+    #[test]
+    fn round_trip() {
+        let map = io::input_as_heights(&Input::text(EXAMPLE)).unwrap();
+        assert_eq!(map.to_string(), EXAMPLE.trim_end());
+        assert_eq!(io::input_as_heights(&Input::text(&map.to_string())).unwrap(), map);
+    }
+}
@@ -1,6 +1,9 @@
-use std::fs::{read_to_string, File};
-use std::io::BufReader;
-use std::io::BufRead;
+use std::env;
+use std::fs::read_to_string;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use crate::{calories, tetris, riddle};
 use crate::game;
@@ -21,148 +24,263 @@ use crate::lava;
 use crate::robot;
 use crate::map;
 use crate::planting;
+use crate::blizzard;
+use crate::snafu;
+use crate::error::{Error, Result, lines_before, parse_lines, parse_lines_from, parse_num, skip};
 
-pub fn input_as_lines(day: i8) -> Vec<String> {
-    let filename = format!("../data/day-{}.txt", day);
-    let file = File::open(filename).expect("Issue opening file");
-    let reader = BufReader::new(&file);
-    reader.lines().map(
-        |s| s.expect("Read failure")
-    ).collect()
+pub const DATA_DIR_VAR : &str = "AOC_DATA_DIR";
+
+#[derive(Debug)]
+pub enum Source {
+    File(PathBuf),
+    Stdin,
+    Text(String)
 }
 
-pub fn input_as_string(day: i8) -> String {
-    let filename = format!("../data/day-{}.txt", day);
-    read_to_string(filename).expect("Read failure")
+// Where a puzzle input comes from, the contents are read at most once
+#[derive(Debug)]
+pub struct Input {
+    source : Source,
+    contents : OnceLock<String>,
+    reported : AtomicBool
 }
 
-pub fn input_as_line(day: i8) -> String {
-    input_as_lines(day).into_iter().next().unwrap()
+// The data directory from the environment, or the one next to the crate
+pub fn data_dir() -> PathBuf {
+    env::var_os(DATA_DIR_VAR).map(PathBuf::from).unwrap_or(
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("..").join("data")
+    )
 }
 
-pub fn input_as_ints(day: i8) -> Vec<i64> {
-    input_as_lines(day).into_iter().filter_map(
-        |line| line.parse::<i64>().ok()
-    ).collect()
+impl Input {
+    pub fn new(source : Source) -> Input {
+        Input { source : source, contents : OnceLock::new(), reported : AtomicBool::new(false) }
+    }
+
+    pub fn file<P>(path : P) -> Input
+      where P : AsRef<Path>
+    {
+        Input::new(Source::File(path.as_ref().to_path_buf()))
+    }
+
+    pub fn stdin() -> Input {
+        Input::new(Source::Stdin)
+    }
+
+    pub fn text(text : &str) -> Input {
+        Input::new(Source::Text(text.to_string()))
+    }
+
+    pub fn in_dir<P>(dir : P, day : u8) -> Input
+      where P : AsRef<Path>
+    {
+        Input::file(dir.as_ref().join(format!("day-{}.txt", day)))
+    }
+
+    // The input for a day from the default data directory
+    pub fn day(day : u8) -> Input {
+        Input::in_dir(data_dir(), day)
+    }
+
+    pub fn source(&self) -> &Source {
+        &self.source
+    }
+
+    // True only the first time, so lines skipped while parsing are reported
+    // once however many parts parse the input
+    pub fn first_report(&self) -> bool {
+        !self.reported.swap(true, Ordering::Relaxed)
+    }
+
+    pub fn contents(&self) -> Result<&str> {
+        if let Some(contents) = self.contents.get() {
+            return Ok(contents);
+        }
+        let contents = match &self.source {
+            Source::File(path) => read_to_string(path).map_err(
+                |err| Error::file(path, err)
+            )?,
+            Source::Stdin => {
+                let mut buffer = String::new();
+                std::io::stdin().read_to_string(&mut buffer)?;
+                buffer
+            },
+            Source::Text(text) => text.clone()
+        };
+        Ok(self.contents.get_or_init(|| contents))
+    }
 }
 
-pub fn input_as_elfs(day: i8) -> calories::Elfs {
-    calories::Elfs::from_lines(&input_as_lines(day))
+pub fn input_as_lines(input : &Input) -> Result<Vec<String>> {
+    Ok(input.contents()?.lines().map(
+        |line| line.to_string()
+    ).collect())
 }
 
-pub fn input_as_guide(day: i8) -> game::Guide {
-    game::Guide::from_lines(&input_as_lines(day))
+pub fn input_as_string(input : &Input) -> Result<String> {
+    Ok(input.contents()?.to_string())
 }
 
-pub fn input_as_game(day: i8) -> game::Game {
-    game::Game::from_lines(&input_as_lines(day))
+pub fn input_as_line(input : &Input) -> Result<String> {
+    input_as_lines(input)?.into_iter().next().ok_or(
+        Error::invalid("the input is empty")
+    )
 }
 
-pub fn input_as_forest(day: i8) -> trees::Forest {
-    trees::Forest::from_lines(&input_as_lines(day))
+pub fn input_as_ints(input : &Input) -> Result<Vec<i64>> {
+    parse_lines(
+        &input_as_lines(input)?,
+        |line| parse_num(line, line)
+    )
 }
 
-pub fn input_as_field(day: i8) -> planting::Field {
-    planting::Field::from_lines(&input_as_lines(day))
+pub fn input_as_elfs(input : &Input) -> Result<calories::Elfs> {
+    calories::Elfs::from_lines(&input_as_lines(input)?)
 }
 
-pub fn input_as_commands(day : i8) -> files::Commands {
-    files::Commands::from_string(&input_as_string(day))
+pub fn input_as_guide(input : &Input) -> Result<game::Guide> {
+    game::Guide::from_lines(&input_as_lines(input)?)
 }
 
-pub fn input_as_monkeys(day : i8) -> monkey::Monkeys {
-    monkey::Monkeys::from_string(&input_as_string(day))
+pub fn input_as_game(input : &Input) -> Result<game::Game> {
+    game::Game::from_lines(&input_as_lines(input)?)
 }
 
-pub fn input_as_directions(day : i8) -> rope::Directions {
-    rope::Directions::from_lines(&input_as_lines(day))
+pub fn input_as_forest(input : &Input) -> Result<trees::Forest> {
+    trees::Forest::from_lines(&input_as_lines(input)?)
 }
 
-pub fn input_as_code(day : i8) -> crt::CPU {
-    crt::CPU::from_lines(&input_as_lines(day))
+pub fn input_as_field(input : &Input) -> Result<planting::Field> {
+    planting::Field::from_lines(&input_as_lines(input)?)
 }
 
-pub fn input_as_jets(day : i8) -> tetris::Jets {
-    tetris::Jets::from_line(&input_as_line(day))
+pub fn input_as_commands(input : &Input) -> Result<files::Commands> {
+    files::Commands::from_string(&input_as_string(input)?)
 }
 
-pub fn input_as_lava(day : i8) -> lava::Droplets {
-    lava::Droplets::from_lines(&input_as_lines(day))
+pub fn input_as_monkeys(input : &Input) -> Result<monkey::Monkeys> {
+    monkey::Monkeys::from_string(&input_as_string(input)?)
 }
 
-pub fn input_as_sensors(day : i8) -> sensors::Sensors {
-    sensors::Sensors::from_lines(&input_as_lines(day))
+pub fn input_as_directions(input : &Input) -> Result<rope::Directions> {
+    rope::Directions::from_lines(&input_as_lines(input)?)
 }
 
-pub fn input_as_map(day : i8) -> map::Map {
-    map::Map::from_lines(&input_as_lines(day))
+pub fn input_as_code(input : &Input) -> Result<crt::CPU> {
+    crt::CPU::from_lines(&input_as_lines(input)?)
 }
 
-pub fn input_as_network(day : i8) -> pressure::Network {
-    pressure::Network::from_lines(&input_as_lines(day))
+pub fn input_as_jets(input : &Input) -> Result<tetris::Jets> {
+    tetris::Jets::from_line(&input_as_line(input)?)
 }
 
-pub fn input_as_packet_pairs(day : i8) -> Vec<packets::PacketPair> {
-    let str = input_as_string(day);
-    str.split("\n\n").filter_map(
-        |str| packets::PacketPair::from_string(str)
-    ).collect()
+pub fn input_as_lava(input : &Input) -> Result<lava::Droplets> {
+    lava::Droplets::from_lines(&input_as_lines(input)?)
 }
 
-pub fn input_as_packets(day : i8) -> Vec<packets::Packet> {
-    input_as_lines(day).iter().filter_map(
-        |str| packets::Packet::from_line(str)
-    ).collect()
+pub fn input_as_sensors(input : &Input) -> Result<sensors::Sensors> {
+    sensors::Sensors::from_lines(&input_as_lines(input)?)
 }
 
-pub fn input_as_heights(day : i8) -> heights::HeightMap {
-    heights::HeightMap::from_lines(&input_as_lines(day))
+pub fn input_as_valley(input : &Input) -> Result<blizzard::Valley> {
+    blizzard::Valley::from_lines(&input_as_lines(input)?)
 }
 
-pub fn input_as_cave(day : i8) -> sand::Cave {
-    sand::Cave::from_lines(&input_as_lines(day))
+pub fn input_as_snafus(input : &Input) -> Result<Vec<snafu::Snafu>> {
+    parse_lines(
+        &input_as_lines(input)?,
+        |line| line.parse::<snafu::Snafu>()
+    )
 }
 
-pub fn input_as_rucksack(day: i8) -> Vec<packing::Rucksack> {
-    input_as_lines(day).iter().map(
-        |line| packing::Rucksack::from_line(line)
-    ).collect()
+pub fn input_as_map(input : &Input) -> Result<map::Map> {
+    map::Map::from_lines(&input_as_lines(input)?)
 }
 
-pub fn input_as_groups(day: i8) -> Vec<packing::Group> {
-    input_as_lines(day).iter().array_chunks::<3>().map(
-        |line| packing::Group::from_lines(line)
-    ).collect()
+pub fn input_as_network(input : &Input) -> Result<pressure::Network> {
+    pressure::Network::from_lines(&input_as_lines(input)?)
 }
 
-pub fn input_as_assignment(day: i8) -> Vec<camp::AssignmentPair> {
-    input_as_lines(day).iter().filter_map(
-        |line| camp::AssignmentPair::from_line(line)
-    ).collect()
+pub fn input_as_packet_pairs(input : &Input) -> Result<Vec<packets::PacketPair>> {
+    let str = input_as_string(input)?;
+    let mut pairs = Vec::new();
+    for pair_str in str.split("\n\n").filter(|pair_str| !pair_str.trim().is_empty()) {
+        match packets::PacketPair::from_string(pair_str) {
+            Ok(pair) => pairs.push(pair),
+            Err(err) => skip(
+                err.offset_lines(lines_before(&str, pair_str))
+            )?
+        }
+    }
+    Ok(pairs)
+}
+
+pub fn input_as_packets(input : &Input) -> Result<Vec<packets::Packet>> {
+    parse_lines(
+        &input_as_lines(input)?,
+        packets::Packet::from_line
+    )
+}
+
+pub fn input_as_heights(input : &Input) -> Result<heights::HeightMap> {
+    heights::HeightMap::from_lines(&input_as_lines(input)?)
+}
+
+pub fn input_as_cave(input : &Input) -> Result<sand::Cave> {
+    sand::Cave::from_lines(&input_as_lines(input)?)
 }
 
-pub fn input_as_blueprints(day: i8) -> Vec<robot::Blueprint> {
-    input_as_lines(day).iter().filter_map(
-        |line| robot::Blueprint::from_line(line)
+pub fn input_as_rucksack(input : &Input) -> Result<Vec<packing::Rucksack>> {
+    parse_lines(
+        &input_as_lines(input)?,
+        packing::Rucksack::from_line
+    )
+}
+
+// Every three lines make a group, one or two lines left over are an error
+pub fn input_as_groups(input : &Input) -> Result<Vec<packing::Group>> {
+    input_as_lines(input)?.chunks(3).enumerate().map(
+        |(i, lines)| match lines {
+            [fst, snd, thd] => packing::Group::from_lines([fst, snd, thd]),
+            _ => Err(Error::line(&lines[0], "a group of three rucksacks"))
+        }.map_err(
+            |err| err.offset_lines(3 * i)
+        )
     ).collect()
 }
 
-pub fn input_as_riddles(day: i8) -> riddle::Riddle {
-    riddle::Riddle::from_lines(&input_as_lines(day))
+pub fn input_as_assignment(input : &Input) -> Result<Vec<camp::AssignmentPair>> {
+    parse_lines(
+        &input_as_lines(input)?,
+        camp::AssignmentPair::from_line
+    )
+}
+
+pub fn input_as_blueprints(input : &Input) -> Result<Vec<robot::Blueprint>> {
+    parse_lines(
+        &input_as_lines(input)?,
+        robot::Blueprint::from_line
+    )
 }
 
-pub fn input_as_crates(day: i8) -> (crates::Crates, Vec<crates::Move>) {
-    let all_lines = input_as_lines(day);
+pub fn input_as_riddles(input : &Input) -> Result<riddle::Riddle> {
+    riddle::Riddle::from_lines(&input_as_lines(input)?)
+}
+
+pub fn input_as_crates(input : &Input) -> Result<(crates::Crates, Vec<crates::Move>)> {
+    let all_lines = input_as_lines(input)?;
     let init_lines : Vec<&String> = all_lines.iter().take_while(
-        |l| l.len() > 0
+        |l| !l.is_empty()
     ).collect();
     let rest_lines : Vec<&String> = all_lines.iter().skip_while(
-        |l| l.len() > 0
+        |l| !l.is_empty()
     ).skip(1).collect();
-    let init = crates::Crates::from_lines(&init_lines).unwrap();
-    let moves = rest_lines.iter().filter_map(
-        |line| crates::Move::from_line(line)
-    ).collect();
-    (init, moves)
+    let init = crates::Crates::from_lines(&init_lines)?;
+    let moves = parse_lines_from(
+        &rest_lines,
+        init_lines.len() + 1,
+        |line| crates::Move::from_line(line, init.stacks())
+    )?;
+    Ok((init, moves))
 }
 
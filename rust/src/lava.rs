@@ -1,160 +1,109 @@
-use std::collections::HashMap;
-use std::{collections::HashSet, hash::Hash};
-use petgraph::graph::{NodeIndex, UnGraph};
-use petgraph::algo::kosaraju_scc;
+use std::collections::HashSet;
+use std::fmt;
 
-#[derive(PartialEq, Eq, Hash, Clone)]
-enum Dir {
-    X,
-    Y, 
-    Z
-}
+use itertools::Itertools;
 
-#[derive(PartialEq, Eq, Hash, Clone)]
-struct Surface {
-    x : i64,
-    y : i64, 
-    z : i64,
-    dir : Dir
-}
+use crate::error::{Error, Result, parse_lines, parse_num};
+use crate::geometry::Point3;
 
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Droplets {
-    pts : HashSet<(i64, i64, i64)>
+    pts : HashSet<Point3>
 }
 
-fn parse_pt(line : &str) -> Option<(i64, i64, i64)> {
+fn parse_pt(line : &str) -> Result<Point3> {
     let parts : Vec<&str> = line.split(",").collect();
-    let x = parts.get(0).and_then(|s| s.parse::<i64>().ok())?;
-    let y = parts.get(1).and_then(|s| s.parse::<i64>().ok())?;
-    let z = parts.get(2).and_then(|s| s.parse::<i64>().ok())?;
-    Some((x,y,z))
-} 
-
-fn insert_rem<T>(set : &mut HashSet<T>, t : T)
- where T : Eq, T: Hash
-{
-    if set.contains(&t) {
-        set.remove(&t);
-    } else {
-        set.insert(t);
+    if parts.len() != 3 {
+        return Err(Error::line(line, "a point x,y,z"));
     }
-}
+    let x = parse_num(line, parts[0])?;
+    let y = parse_num(line, parts[1])?;
+    let z = parse_num(line, parts[2])?;
+    Ok(Point3::new(x, y, z))
+} 
 
 impl Droplets {
-    pub fn from_lines(lines : &Vec<String>) -> Droplets {
-        let pts = lines.iter().filter_map(
-            |line| parse_pt(line)
-        ).collect();
-        Droplets { pts: pts }
+    pub fn from_lines(lines : &Vec<String>) -> Result<Droplets> {
+        let pts = parse_lines(
+            lines,
+            parse_pt
+        )?;
+        Ok(Droplets { pts: pts.into_iter().collect() })
     }
 
+    // Faces of the droplets not touching another droplet
     pub fn surface_area(&self) -> usize {
-        self.surfaces().len()
+        self.pts.iter().map(
+            |pt| pt.neighbours_6().filter(|n| !self.pts.contains(n)).count()
+        ).sum()
     }
 
     pub fn outside_surface_area(&self) -> usize {
-        let comps = self.surface_components();
-        let top_comp = comps.iter().max_by_key(|comp| comp.iter().map(|s| s.z).max()).unwrap();
-        top_comp.len()
+        let outside = self.outside();
+        self.pts.iter().map(
+            |pt| pt.neighbours_6().filter(|n| outside.contains(n)).count()
+        ).sum()
     }
 
-    fn surface_components(&self) -> Vec<Vec<Surface>> {
-        let mut surface_map : HashMap<NodeIndex, Surface> = HashMap::new();
-        let mut surface_map_inv : HashMap<Surface, NodeIndex> = HashMap::new();
-        let mut adjacent_graph : UnGraph<(), ()>= UnGraph::new_undirected();
-        for surface in self.surfaces() {
-            let index = adjacent_graph.add_node(());
-            surface_map.insert(index, surface.clone());
-            surface_map_inv.insert(surface, index);
+    // Flood fill the air around the droplets inside a box one larger than them,
+    // pockets of air trapped inside are never reached
+    fn outside(&self) -> HashSet<Point3> {
+        if self.pts.is_empty() {
+            return HashSet::new();
         }
-        for (index, surface) in surface_map.iter() {
-            for adjacent in surface.adjacent() {
-                if let Some(index_ad) = surface_map_inv.get(&adjacent) {
-                    adjacent_graph.add_edge(*index, *index_ad, ());
+        let min_x = self.pts.iter().map(|pt| pt.x).min().unwrap() - 1;
+        let max_x = self.pts.iter().map(|pt| pt.x).max().unwrap() + 1;
+        let min_y = self.pts.iter().map(|pt| pt.y).min().unwrap() - 1;
+        let max_y = self.pts.iter().map(|pt| pt.y).max().unwrap() + 1;
+        let min_z = self.pts.iter().map(|pt| pt.z).min().unwrap() - 1;
+        let max_z = self.pts.iter().map(|pt| pt.z).max().unwrap() + 1;
+        let in_box = |pt : &Point3| {
+            min_x <= pt.x && pt.x <= max_x && min_y <= pt.y && pt.y <= max_y && min_z <= pt.z && pt.z <= max_z
+        };
+        let start = Point3::new(min_x, min_y, min_z);
+        let mut outside = HashSet::from([start]);
+        let mut queue = vec!(start);
+        while let Some(pt) = queue.pop() {
+            for next in pt.neighbours_6() {
+                if in_box(&next) && !self.pts.contains(&next) && outside.insert(next) {
+                    queue.push(next);
                 }
             }
         }
-        let comps = kosaraju_scc(&adjacent_graph);
-        let s : Vec<usize> = comps.iter().map(|comp| comp.len()).collect();
-        println!("Sizes {:?}",s);
-        comps.iter().map(
-            |comp| comp.iter().map(
-                |index| surface_map.get(index).unwrap().clone()
-            ).collect()
-        ).collect()
+        outside
     }
+}
 
-    fn surfaces(&self) -> HashSet<Surface> {
-        let mut surfaces = HashSet::new();
-        for pt in self.pts.iter() {
-            let x = pt.0;
-            let y = pt.1;
-            let z = pt.2;
-            insert_rem(&mut surfaces, Surface {x:x, y:y, z:z, dir:Dir::X});
-            insert_rem(&mut surfaces, Surface {x:x-1, y:y, z:z, dir:Dir::X});
-            insert_rem(&mut surfaces, Surface {x:x, y:y, z:z, dir:Dir::Y});
-            insert_rem(&mut surfaces, Surface {x:x, y:y-1, z:z, dir:Dir::Y});
-            insert_rem(&mut surfaces, Surface {x:x, y:y, z:z, dir:Dir::Z});
-            insert_rem(&mut surfaces, Surface {x:x, y:y, z:z-1, dir:Dir::Z});
-        }
-        surfaces
+// The droplets are a set so they are written sorted by position
+impl fmt::Display for Droplets {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.pts.iter().sorted_by_key(|pt| (pt.x, pt.y, pt.z)).join("\n"))
     }
 }
 
-impl Surface {
-    fn adjacent(&self) -> [Surface; 12] {
-        let x = self.x;
-        let y = self.y;
-        let z = self.z;
-        match self.dir {
-            Dir::X => {
-                [
-                    Surface { x : x, y : y, z : z, dir : Dir::Y },
-                    Surface { x : x, y : y, z : z, dir : Dir::Z },
-                    Surface { x : x, y : y-1, z : z, dir : Dir::Y },
-                    Surface { x : x, y : y, z : z-1, dir : Dir::Z },
-                    Surface { x : x+1, y : y, z : z, dir : Dir::Y },
-                    Surface { x : x+1, y : y, z : z, dir : Dir::Z },
-                    Surface { x : x+1, y : y-1, z : z, dir : Dir::Y },
-                    Surface { x : x+1, y : y, z : z-1, dir : Dir::Z },
-                    Surface { x: x, y : y+1, z : z, dir : Dir::X},
-                    Surface { x: x, y : y-1, z : z, dir : Dir::X},
-                    Surface { x: x, y : y, z : z+1, dir : Dir::X},
-                    Surface { x: x, y : y, z : z-1, dir : Dir::X}
-                ]
-            },
-            Dir::Y => {
-                [
-                    Surface { x : x, y : y, z : z, dir : Dir::X },
-                    Surface { x : x, y : y, z : z, dir : Dir::Z },
-                    Surface { x : x-1, y : y, z : z, dir : Dir::X },
-                    Surface { x : x, y : y, z : z-1, dir : Dir::Z },
-                    Surface { x : x, y : y+1, z : z, dir : Dir::X },
-                    Surface { x : x, y : y+1, z : z, dir : Dir::Z },
-                    Surface { x : x-1, y : y+1, z : z, dir : Dir::X },
-                    Surface { x : x, y : y+1, z : z-1, dir : Dir::Z },
-                    Surface { x: x+1, y : y, z : z, dir : Dir::Y},
-                    Surface { x: x-1, y : y, z : z, dir : Dir::Y},
-                    Surface { x: x, y : y, z : z+1, dir : Dir::Y},
-                    Surface { x: x, y : y, z : z-1, dir : Dir::Y}
-                ]
-            },
-            Dir::Z => {
-                [
-                    Surface { x : x, y : y, z : z, dir : Dir::X },
-                    Surface { x : x, y : y, z : z, dir : Dir::Y },
-                    Surface { x : x-1, y : y, z : z, dir : Dir::X },
-                    Surface { x : x, y : y-1, z : z, dir : Dir::Y },
-                    Surface { x : x, y : y, z : z+1, dir : Dir::X },
-                    Surface { x : x, y : y, z : z+1, dir : Dir::Y },
-                    Surface { x : x-1, y : y, z : z+1, dir : Dir::X },
-                    Surface { x : x, y : y-1, z : z+1, dir : Dir::Y },
-                    Surface { x: x, y : y+1, z : z, dir : Dir::Z},
-                    Surface { x: x, y : y-1, z : z, dir : Dir::Z},
-                    Surface { x: x+1, y : y, z : z, dir : Dir::Z},
-                    Surface { x: x-1, y : y, z : z, dir : Dir::Z}
-                ]
-            }
-        }
+#[cfg(test)]
+mod tests {
+    use crate::io::{self, Input};
+
+    const EXAMPLE : &str = include_str!("../../data/examples/day-18.txt");
+
+    #[test]
+    fn surface() {
+        let droplets = io::input_as_lava(&Input::text(EXAMPLE)).unwrap();
+        assert_eq!(droplets.surface_area(), 64);
+    }
+
+    #[test]
+    fn outside_surface() {
+        let droplets = io::input_as_lava(&Input::text(EXAMPLE)).unwrap();
+        assert_eq!(droplets.outside_surface_area(), 58);
+    }
+
+    #[test]
+    fn round_trip() {
+        let droplets = io::input_as_lava(&Input::text(EXAMPLE)).unwrap();
+        assert_eq!(io::input_as_lava(&Input::text(&droplets.to_string())).unwrap(), droplets);
+        assert_eq!(droplets.to_string().lines().count(), EXAMPLE.lines().count());
     }
 }
@@ -0,0 +1,42 @@
+extern crate pest;
+#[macro_use]
+extern crate pest_derive;
+
+pub mod io;
+pub mod geometry;
+pub mod grid;
+pub mod cycle;
+pub mod search;
+pub mod metrics;
+pub mod render;
+pub mod gen;
+pub mod error;
+pub mod solution;
+pub mod answers;
+pub mod runner;
+pub mod days;
+pub mod calories;
+pub mod game;
+pub mod packing;
+pub mod camp;
+pub mod crates;
+pub mod signal;
+pub mod files;
+pub mod trees;
+pub mod rope;
+pub mod crt;
+pub mod monkey;
+pub mod heights;
+pub mod packets;
+pub mod sand;
+pub mod sensors;
+pub mod pressure;
+pub mod tetris;
+pub mod lava;
+pub mod robot;
+pub mod riddle;
+pub mod encrypted;
+pub mod map;
+pub mod planting;
+pub mod blizzard;
+pub mod snafu;
@@ -1,382 +1,485 @@
-#![feature(iter_array_chunks)]
-#![feature(btree_drain_filter)]
-#![feature(hash_drain_filter)]
-#![feature(option_result_contains)]
-#![feature(iter_advance_by)]
-
-extern crate pest;
-#[macro_use]
-extern crate pest_derive;
-
-mod io;
-mod calories;
-mod game;
-mod packing;
-mod camp;
-mod crates;
-mod signal;
-mod files;
-mod trees;
-mod rope;
-mod crt;
-mod monkey;
-mod heights;
-mod packets;
-mod sand;
-mod sensors;
-mod pressure;
-mod tetris;
-mod lava;
-mod robot;
-mod riddle;
-mod encrypted;
-mod map;
-mod planting;
-
-mod challenge {
-    use crate::packets::Packet;
-    use crate::tetris::Game;
-    use crate::encrypted;
-
-    use super::io as io;
-
-    fn challenge_1() {
-        let data = io::input_as_elfs(1);
-        println!("{:?}", data.best_elf());
-    }
-
-    fn challenge_2() {
-        let data = io::input_as_elfs(1);
-        println!("{:?}", data.best_elfs(3));
-    }
-
-    fn challenge_3() {
-        let data = io::input_as_game(2);
-        println!("{:?}", data.score());
-    }
-
-    fn challenge_4() {
-        let data = io::input_as_guide(2);
-        println!("{:?}", data.score());
-    }
-
-    fn challenge_5() {
-        let data = io::input_as_rucksack(3);
-        let res : usize = data.iter().map(|r| r.score()).sum();
-        println!("{:?}", res);
-    }
-
-    fn challenge_6() {
-        let data = io::input_as_groups(3);
-        let res : usize = data.iter().map(|r| r.score()).sum();
-        println!("{:?}", res);
-    }
-
-    fn challenge_7() {
-        let data = io::input_as_assignment(4);
-        let res : usize = data.iter().filter(|&r| r.contains()).count();
-        println!("{:?}", res);
-    }
-
-    fn challenge_8() {
-        let data = io::input_as_assignment(4);
-        let res : usize = data.iter().filter(|&r| r.overlap()).count();
-        println!("{:?}", res);
-    }
-
-    fn challenge_9() {
-        let (mut data, moves) = io::input_as_crates(5);
-        data.simulate(&moves, false);
-        println!("{}", data.tops());
-    }
-
-    fn challenge_10() {
-        let (mut data, moves) = io::input_as_crates(5);
-        data.simulate(&moves, true);
-        println!("{}", data.tops());
-    }
-
-    fn challenge_11() {
-        let data = io::input_as_line(6);
-        let res = crate::signal::start_of_packet(&data, 4);
-        println!("{}", res);
-    }
-
-    fn challenge_12() {
-        let data = io::input_as_line(6);
-        let res = crate::signal::start_of_packet(&data, 14);
-        println!("{}", res);
-    }
-
-    fn challenge_13() {
-        let data = io::input_as_commands(7);
-        let files = data.filesystem();
-        let res = files.total(100000);
-        println!("{}", res);
-    }
-
-    fn challenge_14() {
-        let data = io::input_as_commands(7);
-        let files = data.filesystem();
-        let size = files.size();
-        let res = files.smallest_dir(size - 40000000).unwrap();
-        println!("{}", res);
-    }
-
-    fn challenge_15() {
-        let data = io::input_as_forest(8);
-        let res = data.count_visible();
-        println!("{}", res);
-    }
-
-    fn challenge_16() {
-        let data = io::input_as_forest(8);
-        let res = data.scenic_max();
-        println!("{}", res);
-    }
-
-    fn challenge_17() {
-        let data = io::input_as_directions(9);
-        let res = data.simulate(2).len();
-        println!("{}", res);
-    }
-
-    fn challenge_18() {
-        let data = io::input_as_directions(9);
-        let res = data.simulate(10).len();
-        println!("{}", res);
-    }
-
-    fn challenge_19() {
-        let data = io::input_as_code(10);
-        let signals = data.signals();
-        let res = signals[19] + signals[59] + signals[99] + signals[139] + signals[179] + signals[219];
-        println!("{}", res);
-    }
-
-    fn challenge_20() {
-        let data = io::input_as_code(10);
-        let res = data.display();
-        println!("{}", res);
-    }
-
-    fn challenge_21() {
-        let mut data = io::input_as_monkeys(11);
-        data.simulate(20, true);
-        let res = data.monkey_buisness();
-        println!("{}", res);
-    }
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::Instant;
+
+use clap::{ArgAction, Parser, Subcommand, ValueEnum};
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
+use advent_of_code::answers::{answers_path, Answers, Verdict};
+use advent_of_code::days;
+use advent_of_code::error;
+use advent_of_code::gen;
+use advent_of_code::io::{self, Input};
+use advent_of_code::metrics;
+use advent_of_code::render;
+use advent_of_code::runner::{self, Status};
+use advent_of_code::solution::{Part, Registry, Timing};
+
+#[derive(Parser)]
+#[command(about = "Advent of Code 2022 solutions")]
+struct Cli {
+    #[arg(long, global = true, help = "Read the puzzle input from this file, - for stdin")]
+    input : Option<PathBuf>,
+    #[arg(long, global = true, help = "Directory holding the day-N.txt inputs")]
+    data_dir : Option<PathBuf>,
+    #[arg(long, global = true, help = "Fail on the first line that does not parse")]
+    strict : bool,
+    #[arg(long, global = true, help = "Report the parse and solve time of each part")]
+    time : bool,
+    #[arg(long, global = true, value_enum, default_value_t = Format::Text, help = "Output format of run and all")]
+    format : Format,
+    #[arg(long, short, global = true, action = ArgAction::Count, help = "Log progress to stderr, -vv for debug and -vvv for trace")]
+    verbose : u8,
+    #[command(subcommand)]
+    command : Command
+}
 
-    fn challenge_22() {
-        let mut data = io::input_as_monkeys(11);
-        data.simulate(10000, false);
-        let res = data.monkey_buisness();
-        println!("{}", res);
-    }
+// Json prints one record per line for each part that is run
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Format {
+    Text,
+    Json
+}
 
-    fn challenge_23() {
-        let data = io::input_as_heights(12);
-        let res = data.distance();
-        println!("{}", res);
-    }
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Image {
+    Text,
+    Ppm,
+    Gif
+}
 
-    fn challenge_24() {
-        let data = io::input_as_heights(12);
-        let res = data.distance_start();
-        println!("{}", res);
+#[derive(Subcommand)]
+enum Command {
+    #[command(about = "Run one day, or one part of a day")]
+    Run {
+        #[arg(long, short, value_parser = clap::value_parser!(u8).range(1..=25))]
+        day : u8,
+        #[arg(long, short, value_parser = clap::value_parser!(u8).range(1..=2))]
+        part : Option<u8>
+    },
+    #[command(about = "List the days and parts that are implemented")]
+    List,
+    #[command(about = "Run every implemented part of every day")]
+    All {
+        #[arg(long, help = "Run the parts on a thread pool and print a summary table")]
+        parallel : bool,
+        #[arg(long, short, requires = "parallel", help = "Threads in the pool, defaults to one per core")]
+        jobs : Option<usize>
+    },
+    #[command(about = "Watch a simulation as text or write it out as images")]
+    Render {
+        #[arg(long, short, value_parser = clap::value_parser!(u8).range(1..=25))]
+        day : u8,
+        #[arg(long, value_enum, default_value_t = Image::Text, help = "Print text frames or write ppm frames or a gif")]
+        to : Image,
+        #[arg(long, short, help = "Gif file or directory of ppm frames, named after the day by default")]
+        out : Option<PathBuf>,
+        #[arg(long, default_value_t = 1, help = "Keep one frame every this many steps")]
+        every : usize,
+        #[arg(long, default_value_t = 200, help = "Most frames to keep")]
+        limit : usize,
+        #[arg(long, default_value_t = 4, help = "Pixels across each cell in images")]
+        scale : usize,
+        #[arg(long, default_value_t = 10, help = "Hundredths of a second each gif frame is shown")]
+        delay : u16
+    },
+    #[command(about = "Print a random input for a day, pipe it into run --input - to solve it")]
+    Gen {
+        #[arg(long, short, value_parser = clap::value_parser!(u8).range(1..=25))]
+        day : u8,
+        #[arg(long, default_value_t = 0, help = "The same seed always gives the same input")]
+        seed : u64,
+        #[arg(long, help = "Lines, grid side or count of the main items, defaults to about the real input")]
+        size : Option<usize>,
+        #[arg(long, short, help = "Write the input to this file instead of stdout")]
+        out : Option<PathBuf>
+    },
+    #[cfg(feature = "serde")]
+    #[command(about = "Print the parsed input of a day as JSON")]
+    Dump {
+        #[arg(long, short, value_parser = clap::value_parser!(u8).range(1..=25))]
+        day : u8
+    },
+    #[command(about = "Check answers against the recorded answers")]
+    Verify {
+        #[arg(long, short, value_parser = clap::value_parser!(u8).range(1..=25))]
+        day : Option<u8>,
+        #[arg(long, help = "Record answers that are missing from the answers file")]
+        record : bool,
+        #[arg(long, help = "Answers file, defaults to answers.toml in the data directory")]
+        answers : Option<PathBuf>
     }
+}
 
-    fn challenge_25() {
-        let data = io::input_as_packet_pairs(13);
-        let res : usize = data.iter().enumerate().filter_map(
-            |(i, pair)| if pair.ordered() { Some(i+1) } else { None }
-        ).sum();
-        println!("{}", res);
-    }
+// Diagnostics go to stderr so stdout only ever holds answers
+struct Logger;
 
-    fn challenge_26() {
-        let (div1, div2) = Packet::dividers();
-        let mut data = io::input_as_packets(13);
-        data.push(div1.clone());
-        data.push(div2.clone());
-        data.sort();
-        let res : usize = data.iter().enumerate().filter_map(
-            |(i,p)| if *p==div1 || *p==div2 { Some(i+1) } else { None }
-        ).product();
-        println!("{}", res);
+impl Log for Logger {
+    fn enabled(&self, metadata : &Metadata) -> bool {
+        metadata.level() <= log::max_level()
     }
 
-    fn challenge_27() {
-        let mut data = io::input_as_cave(14);
-        let res = data.pour_void();
-        println!("{}", res);
+    fn log(&self, record : &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        match record.level() {
+            Level::Error | Level::Warn | Level::Info => eprintln!(
+                "{}: {}", record.level().as_str().to_lowercase(), record.args()
+            ),
+            _ => eprintln!(
+                "{}: [{}] {}", record.level().as_str().to_lowercase(), record.target(), record.args()
+            )
+        }
     }
 
-    fn challenge_28() {
-        let mut data = io::input_as_cave(14);
-        let res = data.pour_wall();
-        println!("{}", res);
-    }
+    fn flush(&self) {}
+}
 
-    fn challenge_29() {
-        let data = io::input_as_sensors(15);
-        let res = data.no_becons_row(2000000);
-        println!("{}", res);
-    }
+static LOGGER : Logger = Logger;
 
-    fn challenge_30() {
-        let data = io::input_as_sensors(15);
-        let pt = data.find_beacon(0, 4000000, 0, 4000000).unwrap();
-        let res = pt.0 * 4000000 + pt.1;
-        println!("{}", res);
+fn init_logging(verbose : u8) {
+    let level = match verbose {
+        0 => LevelFilter::Warn,
+        1 => LevelFilter::Info,
+        2 => LevelFilter::Debug,
+        _ => LevelFilter::Trace
+    };
+    if log::set_logger(&LOGGER).is_ok() {
+        log::set_max_level(level);
     }
+}
 
-    fn challenge_31() {
-        let data = io::input_as_network(16);
-        let res = data.maximum_pressure();
-        println!("{}", res);
+fn input_for(cli : &Cli, day : u8) -> Input {
+    match (&cli.input, &cli.data_dir) {
+        (Some(path), _) if path.as_os_str() == "-" => Input::stdin(),
+        (Some(path), _) => Input::file(path),
+        (None, Some(dir)) => Input::in_dir(dir, day),
+        (None, None) => Input::day(day)
     }
+}
 
-    fn challenge_32() {
-        let data = io::input_as_network(16);
-        let res = data.maximum_pressure_dual();
-        println!("{}", res);
+// Timing shown after an answer when --time is given
+fn timing_note(cli : &Cli, timing : &Timing) -> String {
+    if cli.time {
+        format!(" ({})", timing)
+    } else {
+        String::new()
     }
+}
 
-    fn challenge_33() {
-        let mut data = io::input_as_jets(17);
-        let mut game = Game::new();
-        game.simulate(2022, &mut data);
-        let res = game.height();
-        println!("{}", res);
+// The solver counters of the part just run, when built with metrics
+fn report_counters(day : u8, part : Part) {
+    let counters = metrics::take();
+    if !counters.is_empty() {
+        eprintln!("Day {} part {}: {}", day, part, counters);
     }
+}
 
-    fn challenge_34() {
-        let mut data = io::input_as_jets(17);
-        let mut game = Game::new();
-        let res = game.simulate_long(1000000000000, &mut data);
-        println!("{}", res);
+fn run(cli : &Cli, registry : &Registry, day : u8, part : Option<u8>) -> ExitCode {
+    let puzzle = match registry.get(day) {
+        Some(puzzle) => puzzle,
+        None => {
+            eprintln!("Day {} is not implemented, see the list command", day);
+            return ExitCode::FAILURE;
+        }
+    };
+    let parts = match part.and_then(Part::from_num) {
+        Some(part) if !puzzle.parts().contains(&part) => {
+            eprintln!("Day {} part {} is not implemented, see the list command", day, part);
+            return ExitCode::FAILURE;
+        },
+        Some(part) => vec!(part),
+        None => puzzle.parts()
+    };
+    let input = input_for(cli, day);
+    if cli.format == Format::Json {
+        return json_records(parts.into_iter().map(
+            |part| runner::run_part(puzzle, part, &input)
+        ));
+    }
+    for part in parts {
+        match puzzle.run_timed(part, &input) {
+            Ok((answer, timing)) => {
+                println!("{}", answer);
+                if cli.time {
+                    eprintln!("Day {} part {}: {}", day, part, timing);
+                }
+                report_counters(day, part);
+            },
+            Err(err) => {
+                eprintln!("Error: {}", err);
+                return ExitCode::FAILURE;
+            }
+        }
     }
+    ExitCode::SUCCESS
+}
 
-    fn challenge_35() {
-        let data = io::input_as_lava(18);
-        let res = data.surface_area();
-        println!("{}", res);
+// Print the outcomes as json records, failing if any part failed
+fn json_records<I>(outcomes : I) -> ExitCode
+  where I : Iterator<Item = runner::Outcome>
+{
+    let mut failed = false;
+    for outcome in outcomes {
+        println!("{}", outcome.to_json());
+        failed |= outcome.is_failed();
     }
+    if failed { ExitCode::FAILURE } else { ExitCode::SUCCESS }
+}
 
-    fn challenge_36() {
-        let data = io::input_as_lava(18);
-        let res = data.outside_surface_area();
-        println!("{}", res);
+fn list(registry : &Registry) -> ExitCode {
+    println!("Day  Part 1  Part 2");
+    for day in 1..=25 {
+        let parts = registry.get(day).map(|puzzle| puzzle.parts()).unwrap_or_default();
+        let mark = |part| if parts.contains(&part) { "yes" } else { "-" };
+        println!("{:>3}  {:<6}  {}", day, mark(Part::One), mark(Part::Two));
     }
+    ExitCode::SUCCESS
+}
 
-    fn challenge_37() {
-        let data = io::input_as_blueprints(19);
-        let res : usize = data.iter().enumerate().map(
-            |(i, bp)|(i+1) * bp.max_geodes(26)
-        ).sum();
-        println!("{}", res);
+fn all(cli : &Cli, registry : &Registry) -> ExitCode {
+    if cli.format == Format::Json {
+        return json_records(registry.iter().flat_map(|puzzle| {
+            let input = input_for(cli, puzzle.day());
+            puzzle.parts().into_iter().map(
+                move |part| runner::run_part(puzzle, part, &input)
+            ).collect::<Vec<_>>()
+        }));
+    }
+    let mut failed = false;
+    for puzzle in registry.iter() {
+        let input = input_for(cli, puzzle.day());
+        for part in puzzle.parts() {
+            match puzzle.run_timed(part, &input) {
+                Ok((answer, timing)) => {
+                    println!("Day {} part {}: {}{}", puzzle.day(), part, answer, timing_note(cli, &timing));
+                    report_counters(puzzle.day(), part);
+                },
+                Err(err) if err.is_missing_input() => {
+                    eprintln!("Day {} part {}: skipped: {}", puzzle.day(), part, err);
+                },
+                Err(err) => {
+                    eprintln!("Day {} part {}: error: {}", puzzle.day(), part, err);
+                    failed = true;
+                }
+            }
+        }
     }
+    if failed { ExitCode::FAILURE } else { ExitCode::SUCCESS }
+}
 
-    fn challenge_39() {
-        let data = io::input_as_ints(20);
-        let encrypted = encrypted::File::make_file(&data);
-        let res = encrypted.data(1000) + encrypted.data(2000) + encrypted.data(3000);
-        println!("{}", res);
-    }
+fn all_parallel(cli : &Cli, registry : &Registry, jobs : usize) -> ExitCode {
+    let start = Instant::now();
+    let outcomes = match runner::run_parallel(registry, jobs, |day| input_for(cli, day)) {
+        Ok(outcomes) => outcomes,
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            return ExitCode::FAILURE;
+        }
+    };
+    let elapsed = start.elapsed();
+    if cli.format == Format::Json {
+        return json_records(outcomes.into_iter());
+    }
+    println!("Day  Part  Result    Time        Answer");
+    for outcome in outcomes.iter() {
+        let (result, shown) = match &outcome.status {
+            Status::Solved(answer) => ("ok", answer.to_string().replace('\n', " / ")),
+            Status::Skipped(err) => ("skipped", err.to_string()),
+            Status::Failed(err) => ("error", err.to_string()),
+            Status::Panicked(message) => ("panic", message.clone())
+        };
+        let time = match outcome.timing {
+            Some(_) => format!("{:.2?}", outcome.duration()),
+            None => "-".to_string()
+        };
+        println!("{:>3}  {:>4}  {:<8}  {:<10}  {}", outcome.day, outcome.part, result, time, shown);
+    }
+    for outcome in outcomes.iter().filter(|outcome| !outcome.counters.is_empty()) {
+        eprintln!("Day {} part {}: {}", outcome.day, outcome.part, outcome.counters);
+    }
+    let solved = outcomes.iter().filter(|outcome| outcome.is_solved()).count();
+    let failed = outcomes.iter().filter(|outcome| outcome.is_failed()).count();
+    println!(
+        "Solved {} of {} parts in {:.2?}, {} skipped without an input",
+        solved, outcomes.len(), elapsed, outcomes.len() - solved - failed
+    );
+    if failed == 0 { ExitCode::SUCCESS } else { ExitCode::FAILURE }
+}
 
-    fn challenge_40() {
-        let data = io::input_as_ints(20);
-        let encrypted = encrypted::File::make_file_key(&data, 811589153, 10);
-        let res = encrypted.data(1000) + encrypted.data(2000) + encrypted.data(3000);
-        println!("{}", res);
+fn verify(cli : &Cli, registry : &Registry, day : Option<u8>, record : bool, path : &Path) -> ExitCode {
+    let mut answers = match Answers::load(path) {
+        Ok(answers) => answers,
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            return ExitCode::FAILURE;
+        }
+    };
+    let mut failed = false;
+    let mut recorded = 0;
+    println!("Day  Part  Result   Answer");
+    for puzzle in registry.iter().filter(|puzzle| day.map(|d| d == puzzle.day()).unwrap_or(true)) {
+        let input = input_for(cli, puzzle.day());
+        for part in puzzle.parts() {
+            let (answer, timing) = match puzzle.run_timed(part, &input) {
+                Ok(result) => result,
+                Err(err) if err.is_missing_input() => {
+                    println!("{:>3}  {:>4}  skipped  {}", puzzle.day(), part, err);
+                    continue;
+                },
+                Err(err) => {
+                    println!("{:>3}  {:>4}  error    {}", puzzle.day(), part, err);
+                    failed = true;
+                    continue;
+                }
+            };
+            let shown = format!("{}{}", answer.to_string().replace('\n', " / "), timing_note(cli, &timing));
+            match answers.check(puzzle.day(), part, &answer) {
+                Verdict::Pass => println!("{:>3}  {:>4}  pass     {}", puzzle.day(), part, shown),
+                Verdict::Fail(expected) => {
+                    let expected = expected.to_string().replace('\n', " / ");
+                    println!("{:>3}  {:>4}  FAIL     {} (expected {})", puzzle.day(), part, shown, expected);
+                    failed = true;
+                },
+                Verdict::Missing => {
+                    println!("{:>3}  {:>4}  missing  {}", puzzle.day(), part, shown);
+                    if record {
+                        answers.record(puzzle.day(), part, answer);
+                        recorded += 1;
+                    }
+                }
+            }
+        }
     }
-
-    fn challenge_41() {
-        let data = io::input_as_riddles(21);
-        let res = data.solve("root").unwrap();
-        println!("{}", res);
+    if recorded > 0 {
+        if let Err(err) = answers.save(path) {
+            eprintln!("Error: {}", err);
+            return ExitCode::FAILURE;
+        }
+        println!("Recorded {} new answers in {}", recorded, path.display());
     }
+    if failed { ExitCode::FAILURE } else { ExitCode::SUCCESS }
+}
 
-    fn challenge_42() {
-        let data = io::input_as_riddles(21);
-        let poly = data.root_eqn("humn");
-        let res = poly.solve();
-        println!("{:?}", res);
+#[allow(clippy::too_many_arguments)]
+fn render(cli : &Cli, day : u8, to : Image, out : Option<&Path>, every : usize, limit : usize, scale : usize, delay : u16) -> ExitCode {
+    let mut animation = match days::animation(day, &input_for(cli, day)) {
+        Ok(Some(animation)) => animation,
+        Ok(None) => {
+            eprintln!("Day {} has no simulation to render, try days 9, 14, 17, 22 or 23", day);
+            return ExitCode::FAILURE;
+        },
+        Err(err) => {
+            eprintln!("Error: {}", err.on_day(day));
+            return ExitCode::FAILURE;
+        }
+    };
+    let frames = render::frames(animation.as_mut(), every, limit);
+    let written = match to {
+        Image::Text => {
+            for (i, frame) in frames.iter().enumerate() {
+                println!("Frame {}\n{}\n", i, frame);
+            }
+            return ExitCode::SUCCESS;
+        },
+        Image::Ppm => {
+            let dir = out.map(Path::to_path_buf).unwrap_or(PathBuf::from(format!("day-{}-frames", day)));
+            render::write_ppm(&frames, &dir, scale).map(|_| dir)
+        },
+        Image::Gif => {
+            let path = out.map(Path::to_path_buf).unwrap_or(PathBuf::from(format!("day-{}.gif", day)));
+            render::write_gif(&frames, &path, scale, delay).map(|_| path)
+        }
+    };
+    match written {
+        Ok(path) => {
+            log::info!("Wrote {} frames to {}", frames.len(), path.display());
+            ExitCode::SUCCESS
+        },
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            ExitCode::FAILURE
+        }
     }
+}
 
-    fn challenge_43() {
-        let data = io::input_as_map(22);
-        let res = data.simulate().val();
-        println!("{:?}", res);
+fn generate(day : u8, seed : u64, size : Option<usize>, out : Option<&Path>) -> ExitCode {
+    let text = match gen::generate(day, seed, size) {
+        Some(text) => text,
+        None => {
+            eprintln!("No generator for day {}", day);
+            return ExitCode::FAILURE;
+        }
+    };
+    match out {
+        Some(path) => match std::fs::write(path, text) {
+            Ok(()) => {
+                log::info!("Wrote day {} input to {}", day, path.display());
+                ExitCode::SUCCESS
+            },
+            Err(err) => {
+                eprintln!("Error: {}", error::Error::file(path, err));
+                ExitCode::FAILURE
+            }
+        },
+        None => {
+            print!("{}", text);
+            ExitCode::SUCCESS
+        }
     }
+}
 
-    fn challenge_45() {
-        let mut data = io::input_as_field(23);
-        data.simulate(10);
-        let res = data.empty_ground();
-        println!("{:?}", res);
+#[cfg(feature = "serde")]
+fn dump(cli : &Cli, registry : &Registry, day : u8) -> ExitCode {
+    let puzzle = match registry.get(day) {
+        Some(puzzle) => puzzle,
+        None => {
+            eprintln!("Day {} is not implemented, see the list command", day);
+            return ExitCode::FAILURE;
+        }
+    };
+    match puzzle.dump(&input_for(cli, day)) {
+        Ok(json) => {
+            println!("{:#}", json);
+            ExitCode::SUCCESS
+        },
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            ExitCode::FAILURE
+        }
     }
+}
 
-    fn challenge_46() {
-        let mut data = io::input_as_field(23);
-        let res = data.simulate_till_still();
-        println!("{:?}", res);
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    init_logging(cli.verbose);
+    error::set_strict(cli.strict);
+    if let Some(dir) = &cli.data_dir {
+        if !dir.is_dir() {
+            eprintln!("Data directory {} does not exist", dir.display());
+            return ExitCode::FAILURE;
+        }
     }
-   
-    pub fn challenge(num : u8) {
-        match num {
-            1 => challenge_1(),
-            2 => challenge_2(),
-            3 => challenge_3(),
-            4 => challenge_4(),
-            5 => challenge_5(),
-            6 => challenge_6(),
-            7 => challenge_7(),
-            8 => challenge_8(),
-            9 => challenge_9(),
-            10 => challenge_10(),
-            11 => challenge_11(),
-            12 => challenge_12(),
-            13 => challenge_13(),
-            14 => challenge_14(),
-            15 => challenge_15(),
-            16 => challenge_16(),
-            17 => challenge_17(),
-            18 => challenge_18(),
-            19 => challenge_19(),
-            20 => challenge_20(),
-            21 => challenge_21(),
-            22 => challenge_22(),
-            23 => challenge_23(),
-            24 => challenge_24(),
-            25 => challenge_25(),
-            26 => challenge_26(),
-            27 => challenge_27(),
-            28 => challenge_28(),
-            29 => challenge_29(),
-            30 => challenge_30(),
-            31 => challenge_31(),
-            32 => challenge_32(),
-            33 => challenge_33(),
-            34 => challenge_34(),
-            35 => challenge_35(),
-            36 => challenge_36(),
-            37 => challenge_37(),
-            39 => challenge_39(),
-            40 => challenge_40(),
-            41 => challenge_41(),
-            42 => challenge_42(),
-            43 => challenge_43(),
-            45 => challenge_45(),
-            46 => challenge_46(),
-            _ => () 
+    let registry = days::registry();
+    match cli.command {
+        Command::Run { day, part } => run(&cli, &registry, day, part),
+        Command::List => list(&registry),
+        Command::All { .. } if cli.input.is_some() => {
+            eprintln!("--input names a single day, use --data-dir or {} with all", io::DATA_DIR_VAR);
+            ExitCode::FAILURE
+        },
+        Command::All { parallel : true, jobs } => all_parallel(&cli, &registry, jobs.unwrap_or(0)),
+        Command::All { .. } => all(&cli, &registry),
+        Command::Render { day, to, ref out, every, limit, scale, delay } => {
+            render(&cli, day, to, out.as_deref(), every, limit, scale, delay)
+        },
+        Command::Gen { day, seed, size, ref out } => generate(day, seed, size, out.as_deref()),
+        #[cfg(feature = "serde")]
+        Command::Dump { day } => dump(&cli, &registry, day),
+        Command::Verify { day, record, ref answers } => {
+            let path = answers.clone().unwrap_or(answers_path(cli.data_dir.as_deref()));
+            verify(&cli, &registry, day, record, &path)
         }
     }
 }
-
-fn main() {
-    let args : Vec<String> = std::env::args().collect();
-    let ver = args.get(1).unwrap().parse::<u8>().unwrap();
-    challenge::challenge(ver);
-}
\ No newline at end of file
@@ -1,213 +1,433 @@
-use std::collections::HashMap;
-use std::cmp::max;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
 
-#[derive(Clone, Copy, Debug)]
-enum Dir {
-    N,
-    E,
-    S,
-    W
-}
+use itertools::Itertools;
+
+use crate::error::{Error, Result, parse_num};
+use crate::geometry::{Direction, Point2, Point3};
+use crate::grid::SparseGrid;
+use crate::render::{Animate, Frame};
 
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct State {
-    dir : Dir,
-    pos : (i64, i64)
+    dir : Direction,
+    pos : Point2
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 enum Instruction {
     Left,
     Right,
     Forward(usize)
 }
 
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Map {
-    map : HashMap<(i64, i64), bool>,
-    min : (i64, i64),
-    max : (i64, i64),
+    map : SparseGrid<bool>,
     instructions : Vec<Instruction>
 }
 
-fn parse_instructions(string : &str) -> Vec<Instruction> {
+// A face of the net folded into a cube. The cube is centred on the origin
+// and the vectors give where the face points and where east and south on
+// the net point once folded
+#[derive(Clone, Copy)]
+struct Face {
+    corner : Point2,
+    normal : Point3,
+    right : Point3,
+    down : Point3
+}
+
+struct Cube {
+    size : i64,
+    faces : Vec<Face>
+}
+
+// The path followed an instruction a step, leaving a trail of the way
+// each tile was last crossed
+pub struct Walk {
+    map : Map,
+    cube : Option<Cube>,
+    state : State,
+    next : usize,
+    trail : HashMap<Point2, Direction>
+}
+
+fn tile(ch : char) -> Option<bool> {
+    match ch {
+        '.' => Some(false),
+        '#' => Some(true),
+        _ => None
+    }
+}
+
+fn parse_instructions(string : &str) -> Result<Vec<Instruction>> {
     let mut iter = string.chars();
     let mut end = false;
     let mut instructions = Vec::new();
     while !end {
-        let num_s = iter.clone().take_while(|c| c.is_numeric()).collect::<String>();
+        let rest = iter.as_str();
+        let num_s = iter.clone().take_while(|c| c.is_ascii_digit()).collect::<String>();
         if !num_s.is_empty() {
-            let res = iter.advance_by(num_s.len());
-            if res.is_err() {
-                end = true
-            }
-            instructions.push(Instruction::Forward(num_s.parse().unwrap()));
+            iter = rest[num_s.len()..].chars();
+            let num = parse_num(string, &rest[..num_s.len()])?;
+            instructions.push(Instruction::Forward(num));
         } else {
             match iter.next() {
                 Some('R') => instructions.push(Instruction::Right),
                 Some('L') => instructions.push(Instruction::Left),
-                _ => end = true
+                Some(_) => return Err(Error::at(string, rest, "a distance, L or R")),
+                None => end = true
             }
         }
     } 
-    println!("{:?}", instructions);
-    instructions
+    log::debug!("Parsed {} instructions", instructions.len());
+    Ok(instructions)
 }
 
 impl Map {
-    pub fn from_lines(lines : &Vec<String>) -> Map {
-        let last_str = lines.last().unwrap();
-        let mut map = HashMap::new();
-        let mut max_i : (i64, i64) = (0,0);
-        for (j, line) in lines.split_last().unwrap().1.iter().enumerate() {
-            for (i,c) in line.char_indices() {
-                if c == '.' {
-                    map.insert((i as i64, j as i64), false);
-                }
-                if c == '#' {
-                    map.insert((i as i64, j as i64), true);
-                }
-                max_i.1 = max(max_i.1, j as i64);
-            }
-            max_i.0 = max(max_i.0, j as i64);
+    pub fn from_lines(lines : &Vec<String>) -> Result<Map> {
+        let (last_str, map_lines) = lines.split_last().ok_or(
+            Error::invalid("missing the map and the path")
+        )?;
+        let map = SparseGrid::parse(map_lines, " ", tile, "one of '.', '#' or ' '")?;
+        if map.is_empty() {
+            return Err(Error::invalid("no tiles in the map"));
         }
-        let instructions = parse_instructions(last_str);
+        let instructions = parse_instructions(last_str).map_err(
+            |err| err.offset_lines(lines.len() - 1)
+        )?;
 
-        Map {
+        Ok(Map {
             map : map,
-            min : (0,0),
-            max : max_i,
             instructions : instructions
-        }
+        })
     }
 
     pub fn simulate(&self) -> State {
-        let top = self.min.1;
-        let start = (self.range_row(top).0, top);
-        let mut state = State { pos : start, dir : Dir::E };
-        println!("Start: {:?}", state);
+        self.walk(|state| self.wrap_flat(state))
+    }
+
+    // Walk the path with the map folded up into a cube
+    pub fn simulate_cube(&self) -> Result<State> {
+        let cube = Cube::fold(&self.map)?;
+        Ok(self.walk(|state| cube.wrap(state)))
+    }
+
+    // Follow the instructions, wrap gives where a step off the map lands
+    fn walk<F>(&self, wrap : F) -> State
+      where F : Fn(&State) -> State
+    {
+        let mut state = self.start();
+        log::debug!("Start: {:?}", state);
         for instruction in self.instructions.iter() {
-            state = self.do_instruction(instruction, &state);
-            println!("{:?}", state);
+            state = match instruction {
+                Instruction::Left => State { pos : state.pos, dir : state.dir.left() },
+                Instruction::Right => State { pos : state.pos, dir : state.dir.right() },
+                Instruction::Forward(n) => (0..*n).fold(state, |state, _| self.move_one(&state, &wrap))
+            };
+            log::trace!("{:?} -> {:?}", instruction, state);
         }
         state
     }
 
-    fn range_row(&self, i : i64) -> (i64, i64) {
-        let min = (self.min.0 ..= self.max.0).find(
-            |j| self.map.contains_key(&(*j,i))
-        ).unwrap();
-        let max = (self.min.0 ..= self.max.0).rev().find(
-            |j| self.map.contains_key(&(*j,i))
-        ).unwrap();
-        (min, max)
+    // The leftmost open tile of the top row, facing east
+    fn start(&self) -> State {
+        let top = self.map.bounds().unwrap().min.y;
+        State { pos : self.map.row(top).next().unwrap(), dir : Direction::East }
     }
 
-    fn range_col(&self, i : i64) -> (i64, i64) {
-        let min = (self.min.1 ..= self.max.1).find(
-            |j| self.map.contains_key(&(i,*j))
-        ).unwrap();
-        let max = (self.min.1 ..= self.max.1).rev().find(
-            |j| self.map.contains_key(&(i,*j))
-        ).unwrap();
-        (min, max)
+    fn move_one<F>(&self, state : &State, wrap : F) -> State
+      where F : Fn(&State) -> State
+    {
+        let mut next = State { pos : state.pos.step(state.dir), dir : state.dir };
+        if !self.map.contains(next.pos) {
+            next = wrap(state);
+        }
+        if *self.map.get(next.pos).unwrap() {
+            *state
+        } else {
+            next
+        }
     }
 
-    fn do_instruction(&self, instruction : &Instruction, state : &State) -> State {
-        match instruction {
-            Instruction::Left => State { pos : state.pos, dir : state.dir.left() },
-            Instruction::Right => State { pos : state.pos, dir : state.dir.right() },
-            Instruction::Forward(n) => State { pos : self.move_forward(state.pos, *n, &state.dir), dir : state.dir}
-        }
+    // Off the edge wrap round to the other end of the row or column
+    fn wrap_flat(&self, state : &State) -> State {
+        let pos = match state.dir {
+            Direction::East => self.map.row(state.pos.y).next(),
+            Direction::West => self.map.row(state.pos.y).next_back(),
+            Direction::South => self.map.column(state.pos.x).next(),
+            Direction::North => self.map.column(state.pos.x).next_back()
+        }.unwrap();
+        State { pos : pos, dir : state.dir }
     }
+}
 
-    fn move_forward(&self, pos : (i64, i64), num : usize, dir : &Dir) -> (i64, i64) {
-        let mut new_pos = pos;
-        for _ in 0..num {
-            new_pos = self.move_one(new_pos, dir);
+impl Face {
+    // Where a direction on the net points on the cube
+    fn axis(&self, dir : Direction) -> Point3 {
+        match dir {
+            Direction::East => self.right,
+            Direction::West => -self.right,
+            Direction::South => self.down,
+            Direction::North => -self.down
         }
-        new_pos
     }
 
-    fn move_one(&self, pos : (i64, i64), dir : &Dir) -> (i64,i64) {
-        let new_pos = match dir {
-            Dir::N => self.move_one_col(pos, true),
-            Dir::S => self.move_one_col(pos, false),
-            Dir::W => self.move_one_row(pos, true),
-            Dir::E => self.move_one_row(pos, false)
+    // The face over the edge in direction dir once folded
+    fn fold(&self, dir : Direction, corner : Point2) -> Face {
+        let axis = self.axis(dir);
+        let fold_vec = |vec : Point3| {
+            // Rotate a vector of this face over the edge, the normal becomes
+            // the axis and the axis turns to point into the cube
+            if vec == axis {
+                -self.normal
+            } else if vec == -axis {
+                self.normal
+            } else {
+                vec
+            }
         };
-        if *self.map.get(&new_pos).unwrap() {
-            pos
-        } else {
-            new_pos
-        }
+        Face { corner : corner, normal : axis, right : fold_vec(self.right), down : fold_vec(self.down) }
     }
+}
 
-    fn move_one_row(&self, pos : (i64, i64), sign : bool) -> (i64,i64) {
-        let (min, max) = self.range_row(pos.1);
-        let mut new_pos = if sign {
-            (pos.0 - 1, pos.1)
-        } else {
-            (pos.0 + 1, pos.1)
-        };
-        if new_pos.0 < min {
-            new_pos.0 = max;
+impl Cube {
+    // Fold the net of a cube, the faces are found from the size of the
+    // map and folded out from the first face
+    fn fold(map : &SparseGrid<bool>) -> Result<Cube> {
+        let not_a_net = || Error::invalid("the map is not a cube net");
+        let size = ((map.len() / 6) as f64).sqrt().round() as i64;
+        if size == 0 || (6 * size * size) as usize != map.len() {
+            return Err(not_a_net());
         }
-        if new_pos.0 > max {
-            new_pos.0 = min;
+        let bounds = map.bounds().unwrap();
+        let corners : Vec<Point2> = bounds.positions().filter(
+            |pos| pos.x.rem_euclid(size) == 0 && pos.y.rem_euclid(size) == 0 && map.contains(*pos)
+        ).collect();
+        let full = corners.iter().all(
+            |corner| (0..size).all(|y| (0..size).all(|x| map.contains(*corner + Point2::new(x, y))))
+        );
+        if corners.len() != 6 || !full {
+            return Err(not_a_net());
         }
-        new_pos
-    }
 
-    fn move_one_col(&self, pos : (i64, i64), sign : bool) -> (i64,i64) {
-        let (min, max) = self.range_col(pos.0);
-        let mut new_pos = if sign {
-            (pos.0, pos.1 - 1)
-        } else {
-            (pos.0, pos.1 + 1)
+        let first = Face {
+            corner : corners[0],
+            normal : Point3::new(0, 0, 1),
+            right : Point3::new(1, 0, 0),
+            down : Point3::new(0, 1, 0)
         };
-        if new_pos.1 < min {
-            new_pos.1 = max;
+        let mut faces = vec!(first);
+        let mut queue = VecDeque::from(vec!(first));
+        while let Some(face) = queue.pop_front() {
+            for dir in Direction::all() {
+                let corner = face.corner + dir.step() * size;
+                if corners.contains(&corner) && faces.iter().all(|other| other.corner != corner) {
+                    let next = face.fold(dir, corner);
+                    faces.push(next);
+                    queue.push_back(next);
+                }
+            }
         }
-        if new_pos.1 > max {
-            new_pos.1 = min;
+        // Six faces pointing different ways, net with overlaps fail here
+        let distinct = faces.iter().enumerate().all(
+            |(i, face)| faces[..i].iter().all(|other| other.normal != face.normal)
+        );
+        if faces.len() != 6 || !distinct {
+            return Err(not_a_net());
         }
-        new_pos
+        Ok(Cube { size : size, faces : faces })
     }
 
+    fn face_at(&self, pos : Point2) -> &Face {
+        let corner = Point2::new(
+            pos.x.div_euclid(self.size) * self.size,
+            pos.y.div_euclid(self.size) * self.size
+        );
+        self.faces.iter().find(|face| face.corner == corner).unwrap()
+    }
+
+    // Step over the edge of a face onto the face it is folded against.
+    // Cells are placed on the cube at twice scale so their centres are on
+    // whole numbers, the cube runs from -size to size on each axis
+    fn wrap(&self, state : &State) -> State {
+        let size = self.size;
+        let from = self.face_at(state.pos);
+        let local = state.pos - from.corner;
+        let centre = from.normal * size
+            + from.right * (2 * local.x + 1 - size)
+            + from.down * (2 * local.y + 1 - size);
+        let axis = from.axis(state.dir);
+        let to = self.faces.iter().find(|face| face.normal == axis).unwrap();
+        let over = centre + axis - from.normal;
+        let pos = to.corner + Point2::new(
+            (over.dot(to.right) + size - 1) / 2,
+            (over.dot(to.down) + size - 1) / 2
+        );
+        let dir = Direction::all().into_iter().find(
+            |dir| to.axis(*dir) == -from.normal
+        ).unwrap();
+        State { pos : pos, dir : dir }
+    }
+}
 
+impl Walk {
+    // Walk the flat map or the map folded into a cube
+    pub fn new(map : Map, cube : bool) -> Result<Walk> {
+        let cube = if cube { Some(Cube::fold(&map.map)?) } else { None };
+        let state = map.start();
+        Ok(Walk { map : map, cube : cube, state : state, next : 0, trail : HashMap::from([(state.pos, state.dir)]) })
+    }
+}
+
+impl Animate for Walk {
+    fn step(&mut self) -> bool {
+        let instruction = match self.map.instructions.get(self.next) {
+            Some(instruction) => instruction,
+            None => return false
+        };
+        let map = &self.map;
+        let wrap = |state : &State| match &self.cube {
+            Some(cube) => cube.wrap(state),
+            None => map.wrap_flat(state)
+        };
+        self.state = match instruction {
+            Instruction::Left => State { pos : self.state.pos, dir : self.state.dir.left() },
+            Instruction::Right => State { pos : self.state.pos, dir : self.state.dir.right() },
+            Instruction::Forward(n) => {
+                let mut state = self.state;
+                for _ in 0..*n {
+                    state = map.move_one(&state, wrap);
+                    self.trail.insert(state.pos, state.dir);
+                }
+                state
+            }
+        };
+        self.trail.insert(self.state.pos, self.state.dir);
+        self.next += 1;
+        true
+    }
+
+    fn frame(&self) -> Frame {
+        Frame::draw(self.map.map.bounds().unwrap(), |pos| match (self.map.map.get(pos), self.trail.get(&pos)) {
+            (None, _) => ' ',
+            (Some(true), _) => '#',
+            (Some(false), Some(Direction::East)) => '>',
+            (Some(false), Some(Direction::South)) => 'v',
+            (Some(false), Some(Direction::West)) => '<',
+            (Some(false), Some(Direction::North)) => '^',
+            (Some(false), None) => '.'
+        })
+    }
 }
 
 impl State {
     pub fn val(&self) -> usize {
-        (1000*(self.pos.1+1) + 4*(self.pos.0+1) + self.dir.val()) as usize
+        (1000*(self.pos.y+1) + 4*(self.pos.x+1) + facing(self.dir)) as usize
     }
 }
 
-impl Dir {
-    fn val(&self) -> i64 {
-        match self {
-            Dir::E => 0,
-            Dir::S => 1,
-            Dir::W => 2,
-            Dir::N => 3
-        }
+fn facing(dir : Direction) -> i64 {
+    match dir {
+        Direction::East => 0,
+        Direction::South => 1,
+        Direction::West => 2,
+        Direction::North => 3
     }
+}
 
-    fn left(&self) -> Dir {
-        match self {
-            Dir::E => Dir::N,
-            Dir::S => Dir::E,
-            Dir::W => Dir::S,
-            Dir::N => Dir::W
+// The rows are written from the left edge with the gaps as spaces, then a
+// blank line and the path
+impl fmt::Display for Map {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let bounds = self.map.bounds().unwrap();
+        for y in 0..=bounds.max.y {
+            let row : String = (0..=bounds.max.x).map(
+                |x| match self.map.get(Point2::new(x, y)) {
+                    Some(true) => '#',
+                    Some(false) => '.',
+                    None => ' '
+                }
+            ).collect();
+            writeln!(f, "{}", row.trim_end())?;
         }
+        write!(f, "\n{}", self.instructions.iter().map(
+            |instruction| match instruction {
+                Instruction::Left => "L".to_string(),
+                Instruction::Right => "R".to_string(),
+                Instruction::Forward(n) => n.to_string()
+            }
+        ).join(""))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::{self, Input};
+    use crate::render;
+
+    const EXAMPLE : &str = include_str!("../../data/examples/day-22.txt");
+
+    #[test]
+    fn walk() {
+        let map = io::input_as_map(&Input::text(EXAMPLE)).unwrap();
+        assert_eq!(map.simulate().val(), 6032);
+    }
+
+    #[test]
+    fn walk_cube() {
+        let map = io::input_as_map(&Input::text(EXAMPLE)).unwrap();
+        assert_eq!(map.simulate_cube().unwrap().val(), 5031);
     }
 
-    fn right(&self) -> Dir {
-        match self {
-            Dir::E => Dir::S,
-            Dir::S => Dir::W,
-            Dir::W => Dir::N,
-            Dir::N => Dir::E
+    // Walking straight round the cube comes back to where you started
+    #[test]
+    fn great_circles() {
+        let map = io::input_as_map(&Input::text(EXAMPLE)).unwrap();
+        let cube = Cube::fold(&map.map).unwrap();
+        for (pos, _) in map.map.iter() {
+            for dir in Direction::all() {
+                let mut state = State { pos : pos, dir : dir };
+                for _ in 0..4 * cube.size {
+                    let next = State { pos : state.pos.step(state.dir), dir : state.dir };
+                    state = if map.map.contains(next.pos) { next } else { cube.wrap(&state) };
+                }
+                assert_eq!(state, State { pos : pos, dir : dir });
+            }
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn frames() {
+        let map = io::input_as_map(&Input::text(EXAMPLE)).unwrap();
+        let frames = render::frames(&mut Walk::new(map, false).unwrap(), 1, 100);
+        assert_eq!(frames.len(), 14);
+        let last = frames[13].to_string();
+        assert_eq!(last.lines().next().unwrap(), "        >>v#    ");
+        assert_eq!(last.lines().nth(5).unwrap(), ">>>v...>#.>>    ");
+        assert_eq!(last.lines().nth(7).unwrap(), "...>>>>v..#.    ");
+    }
+
+    #[test]
+    fn not_a_cube() {
+        let lines = vec!("......".to_string(), "".to_string(), "10R5".to_string());
+        assert!(Map::from_lines(&lines).unwrap().simulate_cube().is_err());
+    }
+
+    #[test]
+    fn bad_tile() {
+        let lines = vec!("..x.".to_string(), "".to_string(), "10R5".to_string());
+        assert!(Map::from_lines(&lines).is_err());
+    }
+
+    #[test]
+    fn round_trip() {
+        let map = io::input_as_map(&Input::text(EXAMPLE)).unwrap();
+        assert_eq!(map.to_string(), EXAMPLE.trim_end());
+        assert_eq!(io::input_as_map(&Input::text(&map.to_string())).unwrap(), map);
+    }
+}
@@ -0,0 +1,104 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+#[cfg(feature = "metrics")]
+use std::cell::RefCell;
+
+// Counts of the key operations of the solvers, by name. Each thread keeps
+// its own so parts run side by side are counted apart. Without the metrics
+// feature nothing is counted and the calls compile away
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Counters {
+    counts : BTreeMap<&'static str, u64>
+}
+
+#[cfg(feature = "metrics")]
+thread_local! {
+    static COUNTERS : RefCell<Counters> = RefCell::new(Counters::default());
+}
+
+pub fn enabled() -> bool {
+    cfg!(feature = "metrics")
+}
+
+// Add to a counter, hot loops should count locally and add once at the end
+#[cfg(feature = "metrics")]
+pub fn add(name : &'static str, num : u64) {
+    COUNTERS.with(|counters| *counters.borrow_mut().counts.entry(name).or_insert(0) += num);
+}
+
+#[cfg(not(feature = "metrics"))]
+#[inline(always)]
+pub fn add(_name : &'static str, _num : u64) {}
+
+pub fn count(name : &'static str) {
+    add(name, 1);
+}
+
+// The counts on this thread since the last take, which starts them again
+#[cfg(feature = "metrics")]
+pub fn take() -> Counters {
+    COUNTERS.with(|counters| counters.take())
+}
+
+#[cfg(not(feature = "metrics"))]
+pub fn take() -> Counters {
+    Counters::default()
+}
+
+impl Counters {
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    pub fn get(&self, name : &str) -> u64 {
+        self.counts.get(name).copied().unwrap_or(0)
+    }
+
+    // The counters in order of name
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, u64)> + '_ {
+        self.counts.iter().map(|(name, num)| (*name, *num))
+    }
+}
+
+impl fmt::Display for Counters {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let counts : Vec<String> = self.iter().map(|(name, num)| format!("{} {}", name, num)).collect();
+        write!(f, "{}", counts.join(", "))
+    }
+}
+
+#[cfg(all(test, feature = "metrics"))]
+mod tests {
+    use super::*;
+    use crate::io::{self, Input};
+
+    #[test]
+    fn counted() {
+        take();
+        count("test.one");
+        add("test.many", 5);
+        add("test.many", 2);
+        let counters = take();
+        assert_eq!(counters.get("test.one"), 1);
+        assert_eq!(counters.get("test.many"), 7);
+        assert_eq!(counters.get("test.none"), 0);
+        assert_eq!(counters.to_string(), "test.many 7, test.one 1");
+        assert!(take().is_empty());
+    }
+
+    #[test]
+    fn solvers() {
+        take();
+        let network = io::input_as_network(&Input::text(include_str!("../../data/examples/day-16.txt"))).unwrap();
+        network.maximum_pressure();
+        let counters = take();
+        assert!(counters.get("pressure.calls") > 0);
+        assert!(counters.get("pressure.cache_hits") > 0);
+        let mut jets = io::input_as_jets(&Input::text(include_str!("../../data/examples/day-17.txt"))).unwrap();
+        crate::tetris::Game::new().simulate(10, &mut jets);
+        let counters = take();
+        assert_eq!(counters.get("tetris.shapes_dropped"), 10);
+        assert!(counters.get("tetris.collision_checks") >= 20);
+    }
+}
@@ -1,9 +1,19 @@
+use std::fmt;
+
+use itertools::Itertools;
+
+use crate::error::{Error, Result, lines_before, parse_num};
+
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 enum Operation {
     Square,
     Add(usize),
     Mult(usize)
 }
 
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 struct Monkey {
     items : Vec<usize>,
     operation : Operation,
@@ -14,19 +24,19 @@ struct Monkey {
 }
 
 impl Operation {
-    fn from_string(string : &str) -> Option<Operation> {
+    fn from_string(string : &str) -> Result<Operation> {
         if string == "new = old * old" {
-            Some(Operation::Square)
+            Ok(Operation::Square)
         } else {
             if let Some(val_s) = string.strip_prefix("new = old * ") {
-                let val = val_s.parse::<usize>().ok()?;
-                Some(Operation::Mult(val))
+                let val = parse_num(string, val_s)?;
+                Ok(Operation::Mult(val))
             } else {
                 if let Some(val_s) = string.strip_prefix("new = old + ") {
-                    let val = val_s.parse::<usize>().ok()?;
-                    Some(Operation::Add(val))
+                    let val = parse_num(string, val_s)?;
+                    Ok(Operation::Add(val))
                 } else {
-                    None
+                    Err(Error::line(string, "an operation on old"))
                 }
             }
         }
@@ -41,27 +51,53 @@ impl Operation {
     } 
 }
 
+// The rest of line i of a monkey after the given prefix
+fn field<'a>(lines : &[&'a str], i : usize, prefix : &str) -> Result<&'a str> {
+    let line = lines.get(i).ok_or(
+        Error::line("", prefix.trim()).offset_lines(i)
+    )?;
+    line.strip_prefix(prefix).ok_or(
+        Error::line(line, prefix.trim()).offset_lines(i)
+    )
+}
+
 impl Monkey {
-    fn from_string(string : &str) -> Option<Monkey> {
+    // The monkey must be the index-th, as the others throw to it by number
+    fn from_string(string : &str, index : usize) -> Result<Monkey> {
         let lines : Vec<&str> = string.lines().collect();
-        let start_str = *lines.get(1)?;
-        let items_s = start_str.strip_prefix("  Starting items: ")?;
-        let items = items_s.split(", ").filter_map(
-            |part| part.parse::<usize>().ok()
-        ).collect();
-        let operation_str = *lines.get(2)?;
-        let operation_s = operation_str.strip_prefix("  Operation: ")?;
-        let operation = Operation::from_string(operation_s)?;
-        let div_str = *lines.get(3)?;
-        let div_s = div_str.strip_prefix("  Test: divisible by ")?;
-        let div = div_s.parse::<usize>().ok()?;
-        let true_str = *lines.get(4)?;
-        let true_s = true_str.strip_prefix("    If true: throw to monkey ")?;
-        let true_v = true_s.parse::<usize>().ok()?;
-        let false_str = *lines.get(5)?;
-        let false_s = false_str.strip_prefix("    If false: throw to monkey ")?;
-        let false_v = false_s.parse::<usize>().ok()?;
-        Some(
+        let num_s = field(&lines, 0, "Monkey ")?;
+        let num_s = num_s.strip_suffix(':').ok_or(
+            Error::line(lines[0], "Monkey N:")
+        )?;
+        if parse_num::<usize>(lines[0], num_s)? != index {
+            return Err(Error::at(lines[0], num_s, &format!("monkey {}", index)));
+        }
+        let items_s = field(&lines, 1, "  Starting items: ")?;
+        let items = items_s.split(", ").map(
+            |part| parse_num(lines[1], part)
+        ).collect::<Result<Vec<usize>>>().map_err(
+            |err| err.offset_lines(1)
+        )?;
+        let operation_s = field(&lines, 2, "  Operation: ")?;
+        let operation = Operation::from_string(operation_s).map_err(
+            |err| err.within(lines[2], operation_s).offset_lines(2)
+        )?;
+        let div_s = field(&lines, 3, "  Test: divisible by ")?;
+        let div = parse_num(lines[3], div_s).map_err(
+            |err| err.offset_lines(3)
+        )?;
+        let true_s = field(&lines, 4, "    If true: throw to monkey ")?;
+        let true_v = parse_num(lines[4], true_s).map_err(
+            |err| err.offset_lines(4)
+        )?;
+        let false_s = field(&lines, 5, "    If false: throw to monkey ")?;
+        let false_v = parse_num(lines[5], false_s).map_err(
+            |err| err.offset_lines(5)
+        )?;
+        if div == 0 {
+            return Err(Error::at(lines[3], div_s, "a non zero divisor").offset_lines(3));
+        }
+        Ok(
             Monkey {
                 items: items, 
                 operation: operation, 
@@ -80,10 +116,10 @@ impl Monkey {
         for item in self.items.iter() {
             let mut new_item = self.operation.op(item);
             if div3 {
-                new_item = new_item / 3;
+                new_item /= 3;
             }
-            new_item = new_item % lcm;
-            if new_item % self.test_div == 0 {
+            new_item %= lcm;
+            if new_item.is_multiple_of(self.test_div) {
                 next_true.push(new_item);
             } else {
                 next_false.push(new_item);
@@ -99,17 +135,30 @@ impl Monkey {
     }
 }
 
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Monkeys {
     monkeys : Vec<Monkey>
 }
 
 
 impl Monkeys {
-    pub fn from_string(string : &str) -> Monkeys {
-        let monkeys = string.split("\n\n").filter_map(
-            |string| Monkey::from_string(string)
-        ).collect();
-        Monkeys { monkeys: monkeys }
+    // A monkey that does not parse is an error even in lenient mode, as
+    // skipping it would throw items to the wrong monkeys
+    pub fn from_string(string : &str) -> Result<Monkeys> {
+        let mut monkeys : Vec<Monkey> = Vec::new();
+        for block in string.split("\n\n").filter(|block| !block.trim().is_empty()) {
+            let monkey = Monkey::from_string(block, monkeys.len()).map_err(
+                |err| err.offset_lines(lines_before(string, block))
+            )?;
+            monkeys.push(monkey);
+        }
+        for monkey in monkeys.iter() {
+            if monkey.next_true >= monkeys.len() || monkey.next_false >= monkeys.len() {
+                return Err(Error::invalid("a monkey throws to a monkey that does not exist"));
+            }
+        }
+        Ok(Monkeys { monkeys: monkeys })
     }
 
     pub fn simulate(&mut self, steps : usize, div3: bool) {
@@ -147,4 +196,71 @@ impl Monkeys {
     }
 
 
-}
\ No newline at end of file
+}
+
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Operation::Square => write!(f, "new = old * old"),
+            Operation::Add(val) => write!(f, "new = old + {}", val),
+            Operation::Mult(val) => write!(f, "new = old * {}", val)
+        }
+    }
+}
+
+// The monkeys as they start out, the activity so far is not part of the notes
+impl fmt::Display for Monkeys {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.monkeys.iter().enumerate().map(
+            |(i, monkey)| format!(
+                "Monkey {}:\n  Starting items: {}\n  Operation: {}\n  Test: divisible by {}\n    If true: throw to monkey {}\n    If false: throw to monkey {}",
+                i, monkey.items.iter().join(", "), monkey.operation, monkey.test_div, monkey.next_true, monkey.next_false
+            )
+        ).join("\n\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::{self, Input};
+
+    const EXAMPLE : &str = include_str!("../../data/examples/day-11.txt");
+
+    #[test]
+    fn relieved() {
+        let mut monkeys = io::input_as_monkeys(&Input::text(EXAMPLE)).unwrap();
+        monkeys.simulate(20, true);
+        assert_eq!(monkeys.monkey_buisness(), 10605);
+    }
+
+    #[test]
+    fn worried() {
+        let mut monkeys = io::input_as_monkeys(&Input::text(EXAMPLE)).unwrap();
+        monkeys.simulate(10000, false);
+        assert_eq!(monkeys.monkey_buisness(), 2713310158);
+    }
+
+    #[test]
+    fn missing_target() {
+        let broken = EXAMPLE.replace("throw to monkey 3", "throw to monkey 7");
+        assert!(Monkeys::from_string(&broken).is_err());
+    }
+
+    #[test]
+    fn round_trip() {
+        let monkeys = io::input_as_monkeys(&Input::text(EXAMPLE)).unwrap();
+        assert_eq!(monkeys.to_string(), EXAMPLE.trim_end());
+        assert_eq!(io::input_as_monkeys(&Input::text(&monkeys.to_string())).unwrap(), monkeys);
+    }
+
+    #[test]
+    fn bad_monkey() {
+        let broken = EXAMPLE.replacen("divisible by 19", "divisible by x", 1);
+        let err = Monkeys::from_string(&broken).unwrap_err();
+        assert!(err.to_string().starts_with("line 11 "), "{}", err);
+        let renumbered = EXAMPLE.replacen("Monkey 1:", "Monkey 2:", 1);
+        let err = Monkeys::from_string(&renumbered).unwrap_err();
+        assert!(err.to_string().starts_with("line 8 "), "{}", err);
+    }
+}
@@ -1,17 +1,27 @@
 use std::cmp::Ordering;
+use std::fmt;
+
+use itertools::Itertools;
 
 use pest::Parser;
+use pest::error::LineColLocation;
+
+use crate::error::{Error, Result};
 
 #[derive(Parser)]
 #[grammar = "packet.pest"]
 struct PacketParser;
 
 #[derive(Clone, Debug)]
+// Serialised the way it is written, as nested lists of numbers
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(untagged))]
 pub enum Packet {
     Num(usize),
     List(Vec<Packet>)
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PacketPair {
     packets : (Packet, Packet)
 }
@@ -29,9 +39,21 @@ fn parse_packet(pair: Pair<Rule>) -> Packet {
 }
 
 impl Packet {
-    pub fn from_line(line : &str) -> Option<Packet> {
-        let packet = PacketParser::parse(Rule::packet, line).ok()?.next().unwrap();
-        Some(parse_packet(packet))
+    pub fn from_line(line : &str) -> Result<Packet> {
+        let mut pairs = PacketParser::parse(Rule::packet, line).map_err(
+            |err| {
+                let column = match err.line_col {
+                    LineColLocation::Pos((_, col)) => col,
+                    LineColLocation::Span((_, col), _) => col
+                };
+                Error::at(line, &line[column-1..], "a packet")
+            }
+        )?;
+        let packet = pairs.next().unwrap();
+        if packet.as_str().len() != line.len() {
+            return Err(Error::at(line, &line[packet.as_str().len()..], "the end of the packet"));
+        }
+        Ok(parse_packet(packet))
     } 
 
     pub fn dividers() -> (Packet, Packet) {
@@ -72,7 +94,7 @@ impl Eq for Packet {
 
 impl PartialOrd for Packet {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.compare(other))
+        Some(self.cmp(other))
     }
 }
 
@@ -82,15 +104,96 @@ impl Ord for Packet {
     }
 }
 
+impl fmt::Display for Packet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Packet::Num(val) => write!(f, "{}", val),
+            Packet::List(packets) => write!(f, "[{}]", packets.iter().join(","))
+        }
+    }
+}
+
 impl PacketPair {
-    pub fn from_string(string : &str) -> Option<PacketPair> {
-        let (fst,snd) = string.split_once("\n")?;
+    pub fn from_string(string : &str) -> Result<PacketPair> {
+        let (fst,snd) = string.split_once("\n").ok_or(
+            Error::line(string, "two lines of packets")
+        )?;
+        let snd = snd.trim_end_matches('\n');
         let fst_pack = Packet::from_line(fst)?;
-        let snd_pack = Packet::from_line(snd)?;
-        Some(PacketPair { packets : (fst_pack,snd_pack) })
+        let snd_pack = Packet::from_line(snd).map_err(
+            |err| err.offset_lines(1)
+        )?;
+        Ok(PacketPair { packets : (fst_pack,snd_pack) })
     }
 
     pub fn ordered(&self) -> bool {
         self.packets.0.compare(&self.packets.1) == Ordering::Less
     }
-}
\ No newline at end of file
+}
+
+impl fmt::Display for PacketPair {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}\n{}", self.packets.0, self.packets.1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::{self, Input};
+
+    const EXAMPLE : &str = include_str!("../../data/examples/day-13.txt");
+
+    #[test]
+    fn ordered_pairs() {
+        let pairs = io::input_as_packet_pairs(&Input::text(EXAMPLE)).unwrap();
+        let ordered : Vec<usize> = pairs.iter().enumerate().filter_map(
+            |(i, pair)| if pair.ordered() { Some(i+1) } else { None }
+        ).collect();
+        assert_eq!(ordered, vec!(1, 2, 4, 6));
+    }
+
+    #[test]
+    fn divider_positions() {
+        let mut packets = io::input_as_packets(&Input::text(EXAMPLE)).unwrap();
+        let (div1, div2) = Packet::dividers();
+        packets.push(div1.clone());
+        packets.push(div2.clone());
+        packets.sort();
+        let positions : Vec<usize> = packets.iter().enumerate().filter_map(
+            |(i, p)| if *p == div1 || *p == div2 { Some(i+1) } else { None }
+        ).collect();
+        assert_eq!(positions, vec!(10, 14));
+    }
+
+    #[test]
+    fn unbalanced() {
+        assert!(Packet::from_line("[1,[2,3]").is_err());
+    }
+
+    // Equal packets can be written differently so the text is compared
+    #[test]
+    fn round_trip() {
+        for line in EXAMPLE.lines().filter(|line| !line.is_empty()) {
+            let packet = Packet::from_line(line).unwrap();
+            assert_eq!(packet.to_string(), line);
+            assert_eq!(Packet::from_line(&packet.to_string()).unwrap().to_string(), line);
+        }
+        let pairs = io::input_as_packet_pairs(&Input::text(EXAMPLE)).unwrap();
+        let text = pairs.iter().join("\n\n");
+        assert_eq!(text, EXAMPLE.trim_end());
+        let again = io::input_as_packet_pairs(&Input::text(&text)).unwrap();
+        assert_eq!(again.iter().join("\n\n"), text);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn json() {
+        for line in EXAMPLE.lines().filter(|line| !line.is_empty()) {
+            let packet = Packet::from_line(line).unwrap();
+            let json = serde_json::to_string(&packet).unwrap();
+            assert_eq!(json, line);
+            assert_eq!(serde_json::from_str::<Packet>(&json).unwrap(), packet);
+        }
+    }
+}
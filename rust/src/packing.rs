@@ -1,22 +1,39 @@
 use std::collections::HashSet;
+use std::fmt;
 
-#[derive(PartialEq,Eq,Hash,Debug,Copy,Clone)]
+use itertools::Itertools;
+
+use crate::error::{Error, Result};
+
+#[derive(PartialEq,Eq,Hash,Debug,Copy,Clone,PartialOrd,Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 struct Item {
     id : char
 }
 
-
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Rucksack {
     comp1 : HashSet<Item>,
     comp2 : HashSet<Item>
 }
 
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Group {
     packs : Vec<Rucksack>
 }
 
 impl Rucksack {
-    pub fn from_line(line : &String) -> Rucksack {
+    pub fn from_line(line : &str) -> Result<Rucksack> {
+        if let Some((pos, _)) = line.char_indices().find(
+            |(_, ch)| !ch.is_ascii_alphabetic()
+        ) {
+            return Err(Error::at(line, &line[pos..], "an item letter"));
+        }
+        if !line.len().is_multiple_of(2) {
+            return Err(Error::line(line, "an even number of items"));
+        }
         let half = line.len() / 2;
         let comp1 = line.chars().take(half).map(
             |ch| Item{id : ch}
@@ -24,7 +41,7 @@ impl Rucksack {
         let comp2 = line.chars().skip(half).map(
             |ch| Item{id : ch}
         ).collect();
-        Rucksack { comp1: comp1, comp2: comp2 }
+        Ok(Rucksack { comp1: comp1, comp2: comp2 })
     }
 
     fn union(&self) -> HashSet<Item> {
@@ -39,8 +56,8 @@ impl Rucksack {
 impl Item {
     fn priority(&self) -> usize {
         let res = match self.id {
-            'a'..='z' => (self.id as u8) - ('a' as u8) + 1,
-            'A'..='Z' => (self.id as u8) - ('A' as u8) + 27,
+            'a'..='z' => (self.id as u8) - b'a' + 1,
+            'A'..='Z' => (self.id as u8) - b'A' + 27,
             _ => 100
         };
         res as usize
@@ -48,11 +65,15 @@ impl Item {
 }
 
 impl Group {
-    pub fn from_lines(lines : [&String; 3]) -> Group {
-        let packs = lines.iter().map(
-            |line| Rucksack::from_line(*line)
-        ).collect();
-        Group { packs: packs }
+    pub fn from_lines(lines : [&String; 3]) -> Result<Group> {
+        let mut packs = Vec::new();
+        for (i, line) in lines.iter().enumerate() {
+            let pack = Rucksack::from_line(line).map_err(
+                |err| err.offset_lines(i)
+            )?;
+            packs.push(pack);
+        }
+        Ok(Group { packs: packs })
     }
 
     pub fn score(&self) -> usize {
@@ -61,4 +82,72 @@ impl Group {
         ).cloned().collect();
         partial.intersection(&self.packs[2].union()).next().unwrap().priority()
     }
-}
\ No newline at end of file
+}
+
+// Each compartment in sorted order, the smaller one padded with copies of
+// its first item so the two halves are the same length
+impl fmt::Display for Rucksack {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let len = self.comp1.len().max(self.comp2.len());
+        for comp in [&self.comp1, &self.comp2] {
+            let items : Vec<char> = comp.iter().sorted().map(|item| item.id).collect();
+            if let Some(first) = items.first() {
+                write!(f, "{}", first.to_string().repeat(len - items.len()))?;
+            }
+            write!(f, "{}", items.iter().collect::<String>())?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Group {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.packs.iter().join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::{self, Input};
+
+    const EXAMPLE : &str = include_str!("../../data/examples/day-3.txt");
+
+    #[test]
+    fn rucksack_scores() {
+        let scores : Vec<usize> = io::input_as_rucksack(&Input::text(EXAMPLE)).unwrap().iter().map(
+            |rucksack| rucksack.score()
+        ).collect();
+        assert_eq!(scores, vec!(16, 38, 42, 22, 20, 19));
+    }
+
+    #[test]
+    fn group_scores() {
+        let scores : Vec<usize> = io::input_as_groups(&Input::text(EXAMPLE)).unwrap().iter().map(
+            |group| group.score()
+        ).collect();
+        assert_eq!(scores, vec!(18, 52));
+    }
+
+    #[test]
+    fn round_trip() {
+        let rucksacks = io::input_as_rucksack(&Input::text(EXAMPLE)).unwrap();
+        let text = rucksacks.iter().join("\n");
+        assert_eq!(io::input_as_rucksack(&Input::text(&text)).unwrap(), rucksacks);
+        let groups = io::input_as_groups(&Input::text(EXAMPLE)).unwrap();
+        let text = groups.iter().join("\n");
+        assert_eq!(io::input_as_groups(&Input::text(&text)).unwrap(), groups);
+    }
+
+    #[test]
+    fn odd_rucksack() {
+        assert!(Rucksack::from_line("abc").is_err());
+    }
+
+    #[test]
+    fn short_group() {
+        let text = EXAMPLE.lines().take(5).join("\n");
+        let err = io::input_as_groups(&Input::text(&text)).unwrap_err();
+        assert!(err.to_string().starts_with("line 4 "), "{}", err);
+    }
+}
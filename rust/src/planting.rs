@@ -1,60 +1,51 @@
 use std::collections::HashMap;
-use std::collections::HashSet;
-
-#[derive(Clone, Copy, Debug)]
-enum Dir {
-    N,
-    E,
-    S,
-    W
-}
+use std::fmt;
+
+use crate::error::{Error, Result};
+use crate::geometry::{Direction, Point2};
+use crate::grid::SparseGrid;
+use crate::render::{Animate, Frame};
 
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Field {
-    elves : HashSet::<(i64,i64)>
+    elves : SparseGrid<()>
+}
+
+// A round of the elves moving a step, until none of them move
+pub struct Spreading {
+    field : Field,
+    round : usize
 }
 
 impl Field {
-    pub fn from_lines(lines : &Vec<String>) -> Field {
-        let mut elfs = HashSet::new();
-        for (j, line) in lines.iter().enumerate() {
-            for (i, ch) in line.chars().enumerate() {
-                if ch == '#' {
-                    elfs.insert((i as i64,j as i64));
-                }
-            }
+    pub fn from_lines(lines : &Vec<String>) -> Result<Field> {
+        let elfs = SparseGrid::parse(
+            lines,
+            ".",
+            |ch| if ch == '#' { Some(()) } else { None },
+            "an elf # or ground ."
+        )?;
+        if elfs.is_empty() {
+            return Err(Error::invalid("no elves in the field"));
         }
-        Field { elves: elfs }
+        Ok(Field { elves: elfs })
     }
 
     pub fn empty_ground(&self) -> usize {
-        let max_x = self.elves.iter().map(
-            |(x,_)| x
-        ).max().unwrap() + 1;
-        let min_x = self.elves.iter().map(
-            |(x,_)| x
-        ).min().unwrap();
-        let max_y = self.elves.iter().map(
-            |(_,y)| y
-        ).max().unwrap() + 1;
-        let min_y = self.elves.iter().map(
-            |(_,y)| y
-        ).min().unwrap();
-        let diff_x = (max_x - min_x) as usize;
-        let diff_y = (max_y - min_y) as usize;
-        let area = diff_x * diff_y;
-        area - self.elves.len()
+        self.elves.bounds().unwrap().area() - self.elves.len()
     }
 
     pub fn simulate(&mut self, rounds : usize) {
-        let order = Dir::N.order();
-        for dir in order.iter().cycle().take(rounds) {
+        let directions = order(Direction::North);
+        for dir in directions.iter().cycle().take(rounds) {
             self.simulate_one(dir);
         }
     }
 
     pub fn simulate_till_still(&mut self) -> usize {
-        let order = Dir::N.order();
-        for (i,dir) in order.iter().cycle().enumerate() {
+        let directions = order(Direction::North);
+        for (i,dir) in directions.iter().cycle().enumerate() {
             let any_move = self.simulate_one(dir);
             if !any_move {
                 return i+1;
@@ -63,42 +54,42 @@ impl Field {
         0
     }
 
-    fn simulate_one(&mut self, base_dir : &Dir) -> bool {
-        let mut proposed_directions : HashMap<(i64, i64), Dir> = HashMap::new();
-        let mut proposed_pos_count : HashMap<(i64, i64), usize> = HashMap::new();
+    fn simulate_one(&mut self, base_dir : &Direction) -> bool {
+        let mut proposed_directions : HashMap<Point2, Direction> = HashMap::new();
+        let mut proposed_pos_count : HashMap<Point2, usize> = HashMap::new();
         let mut any_move = false;
-        for pos in self.elves.iter() {
-            if let Some(new_dir) = self.propose(pos, base_dir) {
-                proposed_directions.insert(*pos, new_dir);
-                let new_pos = new_dir.step(pos);
+        for pos in self.elves.positions() {
+            if let Some(new_dir) = self.propose(&pos, base_dir) {
+                proposed_directions.insert(pos, new_dir);
+                let new_pos = pos.step(new_dir);
                 *proposed_pos_count.entry(new_pos).or_insert(0) += 1;
             }
         }
         // Now have all of them can move elfs
-        let mut new_elfs = HashSet::new();
-        for pos in self.elves.iter() {
-            if let Some(new_dir) = proposed_directions.get(pos) {
-                let new_pos = new_dir.step(pos);
+        let mut new_elfs = SparseGrid::new();
+        for pos in self.elves.positions() {
+            if let Some(new_dir) = proposed_directions.get(&pos) {
+                let new_pos = pos.step(*new_dir);
                 if *proposed_pos_count.get(&new_pos).unwrap() == 1 {
-                    new_elfs.insert(new_pos);
+                    new_elfs.insert(new_pos, ());
                     any_move = true;
                 } else {
-                    new_elfs.insert(*pos);
+                    new_elfs.insert(pos, ());
                 }
             } else {
-                new_elfs.insert(*pos);
+                new_elfs.insert(pos, ());
             }
         }
         self.elves = new_elfs;
         any_move
     }
 
-    fn propose(&self, pos : &(i64,i64), last : &Dir) -> Option<Dir> {
+    fn propose(&self, pos : &Point2, last : &Direction) -> Option<Direction> {
         if self.all_clear(pos) {
             return None;
         }
-        for dir in last.order() {
-            let clear = dir.check(pos).iter().all(
+        for dir in order(*last) {
+            let clear = check(pos, dir).iter().all(
                 |p| self.clear(p)
             );
             if clear {
@@ -108,47 +99,97 @@ impl Field {
         None
     }
 
-    fn all_clear(&self, (x,y) : &(i64,i64)) -> bool {
-        self.clear(&(x+1, y-1)) &&
-        self.clear(&(x+1, *y)) &&
-        self.clear(&(x+1, y+1)) &&
-        self.clear(&(*x, y-1)) &&
-        self.clear(&(*x, y+1)) &&
-        self.clear(&(x-1, y-1)) &&
-        self.clear(&(x-1, *y)) &&
-        self.clear(&(x-1, y+1))
+    fn all_clear(&self, pos : &Point2) -> bool {
+        self.elves.neighbours_8(*pos).next().is_none()
     }
 
-    fn clear(&self, pos : &(i64,i64)) -> bool {
-        !self.elves.contains(pos)
+    fn clear(&self, pos : &Point2) -> bool {
+        !self.elves.contains(*pos)
     }
 }
 
-impl Dir {
-    fn step(&self, (x,y) : &(i64,i64)) -> (i64, i64) {
-        match self {
-            Dir::N => (*x, y-1),
-            Dir::E => (x+1, *y),
-            Dir::S => (*x, y+1),
-            Dir::W => (x-1, *y)
-        }
+impl Spreading {
+    pub fn new(field : Field) -> Spreading {
+        Spreading { field : field, round : 0 }
     }
+}
 
-    fn order(&self) -> [Dir; 4] {
-        match self {
-            Dir::N => [Dir::N, Dir::S, Dir::W, Dir::E],
-            Dir::S => [Dir::S, Dir::W, Dir::E, Dir::N],
-            Dir::W => [Dir::W, Dir::E, Dir::N, Dir::S],
-            Dir::E => [Dir::E, Dir::N, Dir::S, Dir::W]
-        }
+impl Animate for Spreading {
+    fn step(&mut self) -> bool {
+        let dir = order(Direction::North)[self.round % 4];
+        self.round += 1;
+        self.field.simulate_one(&dir)
     }
 
-    fn check(&self, (x,y) : &(i64, i64)) -> [(i64, i64); 3] {
-        match self {
-            Dir::N => [(*x, y-1), (x-1, y-1), (x+1, y-1)],
-            Dir::E => [(x+1, *y), (x+1, y-1), (x+1, y+1)],
-            Dir::S => [(*x, y+1), (x-1, y+1), (x+1, y+1)],
-            Dir::W => [(x-1, *y), (x-1, y-1), (x-1, y+1)]
-        }
+    fn frame(&self) -> Frame {
+        let elves = &self.field.elves;
+        Frame::draw(elves.bounds().unwrap(), |pos| if elves.contains(pos) { '#' } else { '.' })
+    }
+}
+
+// The order the directions are tried in starting from dir
+fn order(dir : Direction) -> [Direction; 4] {
+    match dir {
+        Direction::North => [Direction::North, Direction::South, Direction::West, Direction::East],
+        Direction::South => [Direction::South, Direction::West, Direction::East, Direction::North],
+        Direction::West => [Direction::West, Direction::East, Direction::North, Direction::South],
+        Direction::East => [Direction::East, Direction::North, Direction::South, Direction::West]
+    }
+}
+
+// The three positions that need to be clear to move in dir
+fn check(pos : &Point2, dir : Direction) -> [Point2; 3] {
+    let ahead = pos.step(dir);
+    [ahead, ahead.step(dir.left()), ahead.step(dir.right())]
+}
+
+// Written from the origin so a field that has not moved reads back the same,
+// elves that have spread past the top or left edge move it
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let bounds = self.elves.bounds().unwrap();
+        let rows : Vec<String> = (bounds.min.y.min(0)..=bounds.max.y).map(
+            |y| (bounds.min.x.min(0)..=bounds.max.x).map(
+                |x| if self.elves.contains(Point2::new(x, y)) { '#' } else { '.' }
+            ).collect()
+        ).collect();
+        write!(f, "{}", rows.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::{self, Input};
+    use crate::render;
+
+    const EXAMPLE : &str = include_str!("../../data/examples/day-23.txt");
+
+    #[test]
+    fn ten_rounds() {
+        let mut field = io::input_as_field(&Input::text(EXAMPLE)).unwrap();
+        field.simulate(10);
+        assert_eq!(field.empty_ground(), 110);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn till_still() {
+        let mut field = io::input_as_field(&Input::text(EXAMPLE)).unwrap();
+        assert_eq!(field.simulate_till_still(), 20);
+    }
+
+    #[test]
+    fn frames() {
+        let field = io::input_as_field(&Input::text(EXAMPLE)).unwrap();
+        let frames = render::frames(&mut Spreading::new(field), 1, 100);
+        assert_eq!(frames.len(), 20);
+        assert_eq!(frames[10].to_string().matches('.').count(), 110);
+    }
+
+    #[test]
+    fn round_trip() {
+        let field = io::input_as_field(&Input::text(EXAMPLE)).unwrap();
+        assert_eq!(field.to_string(), EXAMPLE.trim_end());
+        assert_eq!(io::input_as_field(&Input::text(&field.to_string())).unwrap(), field);
+    }
+}
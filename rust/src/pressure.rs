@@ -1,174 +1,226 @@
-use std::{collections::{HashMap, BTreeSet}, cmp::max};
+use std::collections::HashMap;
+use std::fmt;
+
+use itertools::Itertools;
 use petgraph::graph::{NodeIndex, UnGraph};
 use petgraph::algo::dijkstra;
 
+use crate::error::{Error, Result, parse_lines, parse_num};
+use crate::metrics;
+use crate::search::{self, Memoized, Stats};
+
+// A valve as written, with the names of the valves its tunnels lead to
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Valve {
+    name : String,
+    flow : usize,
+    tunnels : Vec<String>
+}
 
+// Every valve as written, and for the search only the valves worth
+// opening and the start, with the minutes it takes to walk between each pair
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Network {
-    names : HashMap<String, NodeIndex<u32>>,
-    graph : UnGraph::<u32,()>,
-    min_distance : HashMap<(NodeIndex, NodeIndex), usize>
+    valves : Vec<Valve>,
+    flows : Vec<usize>,
+    distance : Vec<Vec<usize>>,
+    start : usize
+}
+
+// Every call of the search either finds its state in the cache or works it out
+fn record(stats : &Stats) {
+    metrics::add("pressure.calls", (stats.expanded + stats.cache_hits) as u64);
+    metrics::add("pressure.cache_hits", stats.cache_hits as u64);
+    metrics::add("pressure.cache_misses", stats.expanded as u64);
+}
+
+// The search for the most pressure released, with the time the elephant
+// gets once you are done if it is helping
+struct Pressure<'a> {
+    network : &'a Network,
+    elephant : Option<usize>
+}
+
+// Where you are, the time left and the valves opened as bits
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+struct State {
+    valve : usize,
+    time : usize,
+    open : u64,
+    elephant : bool
 }
 
-fn parse_line(line : &String) -> Option<(String, u32, Vec<String>)> {
-    let (valve_str, tunnel_str) = line.split_once("; ")?;
-    let (name_s, valve_s) = valve_str.split_once(" has flow rate=")?;
-    let name = name_s.strip_prefix("Valve ")?;
-    let flow = valve_s.parse::<u32>().ok()?;
+fn parse_line(line : &str) -> Result<Valve> {
+    let (valve_str, tunnel_str) = line.split_once("; ").ok_or(
+        Error::line(line, "a valve and its tunnels separated by ;")
+    )?;
+    let (name_s, valve_s) = valve_str.split_once(" has flow rate=").ok_or(
+        Error::at(line, valve_str, "Valve NAME has flow rate=N")
+    )?;
+    let name = name_s.strip_prefix("Valve ").ok_or(
+        Error::at(line, name_s, "Valve")
+    )?;
+    let flow = parse_num(line, valve_s)?;
     let tunnel_s = tunnel_str.strip_prefix("tunnels lead to valves ").or(
         tunnel_str.strip_prefix("tunnel leads to valve ")
+    ).ok_or(
+        Error::at(line, tunnel_str, "tunnels lead to valves")
     )?;
     let tunnels = tunnel_s.split(", ").map(|s| s.to_string()).collect();
-    Some((name.to_string(), flow, tunnels))
+    Ok(Valve { name : name.to_string(), flow : flow, tunnels : tunnels })
 }
 
 impl Network {
-    pub fn from_lines(lines : &Vec<String>) -> Network {
-        let mut network = Network { 
-            names : HashMap::new(),
-            graph : UnGraph::new_undirected(),
-            min_distance : HashMap::new()
-        };
-        let mut tunnels : Vec<(String, String)> = Vec::new();
-        for line in lines.iter() {
-            if let Some((name, flow, next)) = parse_line(line) {
-                let id = network.graph.add_node(flow);
-                network.names.insert(name.clone(), id);
-                for other in next {
-                    tunnels.push((name.clone(),other));
-                }
+    pub fn from_lines(lines : &Vec<String>) -> Result<Network> {
+        let mut names = HashMap::new();
+        let mut graph = UnGraph::<usize, ()>::new_undirected();
+        let valves : Vec<Valve> = parse_lines(
+            lines,
+            parse_line
+        )?;
+        for valve in valves.iter() {
+            let id = graph.add_node(valve.flow);
+            names.insert(valve.name.as_str(), id);
+        }
+        let start = *names.get("AA").ok_or(
+            Error::invalid("no starting valve AA")
+        )?;
+        for valve in valves.iter() {
+            for other in valve.tunnels.iter() {
+                let id = names.get(other.as_str()).ok_or(
+                    Error::invalid(&format!("tunnel from {} to unknown valve {}", valve.name, other))
+                )?;
+                graph.update_edge(names[valve.name.as_str()], *id, ());
             }
         }
-        for (from, to) in tunnels {
-            let id1 = network.names.get(&from).unwrap();
-            let id2 = network.names.get(&to).unwrap();
-            network.graph.update_edge(*id1, *id2, ());
+
+        let mut kept : Vec<NodeIndex> = graph.node_indices().filter(
+            |node| graph[*node] > 0
+        ).collect();
+        if kept.len() > 63 {
+            return Err(Error::invalid("more than 63 valves with a flow"));
         }
-        for node in network.graph.node_indices() {
-            let distances = dijkstra(
-                &network.graph, 
-                node, 
-                None, 
-                |_| 1
-            );
-            for (other, dist) in distances.iter() {
-                network.min_distance.insert((node, *other), *dist as usize);
-            }
+        if !kept.contains(&start) {
+            kept.push(start);
         }
-        network
+        let distance = kept.iter().map(|node| {
+            let distances = dijkstra(&graph, *node, None, |_| 1);
+            kept.iter().map(
+                |other| distances.get(other).copied().unwrap_or(usize::MAX)
+            ).collect()
+        }).collect();
+        Ok(Network {
+            flows : kept.iter().map(|node| graph[*node]).collect(),
+            distance : distance,
+            start : kept.iter().position(|node| *node == start).unwrap(),
+            valves : valves
+        })
     }
 
-    fn start_node(&self) -> NodeIndex<u32> {
-        *self.names.get("AA").unwrap()
+    fn start_state(&self, time : usize, elephant : bool) -> State {
+        State { valve : self.start, time : time, open : 0, elephant : elephant }
     }
 
-    fn max_pressure(
-        &self,
-        node : NodeIndex, 
-        left : usize, 
-        remaining : &BTreeSet<NodeIndex>,
-        cache : &mut HashMap<(NodeIndex, usize, BTreeSet<NodeIndex>), usize>
-    ) -> usize {
-        if let Some(res) = cache.get(&(node, left, remaining.clone())) {
-            return *res;
-        }
-        if left == 0 {
-            return 0;
-        }
-        let mut max_left = self.graph.neighbors(node).map(
-            |next| self.max_pressure(next, left-1, remaining, cache)
-        ).max().unwrap();
-        let flow : usize = *self.graph.node_weight(node).unwrap() as usize;
-        if !remaining.contains(&node) && flow > 0 {
-            let mut switch_on = remaining.clone();
-            switch_on.insert(node);
-            let max_sw = self.max_pressure(node, left-1, &switch_on, cache)
-             + (left-1) * flow;
-            if max_sw > max_left {
-                max_left = max_sw;
-            }
-        }
-        cache.insert((node, left, remaining.clone()), max_left);
-        max_left
+    pub fn maximum_pressure(&self) -> usize {
+        let search = Pressure { network : self, elephant : None };
+        let (best, stats) = search::memoized(&search, self.start_state(30, false));
+        log::debug!("Pressure search: {:?}", stats);
+        record(&stats);
+        best
     }
 
-    fn max_pressure_dual(
-        &self,
-        nodes : (NodeIndex,NodeIndex), 
-        left : usize, 
-        remaining : &BTreeSet<NodeIndex>,
-        cache : &mut HashMap<((NodeIndex,NodeIndex), usize, BTreeSet<NodeIndex>), usize>
-    ) -> usize {
-        if let Some(res) = cache.get(&(nodes, left, remaining.clone())) {
-            return *res;
-        }
-        if left == 0 {
-            return 0;
-        }
-        let flow0 = *self.graph.node_weight(nodes.0).unwrap() as usize;
-        let flow1 = *self.graph.node_weight(nodes.1).unwrap() as usize;
-        let can_open0 = !remaining.contains(&nodes.0) && flow0 > 0;
-        let can_open1 = !remaining.contains(&nodes.1) && flow1 > 0;
-        let mut best_pressure : usize = 0;
-        // Both move
-        for next0 in self.graph.node_indices() {
-            for next1 in self.graph.node_indices() {
-                let dist0 = *self.min_distance.get(&(nodes.0, next0)).unwrap();
-                let dist1 = *self.min_distance.get(&(nodes.1, next1)).unwrap();
-                if dist0 == dist1 && dist0 > 0 && left >= dist0 {
-                    let pos_flow0 = *self.graph.node_weight(next0).unwrap() as usize;
-                    let pos_flow1 = *self.graph.node_weight(next1).unwrap() as usize;
-                    // At leas one is an unseen flow node
-                    let possible0 = !remaining.contains(&next0) && pos_flow0 > 0;
-                    let possible1 = !remaining.contains(&next1) && pos_flow1 > 0;
-                    if possible0 || possible1 {
-                        let move_pressure = self.max_pressure_dual((next0, next1), left-dist0, remaining, cache);
-                        best_pressure = max(move_pressure, best_pressure);
-                    }
-                }
-            }
-        }
-        // 0 turn 1 move
-        if can_open0 {
-            let mut switch_on = remaining.clone();
-            switch_on.insert(nodes.0);
-            for next1 in self.graph.neighbors(nodes.1) {
-                let move_pressure = self.max_pressure_dual((nodes.0, next1), left-1, &switch_on, cache);
-                let new_pressure = move_pressure + flow0 * (left-1);
-                best_pressure = max(new_pressure, best_pressure);
-            }
-        }
+    // You open valves for 26 minutes then the elephant does the same with
+    // the valves you left
+    pub fn maximum_pressure_dual(&self) -> usize {
+        let search = Pressure { network : self, elephant : Some(26) };
+        let (best, stats) = search::memoized(&search, self.start_state(26, true));
+        log::debug!("Pressure search with the elephant: {:?}", stats);
+        record(&stats);
+        best
+    }
+}
 
-        // 1 turn 0 move
-        if can_open1 {
-            let mut switch_on = remaining.clone();
-            switch_on.insert(nodes.1);
-            for next0 in self.graph.neighbors(nodes.0) {
-                let move_pressure = self.max_pressure_dual((next0, nodes.1), left-1, &switch_on, cache);
-                let new_pressure = move_pressure + flow1 * (left-1);
-                best_pressure = max(new_pressure, best_pressure);
-            }
+// Each move walks to a closed valve and opens it, the gain is everything
+// it releases in the time left
+impl<'a> Memoized for Pressure<'a> {
+    type State = State;
+    type Key = State;
+
+    fn key(&self, state : &State) -> State {
+        *state
+    }
+
+    fn moves(&self, state : &State) -> Vec<(usize, State)> {
+        let network = self.network;
+        let mut moves : Vec<(usize, State)> = network.flows.iter().enumerate().filter(
+            |(valve, flow)| **flow > 0 && state.open & (1 << valve) == 0
+        ).filter_map(|(valve, flow)| {
+            let walk = network.distance[state.valve][valve].saturating_add(1);
+            (walk < state.time).then(|| {
+                let time = state.time - walk;
+                (flow * time, State { valve : valve, time : time, open : state.open | (1 << valve), ..*state })
+            })
+        }).collect();
+        if let (true, Some(time)) = (state.elephant, self.elephant) {
+            moves.push((0, State { valve : network.start, time : time, open : state.open, elephant : false }));
         }
+        moves
+    }
+}
 
-        // Both turn
-        if can_open0 && can_open1 && nodes.0 != nodes.1{
-            let mut switch_on = remaining.clone();
-            switch_on.insert(nodes.0);
-            switch_on.insert(nodes.1);
-            let move_pressure = self.max_pressure_dual(nodes, left-1,& switch_on, cache);
-            let new_pressure = move_pressure + (flow0 + flow1) * (left-1);
-            best_pressure = max(new_pressure, best_pressure);
+impl fmt::Display for Valve {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Valve {} has flow rate={}; ", self.name, self.flow)?;
+        match self.tunnels.as_slice() {
+            [other] => write!(f, "tunnel leads to valve {}", other),
+            tunnels => write!(f, "tunnels lead to valves {}", tunnels.join(", "))
         }
-        
-        cache.insert((nodes, left, remaining.clone()), best_pressure);
-        best_pressure
     }
+}
+
+impl fmt::Display for Network {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.valves.iter().join("\n"))
+    }
+}
 
-    pub fn maximum_pressure(&self) -> usize {
-        self.max_pressure(self.start_node(), 30, &BTreeSet::new(), &mut HashMap::new())
+#[cfg(test)]
+mod tests {
+    use crate::days;
+    use crate::error;
+    use crate::io::{self, Input};
+    use crate::solution::Part;
+
+    const EXAMPLE : &str = include_str!("../../data/examples/day-16.txt");
+
+    #[test]
+    fn alone() {
+        let network = io::input_as_network(&Input::text(EXAMPLE)).unwrap();
+        assert_eq!(network.maximum_pressure(), 1651);
     }
 
-    pub fn maximum_pressure_dual(&self) -> usize {
-        let mut cache = HashMap::new();
-        self.max_pressure_dual((self.start_node(),self.start_node()), 26, &BTreeSet::new(), &mut cache)
+    #[test]
+    fn with_elephant() {
+        let network = io::input_as_network(&Input::text(EXAMPLE)).unwrap();
+        assert_eq!(network.maximum_pressure_dual(), 1707);
+    }
+
+    #[test]
+    fn round_trip() {
+        let network = io::input_as_network(&Input::text(EXAMPLE)).unwrap();
+        assert_eq!(network.to_string(), EXAMPLE.trim_end());
+        assert_eq!(io::input_as_network(&Input::text(&network.to_string())).unwrap(), network);
     }
-}
\ No newline at end of file
+
+    // The line skipped before the parse failed is still reported
+    #[test]
+    fn skipped_then_failed() {
+        let input = Input::text("Valve AA has flow rate=x; tunnel leads to valve BB\nValve BB has flow rate=0; tunnel leads to valve AA");
+        error::take_skipped();
+        assert!(days::registry().run(16, Part::One, &input).is_err());
+        assert!(error::take_skipped().is_empty());
+        assert!(!input.first_report());
+    }
+}
@@ -0,0 +1,352 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use crate::error::{Error, Result};
+use crate::geometry::Point2;
+use crate::grid::{Bounds, Grid};
+
+// A picture of a simulation at one moment. There is one character a cell
+// so it can be printed, the origin places it so frames of different sizes
+// line up when made into images
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Frame {
+    origin : Point2,
+    cells : Grid<char>
+}
+
+// A simulation that can be watched a step at a time
+pub trait Animate {
+    // Move on one step, false once there is nothing left to do
+    fn step(&mut self) -> bool;
+
+    fn frame(&self) -> Frame;
+}
+
+impl Frame {
+    // A frame covering bounds with each cell drawn by f
+    pub fn draw<F>(bounds : Bounds, f : F) -> Frame
+      where F : Fn(Point2) -> char
+    {
+        let rows : Vec<Vec<char>> = (bounds.min.y..=bounds.max.y).map(
+            |y| bounds.row(y).map(&f).collect()
+        ).collect();
+        Frame { origin : bounds.min, cells : Grid::from_rows(rows).unwrap() }
+    }
+
+    pub fn bounds(&self) -> Bounds {
+        let size = Point2::new(self.cells.width() as i64 - 1, self.cells.height() as i64 - 1);
+        Bounds::new(self.origin, self.origin + size)
+    }
+
+    // The character at a position, blank outside the frame
+    pub fn get(&self, pos : Point2) -> char {
+        self.cells.get(pos - self.origin).copied().unwrap_or(' ')
+    }
+}
+
+impl fmt::Display for Frame {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.cells.render(|ch| *ch))
+    }
+}
+
+// Frames of a simulation from its start, one every so many steps and no
+// more than limit. The last step is always shown
+pub fn frames(animation : &mut dyn Animate, every : usize, limit : usize) -> Vec<Frame> {
+    let mut frames = vec!(animation.frame());
+    let mut steps = 0;
+    while frames.len() < limit {
+        if !animation.step() {
+            if steps % every.max(1) != 0 {
+                frames.push(animation.frame());
+            }
+            break;
+        }
+        steps += 1;
+        if steps % every.max(1) == 0 {
+            frames.push(animation.frame());
+        }
+    }
+    frames
+}
+
+// The colour a character is drawn in, the same character is always the
+// same colour whatever the simulation
+pub fn colour(ch : char) -> [u8; 3] {
+    match ch {
+        ' ' => [0, 0, 0],
+        '.' => [24, 24, 40],
+        '#' => [140, 140, 150],
+        'o' => [230, 190, 90],
+        '@' => [220, 60, 60],
+        'H' => [220, 60, 60],
+        '+' => [255, 255, 255],
+        '|' | '-' => [90, 90, 100],
+        '>' | '<' | '^' | 'v' => [90, 210, 120],
+        '1'..='9' => {
+            let shade = ch as u8 - b'0';
+            [60, 80 + 15 * shade, 240]
+        },
+        _ => {
+            // Anything else gets a bright colour picked from its code
+            let code = ch as u32;
+            [(code * 73 % 200 + 55) as u8, (code * 151 % 200 + 55) as u8, (code * 37 % 200 + 55) as u8]
+        }
+    }
+}
+
+// The area covered by any of the frames
+fn extent(frames : &[Frame]) -> Result<Bounds> {
+    let mut all = frames.iter().map(|frame| frame.bounds());
+    let first = all.next().ok_or(Error::invalid("no frames to draw"))?;
+    Ok(all.fold(first, |bounds, other| bounds.include(other.min).include(other.max)))
+}
+
+// The pixels of a frame scaled up, row by row
+fn pixels(frame : &Frame, bounds : Bounds, scale : usize) -> impl Iterator<Item = char> + '_ {
+    (bounds.min.y..=bounds.max.y).flat_map(
+        move |y| std::iter::repeat_n(y, scale)
+    ).flat_map(
+        move |y| bounds.row(y).flat_map(move |pos| std::iter::repeat_n(frame.get(pos), scale))
+    )
+}
+
+fn write(path : &Path, bytes : &[u8]) -> Result<()> {
+    fs::write(path, bytes).map_err(|err| Error::file(path, err))
+}
+
+// Write each frame to dir as frame-0000.ppm and on, all the same size
+pub fn write_ppm(frames : &[Frame], dir : &Path, scale : usize) -> Result<()> {
+    let bounds = extent(frames)?;
+    fs::create_dir_all(dir).map_err(|err| Error::file(dir, err))?;
+    let (width, height) = (bounds.width() * scale, bounds.height() * scale);
+    for (i, frame) in frames.iter().enumerate() {
+        let mut bytes = format!("P6\n{} {}\n255\n", width, height).into_bytes();
+        bytes.extend(pixels(frame, bounds, scale).flat_map(colour));
+        write(&dir.join(format!("frame-{:04}.ppm", i)), &bytes)?;
+    }
+    Ok(())
+}
+
+// Packs codes of varying width into bytes low bits first
+struct BitWriter {
+    bytes : Vec<u8>,
+    bits : u32,
+    count : u8
+}
+
+impl BitWriter {
+    fn write(&mut self, code : u16, width : u8) {
+        self.bits |= (code as u32) << self.count;
+        self.count += width;
+        while self.count >= 8 {
+            self.bytes.push(self.bits as u8);
+            self.bits >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.bytes.push(self.bits as u8);
+        }
+        self.bytes
+    }
+}
+
+// Variable width LZW as used by gif, the table is cleared once it holds
+// the most codes twelve bits allow
+fn lzw(indices : &[u8], min_width : u8) -> Vec<u8> {
+    let clear : u16 = 1 << min_width;
+    let end = clear + 1;
+    let mut writer = BitWriter { bytes : Vec::new(), bits : 0, count : 0 };
+    let mut table : HashMap<(u16, u8), u16> = HashMap::new();
+    let mut next = end + 1;
+    let mut width = min_width + 1;
+    writer.write(clear, width);
+    let mut current : Option<u16> = None;
+    for &index in indices {
+        current = match current {
+            None => Some(index as u16),
+            Some(code) => match table.get(&(code, index)) {
+                Some(longer) => Some(*longer),
+                None => {
+                    writer.write(code, width);
+                    if next < 4096 {
+                        table.insert((code, index), next);
+                        next += 1;
+                        if next > 1 << width && width < 12 {
+                            width += 1;
+                        }
+                    } else {
+                        writer.write(clear, width);
+                        table.clear();
+                        next = end + 1;
+                        width = min_width + 1;
+                    }
+                    Some(index as u16)
+                }
+            }
+        };
+    }
+    if let Some(code) = current {
+        writer.write(code, width);
+    }
+    writer.write(end, width);
+    writer.finish()
+}
+
+// Write the frames as an animated gif that loops, delay is the time each
+// frame is shown in hundredths of a second
+pub fn write_gif(frames : &[Frame], path : &Path, scale : usize, delay : u16) -> Result<()> {
+    let bounds = extent(frames)?;
+    let (width, height) = (bounds.width() * scale, bounds.height() * scale);
+    if width > u16::MAX as usize || height > u16::MAX as usize {
+        return Err(Error::invalid(&format!("a {} by {} image is too large for a gif", width, height)));
+    }
+    // One palette for every frame, blank first so it is the background
+    let mut palette : Vec<[u8; 3]> = vec!(colour(' '));
+    let mut lookup : HashMap<char, u8> = HashMap::from([(' ', 0)]);
+    for frame in frames {
+        for (_, ch) in frame.cells.iter() {
+            if !lookup.contains_key(ch) {
+                if palette.len() == 256 {
+                    return Err(Error::invalid("more than 256 colours in the frames"));
+                }
+                lookup.insert(*ch, palette.len() as u8);
+                palette.push(colour(*ch));
+            }
+        }
+    }
+    let size_bits = (1..=8).find(|bits| palette.len() <= 1 << bits).unwrap().max(2);
+    palette.resize(1 << size_bits, [0, 0, 0]);
+
+    let mut bytes = b"GIF89a".to_vec();
+    bytes.extend((width as u16).to_le_bytes());
+    bytes.extend((height as u16).to_le_bytes());
+    bytes.extend([0xF0 | (size_bits - 1), 0, 0]);
+    bytes.extend(palette.iter().flatten());
+    // Loop forever
+    bytes.extend(b"\x21\xFF\x0BNETSCAPE2.0\x03\x01\x00\x00\x00");
+    for frame in frames {
+        bytes.extend([0x21, 0xF9, 0x04, 0x00]);
+        bytes.extend(delay.to_le_bytes());
+        bytes.extend([0x00, 0x00, 0x2C, 0, 0, 0, 0]);
+        bytes.extend((width as u16).to_le_bytes());
+        bytes.extend((height as u16).to_le_bytes());
+        bytes.push(0);
+        let indices : Vec<u8> = pixels(frame, bounds, scale).map(|ch| lookup[&ch]).collect();
+        bytes.push(size_bits);
+        for block in lzw(&indices, size_bits).chunks(255) {
+            bytes.push(block.len() as u8);
+            bytes.extend(block);
+        }
+        bytes.push(0);
+    }
+    bytes.push(0x3B);
+    write(path, &bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Reads codes back out the way a gif decoder does
+    fn unlzw(bytes : &[u8], min_width : u8) -> Vec<u8> {
+        let clear = 1usize << min_width;
+        let mut table : Vec<Vec<u8>> = Vec::new();
+        let mut width = min_width + 1;
+        let mut out = Vec::new();
+        let mut previous : Option<Vec<u8>> = None;
+        let (mut bits, mut count, mut at) = (0u32, 0u8, 0);
+        loop {
+            while count < width {
+                bits |= (bytes[at] as u32) << count;
+                at += 1;
+                count += 8;
+            }
+            let code = (bits & ((1 << width) - 1)) as usize;
+            bits >>= width;
+            count -= width;
+            if code == clear {
+                table = (0..clear).map(|i| vec!(i as u8)).collect();
+                table.extend([Vec::new(), Vec::new()]);
+                width = min_width + 1;
+                previous = None;
+                continue;
+            }
+            if code == clear + 1 {
+                return out;
+            }
+            let entry = match (table.get(code), &previous) {
+                (Some(entry), _) => entry.clone(),
+                (None, Some(previous)) => [previous.clone(), vec!(previous[0])].concat(),
+                (None, None) => panic!("unknown first code")
+            };
+            if let Some(previous) = previous {
+                if table.len() < 4096 {
+                    table.push([previous, vec!(entry[0])].concat());
+                }
+            }
+            if table.len() == 1 << width && width < 12 {
+                width += 1;
+            }
+            out.extend(&entry);
+            previous = Some(entry);
+        }
+    }
+
+    #[test]
+    fn lzw_round_trip() {
+        let runs : Vec<u8> = (0..20000).map(|i| ((i / 7) % 3) as u8).collect();
+        assert_eq!(unlzw(&lzw(&runs, 2), 2), runs);
+        // Enough variety to fill the table and clear it
+        let noise : Vec<u8> = (0..50000u32).map(|i| (i.wrapping_mul(2654435761) >> 13) as u8 % 16).collect();
+        assert_eq!(unlzw(&lzw(&noise, 4), 4), noise);
+        assert_eq!(unlzw(&lzw(&[], 2), 2), Vec::<u8>::new());
+    }
+
+    #[test]
+    fn frames_line_up() {
+        let small = Frame::draw(Bounds::new(Point2::new(1, 1), Point2::new(2, 1)), |_| '#');
+        let large = Frame::draw(Bounds::new(Point2::ORIGIN, Point2::new(2, 2)), |_| '.');
+        assert_eq!(small.to_string(), "##");
+        assert_eq!(small.get(Point2::new(2, 1)), '#');
+        assert_eq!(small.get(Point2::ORIGIN), ' ');
+        let bounds = extent(&[small.clone(), large]).unwrap();
+        assert_eq!((bounds.width(), bounds.height()), (3, 3));
+        let scaled : String = pixels(&small, bounds, 2).collect();
+        assert_eq!(&scaled[..12], "            ");
+        assert_eq!(&scaled[12..24], "  ####  ####");
+    }
+
+    struct Counter {
+        count : usize
+    }
+
+    impl Animate for Counter {
+        fn step(&mut self) -> bool {
+            if self.count == 9 {
+                return false;
+            }
+            self.count += 1;
+            true
+        }
+
+        fn frame(&self) -> Frame {
+            Frame::draw(Bounds::new(Point2::ORIGIN, Point2::ORIGIN), |_| char::from_digit(self.count as u32, 10).unwrap())
+        }
+    }
+
+    #[test]
+    fn every_and_limit() {
+        let shown = |every, limit| frames(&mut Counter { count : 0 }, every, limit).iter().map(
+            |frame| frame.to_string()
+        ).collect::<String>();
+        assert_eq!(shown(1, 100), "0123456789");
+        assert_eq!(shown(4, 100), "0489");
+        assert_eq!(shown(1, 3), "012");
+    }
+}
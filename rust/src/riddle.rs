@@ -1,12 +1,18 @@
 use std::collections::HashMap;
+use std::fmt;
+
 use itertools::Itertools;
 use num::Rational64;
 
+use crate::error::{Error, Result, parse_lines};
+
 #[derive(Clone, Debug)]
 pub struct Poly {
     coeff : Vec<Rational64>
 }
 
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 enum Op {
     Num(i64),
     Add(String,String),
@@ -15,28 +21,30 @@ enum Op {
     Div(String, String)
 }
 
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Riddle {
     ops : HashMap<String, Op>
 }
 
 impl Op {
-    fn from_string(string : &str) -> Option<Op> {
+    fn from_string(string : &str) -> Result<Op> {
         if let Ok(num) = string.parse::<i64>() {
-            Some(Op::Num(num))
+            Ok(Op::Num(num))
         } else {
             if let Some((a,b)) = string.split_once(" + ") {
-                Some(Op::Add(a.to_string(), b.to_string()))
+                Ok(Op::Add(a.to_string(), b.to_string()))
             } else {
                 if let Some((a,b)) = string.split_once(" - ") {
-                    Some(Op::Sub(a.to_string(), b.to_string()))
+                    Ok(Op::Sub(a.to_string(), b.to_string()))
                 } else {
                     if let Some((a,b)) = string.split_once(" * ") {
-                        Some(Op::Mul(a.to_string(), b.to_string()))
+                        Ok(Op::Mul(a.to_string(), b.to_string()))
                     } else {
                         if let Some((a,b)) = string.split_once(" / ") {
-                            Some(Op::Div(a.to_string(), b.to_string()))
+                            Ok(Op::Div(a.to_string(), b.to_string()))
                         } else {
-                            None
+                            Err(Error::line(string, "a number or an operation"))
                         }
                     }
                 }
@@ -45,18 +53,23 @@ impl Op {
     }
 }
 
-fn parse_line(line : &str) -> Option<(String, Op)> {
-    let (name, op_s) = line.split_once(": ")?;
-    let op = Op::from_string(op_s)?;
-    Some((name.to_string(), op))
+fn parse_line(line : &str) -> Result<(String, Op)> {
+    let (name, op_s) = line.split_once(": ").ok_or(
+        Error::line(line, "a name and a job separated by :")
+    )?;
+    let op = Op::from_string(op_s).map_err(
+        |err| err.within(line, op_s)
+    )?;
+    Ok((name.to_string(), op))
 }
 
 impl Riddle {
-    pub fn from_lines(lines : &Vec<String>) -> Riddle {
-        let ops = lines.iter().filter_map(
-            |line| parse_line(line)
-        ).collect();
-        Riddle { ops: ops }
+    pub fn from_lines(lines : &Vec<String>) -> Result<Riddle> {
+        let ops = parse_lines(
+            lines,
+            parse_line
+        )?;
+        Ok(Riddle { ops: ops.into_iter().collect() })
     }
 
     pub fn solve(&self, name : &str) -> Option<i64> {
@@ -71,23 +84,23 @@ impl Riddle {
         let res = match op {
             Op::Num(num) => Some(*num),
             Op::Add(a,b) => {
-                let a_res = self.solve_cached(&a, cache)?;
-                let b_res = self.solve_cached(&b, cache)?;
+                let a_res = self.solve_cached(a, cache)?;
+                let b_res = self.solve_cached(b, cache)?;
                 Some(a_res + b_res)
             },
             Op::Sub(a,b) => {
-                let a_res = self.solve_cached(&a, cache)?;
-                let b_res = self.solve_cached(&b, cache)?;
+                let a_res = self.solve_cached(a, cache)?;
+                let b_res = self.solve_cached(b, cache)?;
                 Some(a_res - b_res)
             },
             Op::Mul(a,b) => {
-                let a_res = self.solve_cached(&a, cache)?;
-                let b_res = self.solve_cached(&b, cache)?;
+                let a_res = self.solve_cached(a, cache)?;
+                let b_res = self.solve_cached(b, cache)?;
                 Some(a_res * b_res)
             },
             Op::Div(a,b) => {
-                let a_res = self.solve_cached(&a, cache)?;
-                let b_res = self.solve_cached(&b, cache)?;
+                let a_res = self.solve_cached(a, cache)?;
+                let b_res = self.solve_cached(b, cache)?;
                 Some(a_res / b_res)
             }
         }?;
@@ -95,68 +108,71 @@ impl Riddle {
         Some(res)
     }
 
-    pub fn root_eqn(&self, var : &str) -> Poly {
+    // None if there is no root monkey comparing two others, or a monkey
+    // waited for is missing
+    pub fn root_eqn(&self, var : &str) -> Option<Poly> {
         let mut cache = HashMap::new();
-        let op = self.ops.get("root").unwrap();
-        match op {
-            Op::Num(_) => unreachable!(),
+        let op = self.ops.get("root")?;
+        let pol = match op {
+            Op::Num(_) => return None,
             Op::Add(a,b) => {
-                let a_pol = self.solve_poly_cached(a, var, &mut cache);
-                let b_pol = self.solve_poly_cached(b, var, &mut cache);
+                let a_pol = self.solve_poly_cached(a, var, &mut cache)?;
+                let b_pol = self.solve_poly_cached(b, var, &mut cache)?;
                 a_pol.sub(&b_pol)
             },
             Op::Sub(a,b) => {
-                let a_pol = self.solve_poly_cached(a, var, &mut cache);
-                let b_pol = self.solve_poly_cached(b, var, &mut cache);
+                let a_pol = self.solve_poly_cached(a, var, &mut cache)?;
+                let b_pol = self.solve_poly_cached(b, var, &mut cache)?;
                 a_pol.sub(&b_pol)
             },
             Op::Mul(a,b) => {
-                let a_pol = self.solve_poly_cached(a, var, &mut cache);
-                let b_pol = self.solve_poly_cached(b, var, &mut cache);
+                let a_pol = self.solve_poly_cached(a, var, &mut cache)?;
+                let b_pol = self.solve_poly_cached(b, var, &mut cache)?;
                 a_pol.sub(&b_pol)
             },
             Op::Div(a,b) => {
-                let a_pol = self.solve_poly_cached(a, var, &mut cache);
-                let b_pol = self.solve_poly_cached(b, var, &mut cache);
+                let a_pol = self.solve_poly_cached(a, var, &mut cache)?;
+                let b_pol = self.solve_poly_cached(b, var, &mut cache)?;
                 a_pol.sub(&b_pol)
             }
-        }
+        };
+        Some(pol)
     }
 
-    fn solve_poly_cached(&self, name : &str, var : &str, cache : &mut HashMap<String, Poly>) -> Poly {
+    fn solve_poly_cached(&self, name : &str, var : &str, cache : &mut HashMap<String, Poly>) -> Option<Poly> {
         if let Some(p) = cache.get(name) {
-            return p.clone();
+            return Some(p.clone());
         }
         if name==var {
-            return Poly::id()
+            return Some(Poly::id())
         }
         
-        let op = self.ops.get(name).unwrap();
+        let op = self.ops.get(name)?;
         let res = match op {
             Op::Num(num) => Poly::num(*num),
             Op::Add(a, b) => {
-                let p_a = self.solve_poly_cached(&a, var, cache);
-                let p_b = self.solve_poly_cached(&b, var, cache);
+                let p_a = self.solve_poly_cached(a, var, cache)?;
+                let p_b = self.solve_poly_cached(b, var, cache)?;
                 p_a.add(&p_b)
             },
             Op::Sub(a, b) => {
-                let p_a = self.solve_poly_cached(&a, var, cache);
-                let p_b = self.solve_poly_cached(&b, var, cache);
+                let p_a = self.solve_poly_cached(a, var, cache)?;
+                let p_b = self.solve_poly_cached(b, var, cache)?;
                 p_a.sub(&p_b)
             },
             Op::Mul(a, b) => {
-                let p_a = self.solve_poly_cached(&a, var, cache);
-                let p_b = self.solve_poly_cached(&b, var, cache);
+                let p_a = self.solve_poly_cached(a, var, cache)?;
+                let p_b = self.solve_poly_cached(b, var, cache)?;
                 p_a.mul(&p_b)
             },
             Op::Div(a, b) => {
-                let p_a = self.solve_poly_cached(&a, var, cache);
-                let p_b = self.solve_poly_cached(&b, var, cache);
+                let p_a = self.solve_poly_cached(a, var, cache)?;
+                let p_b = self.solve_poly_cached(b, var, cache)?;
                 p_a.div(&p_b)
             }
         };
         cache.insert(name.to_string(), res.clone());
-        res
+        Some(res)
     }
 }
 
@@ -198,9 +214,9 @@ impl Poly {
     fn mul(&self, p : &Poly) -> Poly { 
         if p.coeff.len()!=1 {
             assert!(self.coeff.len()==1);
-            p.mul(&self)
+            p.mul(self)
         } else {
-            let scalar = *p.coeff.get(0).unwrap();
+            let scalar = *p.coeff.first().unwrap();
             let coeff = self.coeff.iter().map(
                 |a| a*scalar
             ).collect();
@@ -210,7 +226,7 @@ impl Poly {
 
     fn div(&self, p : &Poly) -> Poly { 
         assert!(p.coeff.len()==1);
-        let scalar = *p.coeff.get(0).unwrap();
+        let scalar = *p.coeff.first().unwrap();
         let coeff = self.coeff.iter().map(
             |a| a/scalar
         ).collect();
@@ -219,11 +235,74 @@ impl Poly {
 
     pub fn solve(&self) -> i64 {
         assert!(self.coeff.len()==2);
-        let a = self.coeff.get(0).unwrap();
+        let a = self.coeff.first().unwrap();
         let b = self.coeff.get(1).unwrap();
         // at + b = 0   => t = -b / a
         let res = -b / a;
         assert!(*res.denom() == 1);
         *res.numer()
     }
-}
\ No newline at end of file
+}
+
+impl fmt::Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Op::Num(num) => write!(f, "{}", num),
+            Op::Add(a, b) => write!(f, "{} + {}", a, b),
+            Op::Sub(a, b) => write!(f, "{} - {}", a, b),
+            Op::Mul(a, b) => write!(f, "{} * {}", a, b),
+            Op::Div(a, b) => write!(f, "{} / {}", a, b)
+        }
+    }
+}
+
+// The monkeys are kept by name so they are written sorted by name
+impl fmt::Display for Riddle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.ops.iter().sorted_by_key(|(name, _)| *name).map(
+            |(name, op)| format!("{}: {}", name, op)
+        ).join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use itertools::Itertools;
+
+    use crate::io::{self, Input};
+
+    const EXAMPLE : &str = include_str!("../../data/examples/day-21.txt");
+
+    #[test]
+    fn root() {
+        let riddle = io::input_as_riddles(&Input::text(EXAMPLE)).unwrap();
+        assert_eq!(riddle.solve("root"), Some(152));
+        assert_eq!(riddle.solve("sllz"), Some(4));
+    }
+
+    #[test]
+    fn human() {
+        let riddle = io::input_as_riddles(&Input::text(EXAMPLE)).unwrap();
+        assert_eq!(riddle.root_eqn("humn").unwrap().solve(), 301);
+    }
+
+    #[test]
+    fn round_trip() {
+        let riddle = io::input_as_riddles(&Input::text(EXAMPLE)).unwrap();
+        assert_eq!(io::input_as_riddles(&Input::text(&riddle.to_string())).unwrap(), riddle);
+        assert_eq!(
+            riddle.to_string().lines().collect::<Vec<&str>>(),
+            EXAMPLE.lines().sorted().collect::<Vec<&str>>()
+        );
+    }
+
+    #[test]
+    fn no_root() {
+        let riddle = io::input_as_riddles(&Input::text("humn: 5\nroot: humn + abcd")).unwrap();
+        assert_eq!(riddle.solve("root"), None);
+        assert!(riddle.root_eqn("humn").is_none());
+        let riddle = io::input_as_riddles(&Input::text("humn: 5")).unwrap();
+        assert_eq!(riddle.solve("root"), None);
+        assert!(riddle.root_eqn("humn").is_none());
+    }
+}
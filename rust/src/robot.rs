@@ -1,6 +1,14 @@
-use std::{cmp::{min, max}, collections::HashMap};
+use std::cmp::max;
+use std::fmt;
 
+use crate::error::{Error, Result, parse_num};
+use crate::metrics;
+use crate::search::{self, Bounded};
+
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Blueprint {
+    id : usize,
     ore_ore : usize,
     clay_ore : usize,
     obsidean_ore : usize,
@@ -9,8 +17,22 @@ pub struct Blueprint {
     geode_obsidean : usize
 }
 
-#[derive(PartialEq, Eq, Hash, Clone)]
+#[derive(Clone, Copy)]
+enum Robot {
+    Ore,
+    Clay,
+    Obsidean,
+    Geode
+}
+
+// The search for the most geodes a blueprint can open in a time
+struct Geodes<'a> {
+    blueprint : &'a Blueprint
+}
+
+#[derive(Clone)]
 struct State {
+    time : usize,
     ore_robots : usize,
     clay_robots : usize,
     obsidean_robots : usize,
@@ -22,17 +44,30 @@ struct State {
 }
 
 impl Blueprint {
-    pub fn from_line(line : &str) -> Option<Blueprint> {
-        let numbers : Vec<usize> = line.split(" ").filter_map(
+    pub fn from_line(line : &str) -> Result<Blueprint> {
+        let (id_s, costs_s) = line.split_once(": ").ok_or(
+            Error::line(line, "Blueprint N: followed by the costs")
+        )?;
+        let id = parse_num(line, id_s.strip_prefix("Blueprint ").ok_or(
+            Error::at(line, id_s, "Blueprint")
+        )?)?;
+        let numbers : Vec<usize> = costs_s.split(" ").filter_map(
             |part| part.parse::<usize>().ok()
         ).collect();
-        let ore_ore = *numbers.get(0)?;
-        let clay_ore = *numbers.get(1)?;
-        let obsidean_ore = *numbers.get(2)?;
-        let obsidean_clay = *numbers.get(3)?;
-        let geode_ore = *numbers.get(4)?;
-        let geode_obsidean = *numbers.get(5)?;
-        Some(Blueprint { 
+        if numbers.len() != 6 {
+            return Err(Error::line(line, "six robot costs"));
+        }
+        if numbers.contains(&0) {
+            return Err(Error::line(line, "robot costs above zero"));
+        }
+        let ore_ore = numbers[0];
+        let clay_ore = numbers[1];
+        let obsidean_ore = numbers[2];
+        let obsidean_clay = numbers[3];
+        let geode_ore = numbers[4];
+        let geode_obsidean = numbers[5];
+        Ok(Blueprint { 
+            id : id,
             ore_ore : ore_ore, 
             clay_ore : clay_ore, 
             obsidean_ore : obsidean_ore, 
@@ -42,8 +77,13 @@ impl Blueprint {
         })
     }
 
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
     pub fn max_geodes(&self, time : usize) -> usize {
         let initial_state = State {
+            time : time,
             ore_robots : 1,
             clay_robots : 0,
             obsidean_robots : 0,
@@ -53,98 +93,158 @@ impl Blueprint {
             obsidean : 0,
             geodes : 0
         };
-        self.max_geodes_state(time, &initial_state, &mut HashMap::new())
+        let (best, stats) = search::branch_and_bound(&Geodes { blueprint : self }, initial_state);
+        log::debug!("Blueprint search over {} minutes: {:?}", time, stats);
+        metrics::add("geodes.calls", stats.expanded as u64);
+        metrics::add("geodes.pruned", stats.pruned as u64);
+        best
     }
 
-    fn max_geodes_state(
-        &self, 
-        time : usize, 
-        state : &State,
-        cache : &mut HashMap<(usize, State), usize>
-    ) -> usize {
-        if time == 0 {
-            return state.geodes;
-        }
-        let key = (time, state.clone());
-        if let Some(res) = cache.get(&key) {
-            return *res;
-        }
-        let mut best_count = 0;
-        // Do all combinations of robot making we can do - there are dependencies!
-        for ore_made_state in self.state_create_ore(state) {
-            for clay_made_state in self.state_create_clay(&ore_made_state) {
-                for obsidean_made_state in self.state_create_obsidean(&clay_made_state) {
-                    for mut geode_made_state in self.state_create_geode(&obsidean_made_state) {
-                        // Gather 
-                        geode_made_state.ore += state.ore_robots;
-                        geode_made_state.clay += state.clay_robots;
-                        geode_made_state.obsidean += state.obsidean_robots;
-                        geode_made_state.geodes += state.geode_robots;
-                        best_count = max(
-                            best_count,
-                            self.max_geodes_state(time-1, &geode_made_state, cache)
-                        );
-
-                    }
-                }
-            }
-        }
-        cache.insert(key, best_count);
-        println!("Cache size: {}", cache.len());
-        best_count
-    }
-
-    fn state_create_ore(&self, state : &State) -> Vec<State> {
-        let max_ore_robots = state.ore / self.ore_ore;
-        let mut new_states = Vec::new();
-        for i in 0..=max_ore_robots {
-            let mut new_state = state.clone();
-            new_state.ore_robots += i;
-            new_state.ore -= i*self.ore_ore;
-            new_states.push(new_state);
+    // Only one robot can be built a minute so there is no point having more
+    // robots of a kind than the most of that resource any robot costs
+    fn worth_building(&self, robot : Robot, state : &State) -> bool {
+        match robot {
+            Robot::Ore => {
+                let max_ore = max(max(self.ore_ore, self.clay_ore), max(self.obsidean_ore, self.geode_ore));
+                state.ore_robots < max_ore
+            },
+            Robot::Clay => state.clay_robots < self.obsidean_clay,
+            Robot::Obsidean => state.obsidean_robots < self.geode_obsidean,
+            Robot::Geode => true
         }
-        new_states
     }
 
-    fn state_create_clay(&self, state : &State) -> Vec<State> {
-        let max_clay_robots = state.ore / self.clay_ore;
-        let mut new_states = Vec::new();
-        for i in 0..=max_clay_robots {
-            let mut new_state = state.clone();
-            new_state.clay_robots += i;
-            new_state.ore -= i*self.clay_ore;
-            new_states.push(new_state);
+    // Ore, clay and obsidean a robot costs
+    fn cost(&self, robot : Robot) -> (usize, usize, usize) {
+        match robot {
+            Robot::Ore => (self.ore_ore, 0, 0),
+            Robot::Clay => (self.clay_ore, 0, 0),
+            Robot::Obsidean => (self.obsidean_ore, self.obsidean_clay, 0),
+            Robot::Geode => (self.geode_ore, 0, self.geode_obsidean)
         }
-        new_states
-    }
-
-    fn state_create_obsidean(&self, state : &State) -> Vec<State> {
-        let max_obsidean_robots_ore = state.ore / self.obsidean_ore;
-        let max_obsidean_robots_clay = state.clay / self.obsidean_clay;
-        let max_obsidean_robots = min(max_obsidean_robots_ore, max_obsidean_robots_clay);
-        let mut new_states = Vec::new();
-        for i in 0..=max_obsidean_robots {
-            let mut new_state = state.clone();
-            new_state.obsidean_robots += i;
-            new_state.ore -= i*self.obsidean_ore;
-            new_state.clay -= i*self.obsidean_clay;
-            new_states.push(new_state);
-        }
-        new_states
-    }
-
-    fn state_create_geode(&self, state : &State) -> Vec<State> {
-        let max_geode_robots_ore = state.ore / self.geode_ore;
-        let max_geode_robots_obsidean = state.clay / self.geode_obsidean;
-        let max_geode_robots = min(max_geode_robots_ore, max_geode_robots_obsidean);
-        let mut new_states = Vec::new();
-        for i in 0..=max_geode_robots {
-            let mut new_state = state.clone();
-            new_state.geode_robots += i;
-            new_state.ore -= i*self.geode_ore;
-            new_state.obsidean -= i*self.geode_obsidean;
-            new_states.push(new_state);
+    }
+
+    // Minutes of gathering before the robot can be afforded, None if the
+    // robots needed to gather for it have not been built
+    fn wait_for(&self, robot : Robot, state : &State) -> Option<usize> {
+        let (ore, clay, obsidean) = self.cost(robot);
+        let waits = [
+            wait(ore, state.ore, state.ore_robots)?,
+            wait(clay, state.clay, state.clay_robots)?,
+            wait(obsidean, state.obsidean, state.obsidean_robots)?
+        ];
+        waits.into_iter().max()
+    }
+
+    // The state after gathering for minutes and then paying for the robot
+    fn build(&self, robot : Robot, state : &State, minutes : usize) -> State {
+        let (ore, clay, obsidean) = self.cost(robot);
+        let mut new_state = State {
+            time : state.time - minutes,
+            ore : state.ore + state.ore_robots * minutes - ore,
+            clay : state.clay + state.clay_robots * minutes - clay,
+            obsidean : state.obsidean + state.obsidean_robots * minutes - obsidean,
+            geodes : state.geodes + state.geode_robots * minutes,
+            ..state.clone()
+        };
+        match robot {
+            Robot::Ore => new_state.ore_robots += 1,
+            Robot::Clay => new_state.clay_robots += 1,
+            Robot::Obsidean => new_state.obsidean_robots += 1,
+            Robot::Geode => new_state.geode_robots += 1
         }
-        new_states
+        new_state
+    }
+}
+
+// Branches on which robot to build next, skipping the minutes spent
+// waiting for it. The bound assumes a geode robot every minute from now
+impl<'a> Bounded for Geodes<'a> {
+    type State = State;
+
+    fn value(&self, state : &State) -> usize {
+        state.geodes + state.geode_robots * state.time
+    }
+
+    fn bound(&self, state : &State) -> usize {
+        self.value(state) + state.time * state.time.saturating_sub(1) / 2
+    }
+
+    fn branches(&self, state : &State) -> Vec<State> {
+        let blueprint = self.blueprint;
+        [Robot::Geode, Robot::Obsidean, Robot::Clay, Robot::Ore].into_iter().filter(
+            |robot| blueprint.worth_building(*robot, state)
+        ).filter_map(
+            // A robot finished in the last minute collects nothing
+            |robot| blueprint.wait_for(robot, state).filter(
+                |wait| wait + 1 < state.time
+            ).map(
+                |wait| blueprint.build(robot, state, wait + 1)
+            )
+        ).collect()
+    }
+}
+
+fn wait(cost : usize, have : usize, robots : usize) -> Option<usize> {
+    if have >= cost {
+        Some(0)
+    } else if robots == 0 {
+        None
+    } else {
+        Some((cost - have).div_ceil(robots))
     }
-}
\ No newline at end of file
+}
+
+impl fmt::Display for Blueprint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Blueprint {}: Each ore robot costs {} ore. Each clay robot costs {} ore. Each obsidian robot costs {} ore and {} clay. Each geode robot costs {} ore and {} obsidian.",
+            self.id, self.ore_ore, self.clay_ore, self.obsidean_ore, self.obsidean_clay, self.geode_ore, self.geode_obsidean
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::{self, Input};
+
+    const EXAMPLE : &str = include_str!("../../data/examples/day-19.txt");
+
+    #[test]
+    fn costs() {
+        let blueprints = io::input_as_blueprints(&Input::text(EXAMPLE)).unwrap();
+        assert_eq!(blueprints.len(), 2);
+        assert_eq!(blueprints[1].obsidean_clay, 8);
+        assert_eq!(blueprints[1].geode_obsidean, 12);
+    }
+
+    #[test]
+    fn geodes() {
+        let blueprints = io::input_as_blueprints(&Input::text(EXAMPLE)).unwrap();
+        assert_eq!(blueprints[0].max_geodes(24), 9);
+        assert_eq!(blueprints[1].max_geodes(24), 12);
+    }
+
+    #[test]
+    fn geodes_long() {
+        let blueprints = io::input_as_blueprints(&Input::text(EXAMPLE)).unwrap();
+        assert_eq!(blueprints[0].max_geodes(32), 56);
+        assert_eq!(blueprints[1].max_geodes(32), 62);
+    }
+
+    #[test]
+    fn round_trip() {
+        let blueprints = io::input_as_blueprints(&Input::text(EXAMPLE)).unwrap();
+        let lines : Vec<String> = blueprints.iter().map(|blueprint| blueprint.to_string()).collect();
+        assert_eq!(lines, EXAMPLE.lines().collect::<Vec<&str>>());
+        assert_eq!(io::input_as_blueprints(&Input::text(&lines.join("\n"))).unwrap(), blueprints);
+    }
+
+    #[test]
+    fn missing_cost() {
+        assert!(Blueprint::from_line("Blueprint 1: Each ore robot costs 4 ore.").is_err());
+        assert!(Blueprint::from_line("Each ore robot costs 4 ore.").is_err());
+    }
+}
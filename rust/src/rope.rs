@@ -1,45 +1,58 @@
 use std::collections::HashSet;
-use itertools::repeat_n;
+use std::fmt;
+use itertools::{Itertools, repeat_n};
 
-enum Direction {
-    Right,
-    Left,
-    Up,
-    Down
-}
+use crate::error::{Error, Result, parse_lines, parse_num};
+use crate::geometry::{Direction, Point2};
+use crate::grid::Bounds;
+use crate::render::{Animate, Frame};
 
 struct Rope {
-    head : (i64, i64),
-    tail : Vec<(i64, i64)>
+    head : Point2,
+    tail : Vec<Point2>
 }
 
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Directions {
     direction : Vec<(Direction, usize)>
 }
 
-fn from_line(line : &str) -> Option<(Direction, usize)> {
-    let (dir_s, dist_s) = line.split_once(' ')?;
-    let dist = dist_s.parse::<usize>().ok()?;
+// The head moved a step at a time, showing where the tail has been
+pub struct Motion {
+    directions : Directions,
+    rope : Rope,
+    index : usize,
+    moved : usize,
+    visited : HashSet<Point2>
+}
+
+fn from_line(line : &str) -> Result<(Direction, usize)> {
+    let (dir_s, dist_s) = line.split_once(' ').ok_or(
+        Error::line(line, "a direction and a distance")
+    )?;
+    let dist = parse_num(line, dist_s)?;
     match dir_s {
-        "R" => Some((Direction::Right, dist)),
-        "L" => Some((Direction::Left, dist)),
-        "U" => Some((Direction::Up, dist)),
-        "D" => Some((Direction::Down, dist)),
-        _ => None
+        "R" => Ok((Direction::East, dist)),
+        "L" => Ok((Direction::West, dist)),
+        "U" => Ok((Direction::North, dist)),
+        "D" => Ok((Direction::South, dist)),
+        _ => Err(Error::at(line, dir_s, "one of R, L, U or D"))
     }
 }
 
 impl Directions {
-    pub fn from_lines(lines : &Vec<String>) -> Directions {
-        let dirs = lines.iter().filter_map(
-            |line| from_line(line)
-        ).collect();
-        Directions { direction: dirs }
+    pub fn from_lines(lines : &Vec<String>) -> Result<Directions> {
+        let dirs = parse_lines(
+            lines,
+            from_line
+        )?;
+        Ok(Directions { direction: dirs })
     }
 
-    pub fn simulate(&self, len : usize) -> HashSet<(i64,i64)> {
-        let mut pos : HashSet<(i64,i64)> = HashSet::new();
-        pos.insert((0,0));
+    pub fn simulate(&self, len : usize) -> HashSet<Point2> {
+        let mut pos : HashSet<Point2> = HashSet::new();
+        pos.insert(Point2::ORIGIN);
         let mut rope = Rope::new(len);
         for (dir, dist) in self.direction.iter() {
             for _ in 0..*dist {
@@ -51,44 +64,131 @@ impl Directions {
     }
 }
 
-fn sign(i : i64) -> i64 {
-    if i > 0 { 
-        1 
-    } else { 
-        if i < 0 {-1} else {0}
-    }
-}
-
 impl Rope {
 
     fn new(len : usize) -> Rope {
         Rope {
-            head : (0,0),
-            tail : Vec::from_iter(repeat_n((0,0), len - 1))
+            head : Point2::ORIGIN,
+            tail : Vec::from_iter(repeat_n(Point2::ORIGIN, len - 1))
         }
     } 
 
-    fn end(&self) -> (i64, i64) {
+    fn end(&self) -> Point2 {
         *self.tail.last().unwrap()
     }
 
     fn update(&mut self, dir : &Direction) {
-        match *dir {
-            Direction::Right => self.head.1 += 1,
-            Direction::Left => self.head.1 -= 1,
-            Direction::Up => self.head.0 += 1,
-            Direction::Down => self.head.0 -= 1,
-        };
+        self.head = self.head.step(*dir);
         let mut prev = self.head;
         for tail in self.tail.iter_mut() {
-            let diff_x = prev.0 - tail.0;
-            let diff_y = prev.1 - tail.1;
-            if diff_x.abs() > 1 || diff_y.abs() > 1 {
-                tail.0 += sign(diff_x);
-                tail.1 += sign(diff_y);
+            let diff = prev - *tail;
+            if prev.chebyshev(*tail) > 1 {
+                *tail += diff.signum();
             }
             prev = *tail;
         }
 
     }
-}
\ No newline at end of file
+}
+
+impl Motion {
+    pub fn new(directions : Directions, len : usize) -> Motion {
+        Motion {
+            directions : directions,
+            rope : Rope::new(len),
+            index : 0,
+            moved : 0,
+            visited : HashSet::from([Point2::ORIGIN])
+        }
+    }
+}
+
+impl Animate for Motion {
+    fn step(&mut self) -> bool {
+        let moves = &self.directions.direction;
+        while self.index < moves.len() && self.moved == moves[self.index].1 {
+            self.index += 1;
+            self.moved = 0;
+        }
+        if self.index == moves.len() {
+            return false;
+        }
+        self.rope.update(&moves[self.index].0);
+        self.visited.insert(self.rope.end());
+        self.moved += 1;
+        true
+    }
+
+    // The head is H and the knots behind it count up from 1
+    fn frame(&self) -> Frame {
+        let knots = [self.rope.head].into_iter().chain(self.rope.tail.iter().copied());
+        let bounds = knots.clone().chain(self.visited.iter().copied()).fold(
+            Bounds::new(Point2::ORIGIN, Point2::ORIGIN),
+            |bounds, pos| bounds.include(pos)
+        );
+        Frame::draw(bounds, |pos| match knots.clone().position(|knot| knot == pos) {
+            Some(0) => 'H',
+            Some(i) => char::from_digit(i.min(9) as u32, 10).unwrap(),
+            None if pos == Point2::ORIGIN => 's',
+            None if self.visited.contains(&pos) => '#',
+            None => '.'
+        })
+    }
+}
+
+impl fmt::Display for Directions {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.direction.iter().map(
+            |(dir, dist)| {
+                let dir_s = match dir {
+                    Direction::East => "R",
+                    Direction::West => "L",
+                    Direction::North => "U",
+                    Direction::South => "D"
+                };
+                format!("{} {}", dir_s, dist)
+            }
+        ).join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::{self, Input};
+    use crate::render;
+
+    const EXAMPLE : &str = include_str!("../../data/examples/day-9.txt");
+    const LARGER : &str = include_str!("../../data/examples/day-9-larger.txt");
+
+    #[test]
+    fn short_rope() {
+        let directions = io::input_as_directions(&Input::text(EXAMPLE)).unwrap();
+        assert_eq!(directions.simulate(2).len(), 13);
+    }
+
+    #[test]
+    fn long_rope() {
+        let directions = io::input_as_directions(&Input::text(EXAMPLE)).unwrap();
+        assert_eq!(directions.simulate(10).len(), 1);
+        let directions = io::input_as_directions(&Input::text(LARGER)).unwrap();
+        assert_eq!(directions.simulate(10).len(), 36);
+    }
+
+    #[test]
+    fn frames() {
+        let directions = io::input_as_directions(&Input::text(EXAMPLE)).unwrap();
+        let frames = render::frames(&mut Motion::new(directions, 2), 1, 100);
+        assert_eq!(frames.len(), 25);
+        assert_eq!(frames[24].to_string(), "..##.\n...##\n.1H##\n....#\ns###.");
+    }
+
+    #[test]
+    fn round_trip() {
+        for text in [EXAMPLE, LARGER] {
+            let directions = io::input_as_directions(&Input::text(text)).unwrap();
+            assert_eq!(directions.to_string(), text.trim_end());
+            assert_eq!(io::input_as_directions(&Input::text(&directions.to_string())).unwrap(), directions);
+        }
+    }
+}
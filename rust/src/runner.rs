@@ -0,0 +1,128 @@
+use std::panic::{self, AssertUnwindSafe};
+use std::time::Duration;
+
+use rayon::prelude::*;
+use serde_json::{json, Value};
+
+use crate::error::{Error, Result};
+use crate::io::Input;
+use crate::metrics::{self, Counters};
+use crate::solution::{Answer, Part, Puzzle, Registry, Timing};
+
+// What happened when a part was run
+#[derive(Debug)]
+pub enum Status {
+    Solved(Answer),
+    Skipped(Error),
+    Failed(Error),
+    Panicked(String)
+}
+
+#[derive(Debug)]
+pub struct Outcome {
+    pub day : u8,
+    pub part : Part,
+    pub timing : Option<Timing>,
+    pub counters : Counters,
+    pub status : Status
+}
+
+impl Outcome {
+    pub fn is_solved(&self) -> bool {
+        matches!(self.status, Status::Solved(_))
+    }
+
+    // Skipped for a missing input is neither solved nor failed
+    pub fn is_failed(&self) -> bool {
+        matches!(self.status, Status::Failed(_) | Status::Panicked(_))
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.timing.map(|timing| timing.parse + timing.solve).unwrap_or_default()
+    }
+
+    // A record of the outcome for machine readable output, numbers stay
+    // numbers and the times are in seconds
+    pub fn to_json(&self) -> Value {
+        let answer = match &self.status {
+            Status::Solved(Answer::Num(num)) => json!(num),
+            Status::Solved(Answer::Text(text)) => json!(text),
+            _ => Value::Null
+        };
+        let timing = match self.timing {
+            Some(timing) => json!({
+                "parse" : timing.parse.as_secs_f64(),
+                "solve" : timing.solve.as_secs_f64()
+            }),
+            None => Value::Null
+        };
+        let error = match &self.status {
+            Status::Solved(_) => Value::Null,
+            Status::Skipped(err) | Status::Failed(err) => json!(err.to_string()),
+            Status::Panicked(message) => json!(format!("panicked: {}", message))
+        };
+        let mut record = json!({
+            "day" : self.day,
+            "part" : self.part.num(),
+            "answer" : answer,
+            "timing" : timing,
+            "error" : error,
+            "skipped" : matches!(self.status, Status::Skipped(_))
+        });
+        if metrics::enabled() {
+            record["metrics"] = self.counters.iter().map(
+                |(name, num)| (name.to_string(), json!(num))
+            ).collect();
+        }
+        record
+    }
+}
+
+fn panic_message(payload : &(dyn std::any::Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+// Run a part, a panic in the solution is caught and kept in the outcome
+pub fn run_part(puzzle : &dyn Puzzle, part : Part, input : &Input) -> Outcome {
+    let result = panic::catch_unwind(AssertUnwindSafe(
+        || puzzle.run_timed(part, input)
+    ));
+    let (timing, status) = match result {
+        Ok(Ok((answer, timing))) => (Some(timing), Status::Solved(answer)),
+        Ok(Err(err)) if err.is_missing_input() => (None, Status::Skipped(err)),
+        Ok(Err(err)) => (None, Status::Failed(err)),
+        Err(payload) => (None, Status::Panicked(panic_message(payload.as_ref())))
+    };
+    Outcome { day : puzzle.day(), part : part, timing : timing, counters : metrics::take(), status : status }
+}
+
+// Run every part of every day on a pool of jobs threads, all threads if 0.
+// The outcomes are sorted by day and part
+pub fn run_parallel<F>(registry : &Registry, jobs : usize, input_for : F) -> Result<Vec<Outcome>>
+  where F : Fn(u8) -> Input
+{
+    let pool = rayon::ThreadPoolBuilder::new().num_threads(jobs).build().map_err(
+        |err| Error::invalid(&format!("could not start the thread pool: {}", err))
+    )?;
+    let inputs : Vec<(&dyn Puzzle, Input)> = registry.iter().map(
+        |puzzle| (puzzle, input_for(puzzle.day()))
+    ).collect();
+    let tasks : Vec<(&dyn Puzzle, Part, &Input)> = inputs.iter().flat_map(
+        |(puzzle, input)| puzzle.parts().into_iter().map(
+            move |part| (*puzzle, part, input)
+        )
+    ).collect();
+    let mut outcomes : Vec<Outcome> = pool.install(
+        || tasks.into_par_iter().map(
+            |(puzzle, part, input)| run_part(puzzle, part, input)
+        ).collect()
+    );
+    outcomes.sort_by_key(|outcome| (outcome.day, outcome.part));
+    Ok(outcomes)
+}
@@ -1,37 +1,63 @@
-use std::collections::HashSet;
 use std::cmp::{min, max};
+use std::fmt;
 
-#[derive(Debug)]
+use crate::error::{Error, Result, parse_lines, parse_num};
+use crate::geometry::Point2;
+use crate::grid::SparseGrid;
+use crate::render::{Animate, Frame};
+
+// Where the sand pours in
+const SOURCE : Point2 = Point2::new(500, 0);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+enum Tile {
+    Rock,
+    Sand
+}
+
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Cave {
-    max_y : Option<i64>,
-    rock : HashSet<(i64,i64)>,
-    sand : HashSet<(i64,i64)>
+    max_y : i64,
+    tiles : SparseGrid<Tile>
 }
 
-fn parse_coords(string : &str) -> Vec<(i64,i64)> {
-    string.split(" -> ").filter_map(
-        |part| {
-            let (p1,p2) = part.split_once(",")?;
-            let i1 = p1.parse::<i64>().ok()?;
-            let i2 = p2.parse::<i64>().ok()?;
-            Some((i1,i2))
+// Each point of a path is straight across or straight down from the last
+fn parse_coords(string : &str) -> Result<Vec<Point2>> {
+    let mut coords : Vec<Point2> = Vec::new();
+    for part in string.split(" -> ") {
+        let (p1,p2) = part.split_once(",").ok_or(
+            Error::at(string, part, "a point x,y")
+        )?;
+        let i1 = parse_num(string, p1)?;
+        let i2 = parse_num(string, p2)?;
+        let point = Point2::new(i1, i2);
+        if coords.last().is_some_and(|last| last.x != point.x && last.y != point.y) {
+            return Err(Error::at(string, part, "a point in line with the one before"));
         }
-    ).collect()
+        coords.push(point);
+    }
+    Ok(coords)
 }
 
 impl Cave {
-    pub fn from_lines(lines : &Vec<String>) -> Cave {
-        let mut cave = Cave { max_y : None, rock : HashSet::new(), sand : HashSet::new() };
-        for line in lines.iter() {
-            cave.add_rock_row(&parse_coords(line));
+    pub fn from_lines(lines : &Vec<String>) -> Result<Cave> {
+        let mut cave = Cave { max_y : 0, tiles : SparseGrid::new() };
+        let rows = parse_lines(
+            lines,
+            parse_coords
+        )?;
+        for row in rows.iter() {
+            cave.add_rock_row(row);
         }
-        cave.max_y = cave.rock.iter().map(
-            |(_,y)| *y + 1
-        ).max();
-        cave
+        cave.max_y = cave.tiles.bounds().ok_or(
+            Error::invalid("no rock in the cave")
+        )?.max.y + 1;
+        Ok(cave)
     }
 
-    fn add_rock_row(&mut self, coords : &[(i64, i64)]) {
+    fn add_rock_row(&mut self, coords : &[Point2]) {
         let mut start = coords[0];
         for end in coords[1..].iter() {
             self.add_rock_line(&start, end);
@@ -39,25 +65,25 @@ impl Cave {
         }
     }
 
-    fn add_rock_line(&mut self, start : &(i64,i64), end : &(i64, i64)) {
-        if start.0 == end.0 {
-            let min_y = min(start.1, end.1);
-            let max_y = max(start.1, end.1);
+    fn add_rock_line(&mut self, start : &Point2, end : &Point2) {
+        if start.x == end.x {
+            let min_y = min(start.y, end.y);
+            let max_y = max(start.y, end.y);
             for i in min_y..=max_y {
-                self.rock.insert((start.0, i));
+                self.tiles.insert(Point2::new(start.x, i), Tile::Rock);
             }
         } else {
-            let min_x = min(start.0, end.0);
-            let max_x = max(start.0, end.0);
+            let min_x = min(start.x, end.x);
+            let max_x = max(start.x, end.x);
             for i in min_x..=max_x {
-                self.rock.insert((i, start.1));
+                self.tiles.insert(Point2::new(i, start.y), Tile::Rock);
             }
         }
     }
 
-    fn simulate_sand(&self, start : &(i64,i64)) -> (i64,i64) {
+    fn simulate_sand(&self, start : &Point2) -> Point2 {
         let mut curr = *start;
-        while curr.1 < self.max_y.unwrap() {
+        while curr.y < self.max_y {
             let next = self.step_sand(&curr);
             if next == curr {
                 break;
@@ -67,17 +93,17 @@ impl Cave {
         curr
     }
 
-    fn step_sand(&self, start : &(i64,i64)) -> (i64, i64) {
+    fn step_sand(&self, start : &Point2) -> Point2 {
         // if below free go there
-        let down = (start.0, start.1+1);
+        let down = *start + Point2::new(0, 1);
         if self.is_free(&down) {
             down
         } else {
-            let down_left = (start.0-1, start.1+1);
+            let down_left = *start + Point2::new(-1, 1);
             if self.is_free(&down_left) {
                 down_left
             } else {
-                let down_right = (start.0+1, start.1+1);
+                let down_right = *start + Point2::new(1, 1);
                 if self.is_free(&down_right) {
                     down_right
                 } else {
@@ -87,27 +113,129 @@ impl Cave {
         }
     }
 
-    fn is_free(&self, pos : &(i64,i64)) -> bool {
-        !( self.rock.contains(pos) || self.sand.contains(pos) )
+    fn is_free(&self, pos : &Point2) -> bool {
+        !self.tiles.contains(*pos)
+    }
+
+    fn sand(&self) -> usize {
+        self.tiles.iter().filter(
+            |(_, tile)| **tile == Tile::Sand
+        ).count()
     }
 
     pub fn pour_wall(&mut self) -> usize {
-        let start = (500,0);
+        let start = SOURCE;
         let mut pos = self.simulate_sand(&start);
         while pos != start {
             pos = self.simulate_sand(&start);
-            self.sand.insert(pos);
+            self.tiles.insert(pos, Tile::Sand);
         }
-        self.sand.len()
+        self.sand()
     }
 
+    // Stops once sand falls into the void, or the source itself is covered
     pub fn pour_void(&mut self) -> usize {
-        let start = (500,0);
-        let mut pos = self.simulate_sand(&start);
-        while pos.1 <= self.max_y.unwrap() {
-            self.sand.insert(pos);
-            pos = self.simulate_sand(&start);
+        let mut pos = self.simulate_sand(&SOURCE);
+        while pos.y < self.max_y && !self.tiles.contains(pos) {
+            self.tiles.insert(pos, Tile::Sand);
+            pos = self.simulate_sand(&SOURCE);
         }
-        self.sand.len()
+        self.sand()
     }
-}
\ No newline at end of file
+}
+
+// A grain of sand a step until the sand falls into the void
+impl Animate for Cave {
+    fn step(&mut self) -> bool {
+        let pos = self.simulate_sand(&SOURCE);
+        if pos.y >= self.max_y || self.tiles.contains(pos) {
+            return false;
+        }
+        self.tiles.insert(pos, Tile::Sand);
+        true
+    }
+
+    fn frame(&self) -> Frame {
+        Frame::draw(self.tiles.bounds().unwrap().include(SOURCE), |pos| match self.tiles.get(pos) {
+            Some(Tile::Rock) => '#',
+            Some(Tile::Sand) => 'o',
+            None if pos == SOURCE => '+',
+            None => '.'
+        })
+    }
+}
+
+// The rock as one path for each horizontal run in a row, any sand is left out
+impl fmt::Display for Cave {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut paths = Vec::new();
+        if let Some(bounds) = self.tiles.bounds() {
+            for y in bounds.min.y..=bounds.max.y {
+                let mut run : Option<(i64, i64)> = None;
+                for x in bounds.min.x..=bounds.max.x + 1 {
+                    let rock = self.tiles.get(Point2::new(x, y)) == Some(&Tile::Rock);
+                    run = match (run, rock) {
+                        (None, true) => Some((x, x)),
+                        (Some((start, _)), true) => Some((start, x)),
+                        (Some((start, end)), false) => {
+                            paths.push(format!("{},{} -> {},{}", start, y, end, y));
+                            None
+                        },
+                        (None, false) => None
+                    };
+                }
+            }
+        }
+        write!(f, "{}", paths.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::io::{self, Input};
+    use crate::render;
+
+    const EXAMPLE : &str = include_str!("../../data/examples/day-14.txt");
+
+    #[test]
+    fn into_void() {
+        let mut cave = io::input_as_cave(&Input::text(EXAMPLE)).unwrap();
+        assert_eq!(cave.pour_void(), 24);
+    }
+
+    #[test]
+    fn frames() {
+        let mut cave = io::input_as_cave(&Input::text(EXAMPLE)).unwrap();
+        let frames = render::frames(&mut cave, 1, 100);
+        assert_eq!(frames.len(), 25);
+        assert_eq!(frames[24].to_string().matches('o').count(), 24);
+        assert!(frames[0].to_string().starts_with("......+"));
+    }
+
+    #[test]
+    fn clogged_source() {
+        let mut cave = io::input_as_cave(&Input::text("495,1 -> 495,5 -> 505,5 -> 505,1")).unwrap();
+        assert_eq!(cave.pour_void(), 25);
+    }
+
+    #[test]
+    fn onto_floor() {
+        let mut cave = io::input_as_cave(&Input::text(EXAMPLE)).unwrap();
+        assert_eq!(cave.pour_wall(), 93);
+    }
+
+    // The paths come out as rows so only the rock is compared
+    #[test]
+    fn round_trip() {
+        let cave = io::input_as_cave(&Input::text(EXAMPLE)).unwrap();
+        let again = io::input_as_cave(&Input::text(&cave.to_string())).unwrap();
+        assert_eq!(again, cave);
+        assert_eq!(again.to_string(), cave.to_string());
+    }
+
+    #[test]
+    fn diagonal_path() {
+        let err = super::parse_coords("498,4 -> 498,6 -> 496,8").unwrap_err();
+        assert_eq!(err.to_string(), r#"line 1 column 19: expected a point in line with the one before in "498,4 -> 498,6 -> 496,8""#);
+    }
+}
@@ -0,0 +1,211 @@
+use std::cmp::{max, Ordering};
+use std::collections::{BinaryHeap, HashMap};
+use std::hash::Hash;
+
+// Work done by a search
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Stats {
+    pub expanded : usize,
+    pub cache_hits : usize,
+    pub pruned : usize
+}
+
+// A maximisation where the best from a state only depends on its key, so
+// the answer for each key is worked out once. A state is worth the best
+// over its moves of the gain of the move plus the worth of where it leads
+pub trait Memoized {
+    type State;
+    type Key : Hash + Eq;
+
+    fn key(&self, state : &Self::State) -> Self::Key;
+
+    // The moves out of a state and what each gains, none at the end
+    fn moves(&self, state : &Self::State) -> Vec<(usize, Self::State)>;
+}
+
+// A maximisation where whole branches are skipped when their bound shows
+// they cannot beat the best value found so far
+pub trait Bounded {
+    type State;
+
+    // The value if the search stops at this state
+    fn value(&self, state : &Self::State) -> usize;
+
+    // No state reached from this one has a value above the bound
+    fn bound(&self, state : &Self::State) -> usize;
+
+    // The states reached in one step, the search goes through them in order
+    fn branches(&self, state : &Self::State) -> Vec<Self::State>;
+}
+
+pub fn memoized<P : Memoized>(problem : &P, start : P::State) -> (usize, Stats) {
+    let mut stats = Stats::default();
+    let best = memoized_best(problem, start, &mut HashMap::new(), &mut stats);
+    (best, stats)
+}
+
+fn memoized_best<P : Memoized>(
+    problem : &P,
+    state : P::State,
+    cache : &mut HashMap<P::Key, usize>,
+    stats : &mut Stats
+) -> usize {
+    let key = problem.key(&state);
+    if let Some(best) = cache.get(&key) {
+        stats.cache_hits += 1;
+        return *best;
+    }
+    stats.expanded += 1;
+    let best = problem.moves(&state).into_iter().map(
+        |(gain, next)| gain + memoized_best(problem, next, cache, stats)
+    ).max().unwrap_or(0);
+    cache.insert(key, best);
+    best
+}
+
+// Depth first branch and bound, good branches first make for more pruning
+pub fn branch_and_bound<P : Bounded>(problem : &P, start : P::State) -> (usize, Stats) {
+    let mut stats = Stats::default();
+    let mut best = 0;
+    branch(problem, start, &mut best, &mut stats);
+    (best, stats)
+}
+
+fn branch<P : Bounded>(problem : &P, state : P::State, best : &mut usize, stats : &mut Stats) {
+    stats.expanded += 1;
+    *best = max(*best, problem.value(&state));
+    for next in problem.branches(&state) {
+        if problem.bound(&next) <= *best {
+            stats.pruned += 1;
+        } else {
+            branch(problem, next, best, stats);
+        }
+    }
+}
+
+// A state waiting in the best first queue, ordered by its bound
+struct Queued<S> {
+    bound : usize,
+    state : S
+}
+
+impl<S> PartialEq for Queued<S> {
+    fn eq(&self, other : &Queued<S>) -> bool {
+        self.bound == other.bound
+    }
+}
+
+impl<S> Eq for Queued<S> {}
+
+impl<S> PartialOrd for Queued<S> {
+    fn partial_cmp(&self, other : &Queued<S>) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<S> Ord for Queued<S> {
+    fn cmp(&self, other : &Queued<S>) -> Ordering {
+        self.bound.cmp(&other.bound)
+    }
+}
+
+// Expand the state with the highest bound next, the search is over once no
+// waiting state could beat the best value. Keeps every waiting state in
+// memory so suits problems with tight bounds
+pub fn best_first<P : Bounded>(problem : &P, start : P::State) -> (usize, Stats) {
+    let mut stats = Stats::default();
+    let mut best = 0;
+    let mut queue = BinaryHeap::new();
+    queue.push(Queued { bound : problem.bound(&start), state : start });
+    while let Some(Queued { bound, state }) = queue.pop() {
+        if bound <= best {
+            stats.pruned += queue.len() + 1;
+            break;
+        }
+        stats.expanded += 1;
+        best = max(best, problem.value(&state));
+        for next in problem.branches(&state) {
+            let bound = problem.bound(&next);
+            if bound <= best {
+                stats.pruned += 1;
+            } else {
+                queue.push(Queued { bound : bound, state : next });
+            }
+        }
+    }
+    (best, stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Knapsack with weights and values, the state is the next item to
+    // decide on and the room left, with the value so far for bounding
+    struct Knapsack {
+        items : Vec<(usize, usize)>
+    }
+
+    impl Memoized for Knapsack {
+        type State = (usize, usize);
+        type Key = (usize, usize);
+
+        fn key(&self, state : &(usize, usize)) -> (usize, usize) {
+            *state
+        }
+
+        fn moves(&self, &(item, room) : &(usize, usize)) -> Vec<(usize, (usize, usize))> {
+            match self.items.get(item) {
+                None => Vec::new(),
+                Some(&(weight, value)) if weight <= room => vec!((0, (item + 1, room)), (value, (item + 1, room - weight))),
+                Some(_) => vec!((0, (item + 1, room)))
+            }
+        }
+    }
+
+    impl Bounded for Knapsack {
+        type State = (usize, usize, usize);
+
+        fn value(&self, state : &(usize, usize, usize)) -> usize {
+            state.2
+        }
+
+        fn bound(&self, &(item, _, value) : &(usize, usize, usize)) -> usize {
+            value + self.items[item..].iter().map(|(_, value)| value).sum::<usize>()
+        }
+
+        fn branches(&self, &(item, room, value) : &(usize, usize, usize)) -> Vec<(usize, usize, usize)> {
+            match self.items.get(item) {
+                None => Vec::new(),
+                Some(&(weight, gain)) if weight <= room => vec!((item + 1, room - weight, value + gain), (item + 1, room, value)),
+                Some(_) => vec!((item + 1, room, value))
+            }
+        }
+    }
+
+    fn knapsack() -> Knapsack {
+        Knapsack { items : vec!((12, 4), (2, 2), (1, 1), (1, 2), (4, 10), (3, 7), (7, 9), (5, 5)) }
+    }
+
+    #[test]
+    fn strategies_agree() {
+        let problem = knapsack();
+        let (memo, memo_stats) = memoized(&problem, (0, 15));
+        let (bnb, bnb_stats) = branch_and_bound(&problem, (0, 15, 0));
+        let (best, best_stats) = best_first(&problem, (0, 15, 0));
+        assert_eq!(memo, 28);
+        assert_eq!(bnb, 28);
+        assert_eq!(best, 28);
+        assert!(memo_stats.cache_hits > 0);
+        assert!(bnb_stats.pruned > 0);
+        assert!(best_stats.expanded < 1 << problem.items.len());
+    }
+
+    #[test]
+    fn no_moves() {
+        let problem = Knapsack { items : Vec::new() };
+        assert_eq!(memoized(&problem, (0, 10)).0, 0);
+        assert_eq!(branch_and_bound(&problem, (0, 10, 0)).0, 0);
+        assert_eq!(best_first(&problem, (0, 10, 0)).0, 0);
+    }
+}
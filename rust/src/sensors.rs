@@ -1,5 +1,12 @@
 use std::collections::BTreeSet;
 use std::cmp::{min, max};
+use std::fmt;
+
+use itertools::Itertools;
+
+use crate::error::{Error, Result, parse_lines, parse_num};
+use crate::geometry::Point2;
+use crate::metrics;
 
 struct Ranges {
     spans : BTreeSet<(i64, i64)>
@@ -12,43 +19,64 @@ struct Box2 {
     ymax : i64
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 struct Sensor {
-    pos : (i64, i64),
+    pos : Point2,
+    beacon : Point2,
     dist : i64
 }
 
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Sensors {
     sensors : Vec<Sensor>
 }
 
-fn parse_pos(string : &str) -> Option<(i64, i64)> {
-    let (x_str, y_str) = string.split_once(", ")?;
-    let x_s = x_str.strip_prefix("x=")?;
-    let x = x_s.parse::<i64>().ok()?;
-    let y_s = y_str.strip_prefix("y=")?;
-    let y = y_s.parse::<i64>().ok()?;
-    Some((x,y))
+fn parse_pos(string : &str) -> Result<Point2> {
+    let (x_str, y_str) = string.split_once(", ").ok_or(
+        Error::line(string, "a position x=X, y=Y")
+    )?;
+    let x_s = x_str.strip_prefix("x=").ok_or(
+        Error::at(string, x_str, "x=")
+    )?;
+    let x = parse_num(string, x_s)?;
+    let y_s = y_str.strip_prefix("y=").ok_or(
+        Error::at(string, y_str, "y=")
+    )?;
+    let y = parse_num(string, y_s)?;
+    Ok(Point2::new(x, y))
 }
 
 impl Sensor {
-    fn from_line(line : &str) -> Option<Sensor> {
-        let (sensor_s, beacon_s) = line.split_once(":")?;
-        let sensor_p_s = sensor_s.strip_prefix("Sensor at ")?;
-        let sensor_p = parse_pos(sensor_p_s)?;
-        let beacon_p_s = beacon_s.strip_prefix(" closest beacon is at ")?;
-        let beacon_p = parse_pos(beacon_p_s)?;
-        let dist = (sensor_p.0 - beacon_p.0).abs() + (sensor_p.1 - beacon_p.1).abs();
-        Some(Sensor{
+    fn from_line(line : &str) -> Result<Sensor> {
+        let (sensor_s, beacon_s) = line.split_once(":").ok_or(
+            Error::line(line, "a sensor and a beacon separated by :")
+        )?;
+        let sensor_p_s = sensor_s.strip_prefix("Sensor at ").ok_or(
+            Error::at(line, sensor_s, "Sensor at")
+        )?;
+        let sensor_p = parse_pos(sensor_p_s).map_err(
+            |err| err.within(line, sensor_p_s)
+        )?;
+        let beacon_p_s = beacon_s.strip_prefix(" closest beacon is at ").ok_or(
+            Error::at(line, beacon_s, "closest beacon is at")
+        )?;
+        let beacon_p = parse_pos(beacon_p_s).map_err(
+            |err| err.within(line, beacon_p_s)
+        )?;
+        let dist = sensor_p.manhattan(beacon_p);
+        Ok(Sensor{
             pos : sensor_p,
+            beacon : beacon_p,
             dist : dist
         })
     }
 
     fn span_at_row(&self, row_num : i64) -> Option<(i64, i64)> {
-        let x_diff =  self.dist - (self.pos.1 - row_num).abs();
+        let x_diff =  self.dist - (self.pos.y - row_num).abs();
         if x_diff >= 0 {
-            Some((self.pos.0 - x_diff, self.pos.0 + x_diff))
+            Some((self.pos.x - x_diff, self.pos.x + x_diff))
         } else {
             None
         }
@@ -62,18 +90,17 @@ impl Sensor {
         self.contains_point(b.nearest(&self.pos))
     }
 
-    fn contains_point(&self, pt : (i64, i64)) ->bool {
-        let dist =  (pt.0 - self.pos.0).abs() + (pt.1 - self.pos.1).abs();
-        dist <= self.dist
+    fn contains_point(&self, pt : Point2) ->bool {
+        pt.manhattan(self.pos) <= self.dist
     }
 
 }
 
 
-fn find_beacon_in_box(box2 : &Box2, sensors : &[&Sensor]) -> Option<(i64,i64)> {
+fn find_beacon_in_box(box2 : &Box2, sensors : &[&Sensor]) -> Option<Point2> {
     if box2.unit() {
         if sensors.is_empty() {
-          return Some((box2.xmin,box2.ymin));
+          return Some(Point2::new(box2.xmin, box2.ymin));
         } else {
           return None;
         }
@@ -83,6 +110,7 @@ fn find_beacon_in_box(box2 : &Box2, sensors : &[&Sensor]) -> Option<(i64,i64)> {
             return None;
         }
     }
+    metrics::count("sensors.box_splits");
     for sub_box in box2.split() {
         let sub_sensors :Vec<&Sensor> = sensors.iter().filter(
             |sensor| sensor.overlap(&sub_box)
@@ -98,11 +126,12 @@ fn find_beacon_in_box(box2 : &Box2, sensors : &[&Sensor]) -> Option<(i64,i64)> {
 
 
 impl Sensors {
-    pub fn from_lines(lines : &Vec<String>) -> Sensors {
-        let sensors = lines.iter().filter_map(
-            |line| Sensor::from_line(line)
-        ).collect();
-        Sensors { sensors: sensors }
+    pub fn from_lines(lines : &Vec<String>) -> Result<Sensors> {
+        let sensors = parse_lines(
+            lines,
+            Sensor::from_line
+        )?;
+        Ok(Sensors { sensors: sensors })
     }
 
     pub fn no_becons_row(&self, row_num : i64) -> usize {
@@ -121,7 +150,7 @@ impl Sensors {
         range
     }
 
-    pub fn find_beacon(&self, xmin : i64, xmax : i64, ymin : i64, ymax : i64) -> Option<(i64,i64)> {
+    pub fn find_beacon(&self, xmin : i64, xmax : i64, ymin : i64, ymax : i64) -> Option<Point2> {
         let b = Box2{xmin : xmin, xmax : xmax, ymin: ymin, ymax : ymax};
         let sen_ref : Vec<&Sensor> = self.sensors.iter().collect();
         find_beacon_in_box(&b, &sen_ref)
@@ -135,14 +164,14 @@ fn overlap((a_min, a_max) : &(i64, i64), (b_min, b_max) : &(i64, i64)) -> bool {
 impl Ranges {
     fn merge(&mut self, new_span : (i64, i64)) {
         let (mut span_min, mut span_max) = new_span;
-        self.spans.drain_filter(
+        self.spans.retain(
             |span| {
                 if overlap(span, &new_span) {
                     span_min = min(span.0, span_min);
                     span_max = max(span.1, span_max);
-                    true
-                } else {
                     false
+                } else {
+                    true
                 }
             }
         );
@@ -179,23 +208,61 @@ impl Box2 {
         ]
     }
 
-    fn nearest(&self, pt : &(i64, i64)) -> (i64, i64) {
-        let x = max(self.xmin, min(self.xmax, pt.0));
-        let y = max(self.ymin, min(self.ymax, pt.1));
-        (x, y)
+    fn nearest(&self, pt : &Point2) -> Point2 {
+        let x = max(self.xmin, min(self.xmax, pt.x));
+        let y = max(self.ymin, min(self.ymax, pt.y));
+        Point2::new(x, y)
     }
 
-    fn furthest(&self, pt : &(i64, i64)) -> (i64, i64) {
-        let x = if (pt.0 - self.xmin).abs() < (pt.0 - self.xmax).abs() {
+    fn furthest(&self, pt : &Point2) -> Point2 {
+        let x = if (pt.x - self.xmin).abs() < (pt.x - self.xmax).abs() {
             self.xmax
         } else {
             self.xmin
         };
-        let y = if (pt.1 - self.ymin).abs() < (pt.1 - self.ymax).abs() {
+        let y = if (pt.y - self.ymin).abs() < (pt.y - self.ymax).abs() {
             self.ymax
         } else {
             self.ymin
         };
-        (x, y)
+        Point2::new(x, y)
     }
-}
\ No newline at end of file
+}
+
+impl fmt::Display for Sensors {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.sensors.iter().map(
+            |sensor| format!(
+                "Sensor at x={}, y={}: closest beacon is at x={}, y={}",
+                sensor.pos.x, sensor.pos.y, sensor.beacon.x, sensor.beacon.y
+            )
+        ).join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::{self, Input};
+
+    const EXAMPLE : &str = include_str!("../../data/examples/day-15.txt");
+
+    #[test]
+    fn row() {
+        let sensors = io::input_as_sensors(&Input::text(EXAMPLE)).unwrap();
+        assert_eq!(sensors.no_becons_row(10), 26);
+    }
+
+    #[test]
+    fn beacon() {
+        let sensors = io::input_as_sensors(&Input::text(EXAMPLE)).unwrap();
+        assert_eq!(sensors.find_beacon(0, 20, 0, 20), Some(Point2::new(14, 11)));
+    }
+
+    #[test]
+    fn round_trip() {
+        let sensors = io::input_as_sensors(&Input::text(EXAMPLE)).unwrap();
+        assert_eq!(sensors.to_string(), EXAMPLE.trim_end());
+        assert_eq!(io::input_as_sensors(&Input::text(&sensors.to_string())).unwrap(), sensors);
+    }
+}
@@ -8,4 +8,27 @@ pub fn start_of_packet(string : &String, len : usize) -> usize {
         }
     }
     0
-} 
\ No newline at end of file
+} 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE : &str = include_str!("../../data/examples/day-6.txt");
+
+    fn starts(len : usize) -> Vec<usize> {
+        EXAMPLE.lines().map(
+            |line| start_of_packet(&line.to_string(), len)
+        ).collect()
+    }
+
+    #[test]
+    fn packet_start() {
+        assert_eq!(starts(4), vec!(7, 5, 6, 10, 11));
+    }
+
+    #[test]
+    fn message_start() {
+        assert_eq!(starts(14), vec!(19, 23, 23, 29, 26));
+    }
+}
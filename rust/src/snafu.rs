@@ -0,0 +1,158 @@
+use std::fmt;
+use std::iter::Sum;
+use std::ops::Add;
+use std::str::FromStr;
+
+use crate::error::{Error, Result};
+
+// A number written in balanced base five, the digits are = - 0 1 2 for
+// minus two up to two
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Snafu(i128);
+
+fn digit(ch : char) -> Option<i128> {
+    match ch {
+        '=' => Some(-2),
+        '-' => Some(-1),
+        '0' => Some(0),
+        '1' => Some(1),
+        '2' => Some(2),
+        _ => None
+    }
+}
+
+impl Snafu {
+    pub fn value(&self) -> i128 {
+        self.0
+    }
+}
+
+impl FromStr for Snafu {
+    type Err = Error;
+
+    fn from_str(string : &str) -> Result<Snafu> {
+        if string.is_empty() {
+            return Err(Error::line(string, "a SNAFU number"));
+        }
+        let mut value : i128 = 0;
+        for (i, ch) in string.char_indices() {
+            let digit = digit(ch).ok_or_else(
+                || Error::at(string, &string[i..], "a SNAFU digit =, -, 0, 1 or 2")
+            )?;
+            value = value.checked_mul(5).and_then(|value| value.checked_add(digit)).ok_or_else(
+                || Error::line(string, "a SNAFU number that fits in 128 bits")
+            )?;
+        }
+        Ok(Snafu(value))
+    }
+}
+
+impl fmt::Display for Snafu {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.0 == 0 {
+            return write!(f, "0");
+        }
+        let mut digits = Vec::new();
+        let mut rest = self.0;
+        while rest != 0 {
+            // Digits 3 and 4 become -2 and -1 with one carried to the next
+            let (ch, carry) = match rest.rem_euclid(5) {
+                0 => ('0', 0),
+                1 => ('1', 0),
+                2 => ('2', 0),
+                3 => ('=', 1),
+                _ => ('-', 1)
+            };
+            digits.push(ch);
+            rest = rest.div_euclid(5) + carry;
+        }
+        write!(f, "{}", digits.iter().rev().collect::<String>())
+    }
+}
+
+impl From<i64> for Snafu {
+    fn from(num : i64) -> Snafu {
+        Snafu(num as i128)
+    }
+}
+
+impl From<i128> for Snafu {
+    fn from(num : i128) -> Snafu {
+        Snafu(num)
+    }
+}
+
+impl From<Snafu> for i128 {
+    fn from(snafu : Snafu) -> i128 {
+        snafu.0
+    }
+}
+
+impl TryFrom<Snafu> for i64 {
+    type Error = Error;
+
+    fn try_from(snafu : Snafu) -> Result<i64> {
+        i64::try_from(snafu.0).map_err(
+            |_| Error::invalid(&format!("SNAFU number {} does not fit in 64 bits", snafu))
+        )
+    }
+}
+
+impl Add for Snafu {
+    type Output = Snafu;
+
+    fn add(self, other : Snafu) -> Snafu {
+        Snafu(self.0 + other.0)
+    }
+}
+
+impl Sum for Snafu {
+    fn sum<I : Iterator<Item = Snafu>>(iter : I) -> Snafu {
+        iter.fold(Snafu::default(), |total, num| total + num)
+    }
+}
+
+impl<'a> Sum<&'a Snafu> for Snafu {
+    fn sum<I : Iterator<Item = &'a Snafu>>(iter : I) -> Snafu {
+        iter.copied().sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::{self, Input};
+
+    const EXAMPLE : &str = include_str!("../../data/examples/day-25.txt");
+
+    #[test]
+    fn sum() {
+        let numbers = io::input_as_snafus(&Input::text(EXAMPLE)).unwrap();
+        let total : Snafu = numbers.iter().sum();
+        assert_eq!(i128::from(total), 4890);
+        assert_eq!(total.to_string(), "2=-1=0");
+    }
+
+    #[test]
+    fn round_trip() {
+        let pairs = [
+            (0, "0"), (1, "1"), (3, "1="), (8, "2="), (10, "20"), (15, "1=0"),
+            (2022, "1=11-2"), (12345, "1-0---0"), (314159265, "1121-1110-1=0"), (-1, "-"), (-7, "-=")
+        ];
+        for (num, text) in pairs {
+            assert_eq!(Snafu::from(num as i64).to_string(), text);
+            assert_eq!(text.parse::<Snafu>().unwrap().value(), num);
+        }
+        assert_eq!(Snafu::from(i128::MAX).to_string().parse::<Snafu>().unwrap().value(), i128::MAX);
+    }
+
+    #[test]
+    fn errors() {
+        assert!("".parse::<Snafu>().is_err());
+        assert!("1=3".parse::<Snafu>().is_err());
+        assert!("2".repeat(60).parse::<Snafu>().is_err());
+        assert!(i64::try_from(Snafu::from(i128::MAX)).is_err());
+        assert_eq!(i64::try_from(Snafu::from(-42i64)).unwrap(), -42);
+    }
+}
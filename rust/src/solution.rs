@@ -0,0 +1,238 @@
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::error::{self, Error, Result};
+use crate::io::Input;
+use crate::metrics;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Part {
+    One,
+    Two
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Answer {
+    Num(i64),
+    Text(String)
+}
+
+// A single day of the puzzle, each part consumes a freshly parsed input
+pub trait Solution {
+    #[cfg(feature = "serde")]
+    type Input : serde::Serialize;
+    #[cfg(not(feature = "serde"))]
+    type Input;
+    type PartOne : Into<Answer>;
+    type PartTwo : Into<Answer>;
+
+    fn day(&self) -> u8;
+
+    fn parse(&self, input : &Input) -> Result<Self::Input>;
+
+    // A part fails when the input parses but has no answer
+    fn part_one(&self, input : Self::Input) -> Result<Self::PartOne>;
+
+    // Days without a second part keep the default and say so in has_part_two
+    fn part_two(&self, _input : Self::Input) -> Result<Option<Self::PartTwo>> {
+        Ok(None)
+    }
+
+    fn has_part_two(&self) -> bool {
+        true
+    }
+}
+
+// Time spent parsing the input and solving a part
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Timing {
+    pub parse : Duration,
+    pub solve : Duration
+}
+
+// Type erased view of a solution so the days can be kept in one registry
+pub trait Puzzle : Sync {
+    fn day(&self) -> u8;
+
+    fn parts(&self) -> Vec<Part>;
+
+    fn run_timed(&self, part : Part, input : &Input) -> Result<(Answer, Timing)>;
+
+    fn run(&self, part : Part, input : &Input) -> Result<Answer> {
+        self.run_timed(part, input).map(|(answer, _)| answer)
+    }
+
+    // The parsed input as JSON
+    #[cfg(feature = "serde")]
+    fn dump(&self, input : &Input) -> Result<serde_json::Value>;
+}
+
+#[derive(Default)]
+pub struct Registry {
+    puzzles : Vec<Box<dyn Puzzle>>
+}
+
+impl Part {
+    pub fn all() -> [Part; 2] {
+        [Part::One, Part::Two]
+    }
+
+    pub fn num(&self) -> u8 {
+        match self {
+            Part::One => 1,
+            Part::Two => 2
+        }
+    }
+
+    pub fn from_num(num : u8) -> Option<Part> {
+        match num {
+            1 => Some(Part::One),
+            2 => Some(Part::Two),
+            _ => None
+        }
+    }
+}
+
+impl fmt::Display for Part {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.num())
+    }
+}
+
+impl fmt::Display for Timing {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "parse {:.2?}, solve {:.2?}", self.parse, self.solve)
+    }
+}
+
+impl From<usize> for Answer {
+    fn from(num : usize) -> Answer {
+        Answer::Num(num as i64)
+    }
+}
+
+impl From<i64> for Answer {
+    fn from(num : i64) -> Answer {
+        Answer::Num(num)
+    }
+}
+
+impl From<String> for Answer {
+    fn from(text : String) -> Answer {
+        Answer::Text(text)
+    }
+}
+
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Answer::Num(num) => write!(f, "{}", num),
+            Answer::Text(text) => write!(f, "{}", text)
+        }
+    }
+}
+
+// Warn about the lines skipped by the parse just done, unless another part
+// already did for the same input
+fn report_skipped(day : u8, input : &Input) {
+    let skipped = error::take_skipped();
+    if input.first_report() {
+        for err in skipped {
+            log::warn!("Skipping {}", err.on_day(day));
+        }
+    }
+}
+
+impl<S> Puzzle for S
+  where S : Solution + Sync
+{
+    fn day(&self) -> u8 {
+        Solution::day(self)
+    }
+
+    fn parts(&self) -> Vec<Part> {
+        if self.has_part_two() {
+            vec!(Part::One, Part::Two)
+        } else {
+            vec!(Part::One)
+        }
+    }
+
+    fn run_timed(&self, part : Part, input : &Input) -> Result<(Answer, Timing)> {
+        let day = Solution::day(self);
+        if part == Part::Two && !self.has_part_two() {
+            return Err(Error::Unsolved { day : day, part : part.num() });
+        }
+        // Counts left from anything run before on this thread are dropped
+        metrics::take();
+        // Read the input first so file access is not counted as parsing
+        input.contents().map_err(
+            |err| err.on_day(day)
+        )?;
+        error::take_skipped();
+        let start = Instant::now();
+        let data = self.parse(input);
+        let parsed = Instant::now();
+        report_skipped(day, input);
+        let data = data.map_err(
+            |err| err.on_day(day)
+        )?;
+        log::info!("Day {} part {}: parsed input in {:.2?}", day, part, parsed - start);
+        let answer = match part {
+            Part::One => self.part_one(data).map_err(
+                |err| err.on_day(day)
+            )?.into(),
+            Part::Two => self.part_two(data).map_err(
+                |err| err.on_day(day)
+            )?.ok_or(
+                Error::Unsolved { day : day, part : part.num() }
+            )?.into()
+        };
+        let timing = Timing { parse : parsed - start, solve : parsed.elapsed() };
+        log::info!("Day {} part {}: solved in {:.2?}", day, part, timing.solve);
+        Ok((answer, timing))
+    }
+
+    #[cfg(feature = "serde")]
+    fn dump(&self, input : &Input) -> Result<serde_json::Value> {
+        let day = Solution::day(self);
+        error::take_skipped();
+        let data = self.parse(input);
+        report_skipped(day, input);
+        let data = data.map_err(
+            |err| err.on_day(day)
+        )?;
+        serde_json::to_value(&data).map_err(
+            |err| Error::invalid(&format!("the parsed input does not serialise: {}", err)).on_day(day)
+        )
+    }
+}
+
+impl Registry {
+    pub fn new() -> Registry {
+        Registry { puzzles : Vec::new() }
+    }
+
+    pub fn register<P>(&mut self, puzzle : P)
+      where P : Puzzle + 'static
+    {
+        self.puzzles.push(Box::new(puzzle));
+        self.puzzles.sort_by_key(|puzzle| puzzle.day());
+    }
+
+    pub fn get(&self, day : u8) -> Option<&dyn Puzzle> {
+        self.puzzles.iter().find(
+            |puzzle| puzzle.day() == day
+        ).map(|puzzle| puzzle.as_ref())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &dyn Puzzle> {
+        self.puzzles.iter().map(|puzzle| puzzle.as_ref())
+    }
+
+    pub fn run(&self, day : u8, part : Part, input : &Input) -> Result<Answer> {
+        self.get(day).ok_or(
+            Error::Unsolved { day : day, part : part.num() }
+        )?.run(part, input)
+    }
+}
@@ -1,5 +1,13 @@
-use std::collections::{HashSet, HashMap};
+use std::collections::HashSet;
 use std::cmp::max;
+use std::fmt;
+
+use crate::cycle;
+use crate::error::{Error, Result};
+use crate::geometry::Point2;
+use crate::grid::Bounds;
+use crate::metrics;
+use crate::render::{Animate, Frame};
 
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
 enum Shape {
@@ -10,15 +18,43 @@ enum Shape {
     Square
 }
 
+// The order the rocks fall in
+const SHAPES : [Shape; 5] = [
+    Shape::Horizontal,
+    Shape::Cross,
+    Shape::Corner,
+    Shape::Vertical,
+    Shape::Square
+];
+
 pub struct Game {
     rocks : HashSet<(usize, usize)>
 }
 
+// Rows of the chamber shown when watching the rocks fall
+const VIEW : i64 = 40;
+
+// Rocks dropped one a step, watched from the top of the tower
+pub struct Tower {
+    game : Game,
+    jets : Jets,
+    dropped : usize,
+    count : usize
+}
+
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Jets {
     jets_r : Vec<bool>,
     index : usize
 }
 
+impl Default for Game {
+    fn default() -> Game {
+        Game::new()
+    }
+}
+
 impl Game {
     pub fn new() -> Game {
         Game { 
@@ -29,14 +65,7 @@ impl Game {
     }
 
     pub fn simulate(&mut self, count : usize, jets : &mut Jets) {
-        let shapes = [
-            Shape::Horizontal,
-            Shape::Cross,
-            Shape::Corner,
-            Shape::Vertical,
-            Shape::Square
-        ];
-        for shape in shapes.iter().cycle().take(count) {
+        for shape in SHAPES.iter().cycle().take(count) {
             self.simulate_shape(shape, jets);
         }
     }
@@ -46,10 +75,12 @@ impl Game {
         let mut x : usize = 2;
         let mut fall = false;
         let mut at_base = false;
+        let mut checks = 0;
         while !at_base {
+            checks += 1;
             if fall {
                 if !self.collide(shape, x, y-1) {
-                    y = y-1;
+                    y -= 1;
                 } else {
                     at_base = true;
                 }
@@ -76,52 +107,24 @@ impl Game {
             }
         }
         self.add_shape(shape, x, y);
-        //self.print();
+        metrics::count("tetris.shapes_dropped");
+        metrics::add("tetris.collision_checks", checks);
     }
 
+    // Height after a number of rocks too large to simulate. Once the next
+    // shape, the jet and the shape of the top of the tower repeat so does
+    // the growth in height
     pub fn simulate_long(&mut self, long_count : usize, jets : &mut Jets) -> usize {
-        // Simulate till we hit a cycle
-        let (cycle_start, cycle_period, heights_at) = 
-          self.simulate_cycle(jets);
-        assert!(long_count > cycle_period);
-        // Work out how many cycles and what remainder we need
-        let cycle_num = (long_count - cycle_start) / cycle_period;
-        let cycle_rem = (long_count - cycle_start) % cycle_period;
-        let delta_height = heights_at[cycle_start + cycle_period] - heights_at[cycle_start];
-        let cycle_hieght = (cycle_num-1) * delta_height;
-        heights_at[cycle_start + cycle_period + cycle_rem] + cycle_hieght
-    }
-
-    fn simulate_cycle(&mut self, jets : &mut Jets) -> (usize, usize, Vec<usize>) {
-        let shapes = [
-            Shape::Horizontal,
-            Shape::Cross,
-            Shape::Corner,
-            Shape::Vertical,
-            Shape::Square
-        ];
-        let mut heights = Vec::new();
-        let mut top_repeat : HashMap<(Shape, [usize; 7], usize), usize> = HashMap::new();
-        let mut goal = None;
-        let mut cycle_start = 0;
-        let mut cycle_period = 0;
-        for (i, shape) in shapes.iter().cycle().enumerate() {
-            heights.push(self.height());
-            if goal.contains(&i) {
-                break;
-            }
-            if goal.is_none() {
-                let profile = self.profile();
-                if let Some(prev) = top_repeat.get(&(*shape, profile, jets.index)) {
-                    cycle_start = *prev;
-                    cycle_period = i - *prev;
-                    goal = Some(i + cycle_period);
-                }
-                top_repeat.insert((*shape, profile, jets.index), i);
+        let cycle = cycle::detect(
+            (self, jets, 0),
+            |(game, jets, count)| (count % SHAPES.len(), game.profile(), jets.index),
+            |(game, _, _)| game.height(),
+            |(game, jets, count)| {
+                game.simulate_shape(&SHAPES[*count % SHAPES.len()], jets);
+                *count += 1;
             }
-            self.simulate_shape(&shape, jets);
-        };
-        (cycle_start, cycle_period, heights)
+        );
+        cycle.at(long_count)
     }
 
     pub fn height(&self) -> usize {
@@ -158,6 +161,39 @@ impl Game {
     }
 }
 
+impl Tower {
+    pub fn new(jets : Jets, count : usize) -> Tower {
+        Tower { game : Game::new(), jets : jets, dropped : 0, count : count }
+    }
+}
+
+impl Animate for Tower {
+    fn step(&mut self) -> bool {
+        if self.dropped == self.count {
+            return false;
+        }
+        self.game.simulate_shape(&SHAPES[self.dropped % SHAPES.len()], &mut self.jets);
+        self.dropped += 1;
+        true
+    }
+
+    // The walls are at -1 and 7 and the floor at height 0
+    fn frame(&self) -> Frame {
+        let top = max(self.game.height() as i64 + 3, VIEW - 1);
+        let bounds = Bounds::new(Point2::new(-1, 0), Point2::new(7, VIEW - 1));
+        Frame::draw(bounds, |pos| {
+            let y = top - pos.y;
+            match (pos.x, y) {
+                (-1 | 7, 0) => '+',
+                (-1 | 7, _) => '|',
+                (_, 0) => '-',
+                _ if self.game.rocks.contains(&(pos.x as usize, y as usize)) => '#',
+                _ => '.'
+            }
+        })
+    }
+}
+
 impl Jets {
     fn next_right(&mut self) -> bool {
         let res = self.jets_r[self.index];
@@ -169,11 +205,18 @@ impl Jets {
         res
     }
 
-    pub fn from_line(line : &str) -> Jets {
-        let jets = line.chars().map(
-            |ch| ch == '>'
-        ).collect();
-        Jets { jets_r : jets, index : 0 }
+    pub fn from_line(line : &str) -> Result<Jets> {
+        let jets : Vec<bool> = line.char_indices().map(
+            |(i, ch)| match ch {
+                '>' => Ok(true),
+                '<' => Ok(false),
+                _ => Err(Error::at(line, &line[i..], "a jet < or >"))
+            }
+        ).collect::<Result<Vec<bool>>>()?;
+        if jets.is_empty() {
+            return Err(Error::line(line, "at least one jet"));
+        }
+        Ok(Jets { jets_r : jets, index : 0 })
     } 
 }
 
@@ -199,3 +242,54 @@ impl Shape {
     }
 }
 
+impl fmt::Display for Jets {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.jets_r.iter().map(|right| if *right { '>' } else { '<' }).collect::<String>())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::{self, Input};
+    use crate::render;
+
+    const EXAMPLE : &str = include_str!("../../data/examples/day-17.txt");
+
+    #[test]
+    fn short_game() {
+        let mut jets = io::input_as_jets(&Input::text(EXAMPLE)).unwrap();
+        let mut game = Game::new();
+        game.simulate(2022, &mut jets);
+        assert_eq!(game.height(), 3068);
+    }
+
+    #[test]
+    fn long_game() {
+        let mut jets = io::input_as_jets(&Input::text(EXAMPLE)).unwrap();
+        let mut game = Game::new();
+        assert_eq!(game.simulate_long(1000000000000, &mut jets), 1514285714288);
+    }
+
+    #[test]
+    fn frames() {
+        let jets = io::input_as_jets(&Input::text(EXAMPLE)).unwrap();
+        let frames = render::frames(&mut Tower::new(jets, 2), 1, 100);
+        assert_eq!(frames.len(), 3);
+        let rows : Vec<String> = frames[2].to_string().lines().map(String::from).collect();
+        assert_eq!(rows.len(), 40);
+        assert_eq!(rows[35..], ["|...#...|", "|..###..|", "|...#...|", "|..####.|", "+-------+"]);
+    }
+
+    #[test]
+    fn bad_jet() {
+        assert!(Jets::from_line("<<>^").is_err());
+    }
+
+    #[test]
+    fn round_trip() {
+        let jets = io::input_as_jets(&Input::text(EXAMPLE)).unwrap();
+        assert_eq!(jets.to_string(), EXAMPLE.trim_end());
+        assert_eq!(io::input_as_jets(&Input::text(&jets.to_string())).unwrap(), jets);
+    }
+}
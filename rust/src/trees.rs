@@ -1,82 +1,64 @@
-use std::collections::{HashSet, HashMap};
+use std::collections::HashSet;
+use std::fmt;
 
+use crate::error::{Error, Result};
+use crate::geometry::Point2;
+use crate::grid::Grid;
+
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Forest {
-    hieghts : Vec<Vec<u32>>
+    hieghts : Grid<u32>
 }
 
 
 impl Forest {
-    pub fn from_lines(lines : &Vec<String>) -> Forest {
-        let hieghts : Vec<Vec<u32>> = lines.iter().map(
-            |line| line.chars().filter_map(
-                |ch| ch.to_digit(10)
-            ).collect()
-        ).collect();
-        Forest { hieghts: hieghts }
+    pub fn from_lines(lines : &Vec<String>) -> Result<Forest> {
+        let hieghts = Grid::parse(
+            lines,
+            |ch| ch.to_digit(10),
+            "a tree height digit"
+        )?;
+        if hieghts.is_empty() {
+            return Err(Error::invalid("no trees in the forest"));
+        }
+        Ok(Forest { hieghts: hieghts })
     }
 
     pub fn count_visible(&self) -> usize {
-        let width = self.hieghts[0].len();
-        let height = self.hieghts.len();
         let mut visible = HashSet::new();
-        for i in 0..height {
-            self.visible_along(
-                (0..width).map(|j| (i,j)),
-                &mut visible
-            );
-            self.visible_along(
-                (0..width).rev().map(|j| (i,j)),
-                &mut visible
-            );
+        for y in 0..self.hieghts.height() as i64 {
+            self.visible_along(self.hieghts.row(y), &mut visible);
+            self.visible_along(self.hieghts.row(y).rev(), &mut visible);
         }
-        for j in 0..width {
-            self.visible_along(
-                (0..height).map(|i| (i,j)),
-                &mut visible
-            );
-            self.visible_along(
-                (0..height).rev().map(|i| (i,j)),
-                &mut visible
-            );
+        for x in 0..self.hieghts.width() as i64 {
+            self.visible_along(self.hieghts.column(x), &mut visible);
+            self.visible_along(self.hieghts.column(x).rev(), &mut visible);
         }
         visible.len()
     }
 
     pub fn scenic_max(&self) -> usize {
-        let width = self.hieghts[0].len();
-        let height = self.hieghts.len();
-        let mut scores = HashMap::new();
-        for i in 0..height {
-            self.scenic_along(
-                (0..width).map(|j| (i,j)),
-                &mut scores
-            );
-            self.scenic_along(
-                (0..width).rev().map(|j| (i,j)),
-                &mut scores
-            );
+        let mut scores = Grid::new(self.hieghts.width(), self.hieghts.height(), 1);
+        for y in 0..self.hieghts.height() as i64 {
+            self.scenic_along(self.hieghts.row(y), &mut scores);
+            self.scenic_along(self.hieghts.row(y).rev(), &mut scores);
         }
-        for j in 0..width {
-            self.scenic_along(
-                (0..height).map(|i| (i,j)),
-                &mut scores
-            );
-            self.scenic_along(
-                (0..height).rev().map(|i| (i,j)),
-                &mut scores
-            );
+        for x in 0..self.hieghts.width() as i64 {
+            self.scenic_along(self.hieghts.column(x), &mut scores);
+            self.scenic_along(self.hieghts.column(x).rev(), &mut scores);
         }
-        *scores.values().max().unwrap()
+        *scores.iter().map(|(_, score)| score).max().unwrap()
     }
 
-    fn visible_along<I>(&self, mut iter : I, visible : &mut HashSet<(usize, usize)>)
-        where I : Iterator<Item = (usize, usize)>
+    fn visible_along<I>(&self, mut iter : I, visible : &mut HashSet<Point2>)
+        where I : Iterator<Item = Point2>
     {
         let first = iter.next().unwrap();
         visible.insert(first);
-        let mut best = self.get(&first);
+        let mut best = self.hieghts[first];
         for index in iter {
-            let val = self.get(&index);
+            let val = self.hieghts[index];
             if val > best {
                 best = val;
                 visible.insert(index);
@@ -84,37 +66,70 @@ impl Forest {
         }
     }
 
-    fn scenic_along<I>(&self, iter : I, scores : &mut HashMap<(usize, usize), usize>)
-        where I : Iterator<Item = (usize, usize)>
+    fn scenic_along<I>(&self, iter : I, scores : &mut Grid<usize>)
+        where I : Iterator<Item = Point2>
     {
         // Distance to the last maxima seen under that height
         let mut prev_dist: [Option<usize>; 11] = [None; 11];
         // Put a pretend 10 hieght tree before the start
         prev_dist[10] = Some(0);
         for index in iter {
-            let val = self.get(&index) as usize;
+            let val = self.hieghts[index] as usize;
             // Find the last tree higher than this one
             let dist = (val..11).filter_map(
                 |v| prev_dist[v]
             ).next().unwrap();
             // Update score
-            *scores.entry(index).or_insert(1) *= dist;
+            scores[index] *= dist;
             // Update distances - clear anything below val
-            for v in 0..val {
-                prev_dist[v] = None
+            for dist in prev_dist[..val].iter_mut() {
+                *dist = None
             }
             // Add marker for tree
             prev_dist[val] = Some(1);
             // Increment all greater than val
-            for v in val+1..11 {
-                if let Some(dist) = prev_dist[v].as_mut() {
-                    *dist += 1;
-                }
+            for dist in prev_dist[val+1..].iter_mut().flatten() {
+                *dist += 1;
             }
         }
     }
+}
+
+impl fmt::Display for Forest {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.hieghts.render(|height| char::from_digit(*height, 10).unwrap()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::{self, Input};
+
+    const EXAMPLE : &str = include_str!("../../data/examples/day-8.txt");
 
-    fn get(&self, index : &(usize, usize))  -> u32 {
-        self.hieghts[index.0][index.1]
+    #[test]
+    fn visible() {
+        let forest = io::input_as_forest(&Input::text(EXAMPLE)).unwrap();
+        assert_eq!(forest.count_visible(), 21);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn scenic() {
+        let forest = io::input_as_forest(&Input::text(EXAMPLE)).unwrap();
+        assert_eq!(forest.scenic_max(), 8);
+    }
+
+    #[test]
+    fn ragged_rows() {
+        let lines = vec!("303".to_string(), "25".to_string());
+        assert!(Forest::from_lines(&lines).is_err());
+    }
+
+    #[test]
+    fn round_trip() {
+        let forest = io::input_as_forest(&Input::text(EXAMPLE)).unwrap();
+        assert_eq!(forest.to_string(), EXAMPLE.trim_end());
+        assert_eq!(io::input_as_forest(&Input::text(&forest.to_string())).unwrap(), forest);
+    }
+}